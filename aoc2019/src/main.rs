@@ -0,0 +1,80 @@
+mod report;
+mod run;
+mod scaffold;
+mod watch;
+
+use std::{env, path::PathBuf, process};
+
+use report::write_report;
+use run::{default_input, run_and_report};
+use scaffold::new_day;
+use watch::watch_day;
+
+fn usage() {
+    println!("usage: aoc2019 <command> --day N [--input FILE]");
+    println!("       aoc2019 report --html DIR");
+    println!();
+    println!("commands:");
+    println!("    run      build, test and run a day once");
+    println!("    watch    re-run a day whenever its source or input changes");
+    println!("    new-day  create a dayNN crate wired to the shared crates");
+    println!("    report   run every day and write an HTML summary to DIR");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(command) = args.first() else {
+        usage();
+        return;
+    };
+
+    let mut day: Option<u32> = None;
+    let mut input: Option<PathBuf> = None;
+    let mut html: Option<PathBuf> = None;
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--day" => day = iter.next().and_then(|d| d.parse().ok()),
+            "--input" => input = iter.next().map(PathBuf::from),
+            "--html" => html = iter.next().map(PathBuf::from),
+            _ => {
+                println!("\x1b[31mUnknown argument: {arg}\x1b[m");
+                usage();
+                process::exit(2);
+            }
+        }
+    }
+
+    // The report covers every day, so it doesn't need --day
+    if command == "report" {
+        let Some(html) = html else {
+            println!("\x1b[31mreport needs --html DIR\x1b[m");
+            process::exit(2);
+        };
+        if !write_report(&html) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    let Some(day) = day else {
+        println!("\x1b[31m--day needs a day number\x1b[m");
+        process::exit(2);
+    };
+    let input = input.unwrap_or_else(|| default_input(day));
+
+    match command.as_str() {
+        "run" => {
+            if !run_and_report(day, &input) {
+                process::exit(1);
+            }
+        }
+        "watch" => watch_day(day, &input),
+        "new-day" => {
+            if !new_day(day) {
+                process::exit(1);
+            }
+        }
+        _ => usage(),
+    }
+}
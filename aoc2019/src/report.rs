@@ -0,0 +1,320 @@
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::run::{batch_args, build_day, default_input, run_day, workspace_root};
+
+const HISTORY_FILE: &str = "history.txt";
+// Runs shown in each sparkline
+const HISTORY_LEN: usize = 20;
+// A picture has to be at least this many rows to be worth embedding
+const MIN_PICTURE_ROWS: usize = 5;
+
+struct DayResult {
+    name: String,
+    status: &'static str,
+    elapsed_ns: Option<u128>,
+    instructions: Option<u64>,
+    answers: Vec<String>,
+    picture: Option<Vec<String>>,
+}
+
+// Every dayNN crate in the workspace, in order
+fn day_numbers() -> Vec<u32> {
+    let mut days: Vec<u32> = fs::read_dir(workspace_root())
+        .expect("Failed to read workspace")
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            let day = name.strip_prefix("day")?.parse().ok()?;
+            entry.path().join("Cargo.toml").exists().then_some(day)
+        })
+        .collect();
+    days.sort();
+    days
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip to the end of the escape sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn is_picture_row(line: &str) -> bool {
+    !line.trim().is_empty() && line.chars().all(|c| matches!(c, '#' | '.' | ' '))
+}
+
+// The tallest run of rows drawn with '#', like the hull registration from day 11
+fn find_picture(lines: &[String]) -> Option<Vec<String>> {
+    lines
+        .split(|line| !is_picture_row(line))
+        .filter(|rows| rows.len() >= MIN_PICTURE_ROWS && rows.iter().any(|row| row.contains('#')))
+        .max_by_key(|rows| rows.len())
+        .map(|rows| rows.to_vec())
+}
+
+fn solve_day(day: u32) -> DayResult {
+    let name = format!("day{day:02}");
+    let mut result = DayResult {
+        name: name.clone(),
+        status: "build failed",
+        elapsed_ns: None,
+        instructions: None,
+        answers: Vec::new(),
+        picture: None,
+    };
+    if !build_day(day) {
+        return result;
+    }
+    let input = default_input(day);
+    if !input.exists() {
+        result.status = "no input";
+        return result;
+    }
+    let Some(args) = batch_args(day) else {
+        result.status = "interactive";
+        return result;
+    };
+
+    let run = run_day(day, &input, args);
+    let lines: Vec<String> = run.stdout.lines().map(strip_ansi).collect();
+    result.status = if run.timed_out {
+        "timeout"
+    } else if run.success {
+        "pass"
+    } else {
+        "fail"
+    };
+    result.elapsed_ns = Some(run.elapsed.as_nanos());
+    result.instructions = run.instructions;
+    // Solvers finish by printing their answers, so the tail is what matters
+    result.answers = lines[lines.len().saturating_sub(2)..].to_vec();
+    result.picture = find_picture(&lines);
+    result
+}
+
+// Appends this run's timings to the history file and returns every run per day,
+// oldest first
+fn update_history(out_dir: &Path, results: &[DayResult]) -> Vec<(String, Vec<u128>)> {
+    let path = out_dir.join(HISTORY_FILE);
+    let mut text = fs::read_to_string(&path).unwrap_or_default();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    for result in results {
+        if let Some(ns) = result.elapsed_ns {
+            text.push_str(&format!("{now} {} {ns}\n", result.name));
+        }
+    }
+    fs::write(&path, &text).expect("Failed to write report history");
+
+    let mut history: Vec<(String, Vec<u128>)> = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [_, name, ns] = fields[..] else {
+            continue;
+        };
+        let Ok(ns) = ns.parse() else {
+            continue;
+        };
+        match history.iter_mut().find(|(day, _)| day == name) {
+            Some((_, runs)) => runs.push(ns),
+            None => history.push((name.to_string(), vec![ns])),
+        }
+    }
+    history
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn format_ns(ns: u128) -> String {
+    match ns {
+        0..1_000_000 => format!("{:.1} µs", ns as f64 / 1e3),
+        1_000_000..1_000_000_000 => format!("{:.1} ms", ns as f64 / 1e6),
+        _ => format!("{:.2} s", ns as f64 / 1e9),
+    }
+}
+
+fn sparkline(runs: &[u128]) -> String {
+    const WIDTH: usize = 120;
+    const HEIGHT: usize = 24;
+
+    let runs = &runs[runs.len().saturating_sub(HISTORY_LEN)..];
+    if runs.len() < 2 {
+        return String::new();
+    }
+    let max = *runs.iter().max().expect("History is not empty") as f64;
+    let step = WIDTH as f64 / (runs.len() - 1) as f64;
+    let points: Vec<String> = runs
+        .iter()
+        .enumerate()
+        .map(|(i, ns)| {
+            let y = HEIGHT as f64 - *ns as f64 / max * (HEIGHT - 2) as f64 - 1.0;
+            format!("{:.1},{:.1}", i as f64 * step, y)
+        })
+        .collect();
+    format!(
+        "<svg width=\"{WIDTH}\" height=\"{HEIGHT}\"><polyline points=\"{}\"/></svg>",
+        points.join(" ")
+    )
+}
+
+#[cfg(feature = "png")]
+fn picture_html(rows: &[String]) -> String {
+    const SCALE: usize = 4;
+
+    let n_cols = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let width = n_cols * SCALE;
+    let height = rows.len() * SCALE;
+    let mut pixels: Vec<u8> = Vec::with_capacity(width * height);
+    for row in rows {
+        let shades: Vec<u8> = (0..n_cols)
+            .map(|x| match row.as_bytes().get(x) {
+                Some(b'#') => 255,
+                _ => 0,
+            })
+            .collect();
+        for _ in 0..SCALE {
+            for shade in &shades {
+                pixels.extend(std::iter::repeat_n(*shade, SCALE));
+            }
+        }
+    }
+
+    let mut data: Vec<u8> = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().expect("Failed to write png header");
+    writer
+        .write_image_data(&pixels)
+        .expect("Failed to write png data");
+    writer.finish().expect("Failed to finish png");
+
+    format!("<img src=\"data:image/png;base64,{}\">", base64(&data))
+}
+
+#[cfg(feature = "png")]
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Without the png feature the picture goes in as text
+#[cfg(not(feature = "png"))]
+fn picture_html(rows: &[String]) -> String {
+    format!("<pre class=\"picture\">{}</pre>", escape(&rows.join("\n")))
+}
+
+const STYLE: &str = "\
+body { background: #0f0f23; color: #ccc; font-family: monospace; margin: 2em; }
+h1 { color: #ffff66; }
+table { border-collapse: collapse; }
+td, th { padding: 0.4em 1em; border-bottom: 1px solid #333; text-align: left; vertical-align: top; }
+.pass { color: #00cc00; }
+.fail, .timeout, .build.failed { color: #ff4444; }
+.no.input, .interactive { color: #999; }
+polyline { fill: none; stroke: #ffff66; stroke-width: 1.5; }
+pre.picture { line-height: 1; color: #6688ff; }
+img { image-rendering: pixelated; }
+";
+
+fn to_html(results: &[DayResult], history: &[(String, Vec<u128>)]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Advent of Code 2019</title>\n");
+    html.push_str(&format!("<style>\n{STYLE}</style>\n</head>\n<body>\n"));
+    html.push_str("<h1>Advent of Code 2019</h1>\n<table>\n");
+    html.push_str(
+        "<tr><th>day</th><th>status</th><th>time</th><th>instructions</th><th>history</th><th>answers</th></tr>\n",
+    );
+
+    for result in results {
+        let time = result.elapsed_ns.map(format_ns).unwrap_or_default();
+        let instructions = result
+            .instructions
+            .map(|count| count.to_string())
+            .unwrap_or_default();
+        let spark = history
+            .iter()
+            .find(|(name, _)| *name == result.name)
+            .map(|(_, runs)| sparkline(runs))
+            .unwrap_or_default();
+        let mut answers: Vec<String> = result.answers.iter().map(|a| escape(a)).collect();
+        if let Some(picture) = &result.picture {
+            answers.push(picture_html(picture));
+        }
+        html.push_str(&format!(
+            "<tr><td>{}</td><td class=\"{}\">{}</td><td>{time}</td><td>{instructions}</td><td>{spark}</td><td>{}</td></tr>\n",
+            result.name,
+            result.status,
+            result.status,
+            answers.join("<br>\n")
+        ));
+    }
+
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+// Builds and runs every day with an input, then writes a self-contained index.html to
+// `out_dir`. Timings are kept in a history file next to it for the sparklines.
+pub fn write_report(out_dir: &Path) -> bool {
+    fs::create_dir_all(out_dir).expect("Failed to create report directory");
+
+    let mut results: Vec<DayResult> = Vec::new();
+    for day in day_numbers() {
+        let result = solve_day(day);
+        let colour = match result.status {
+            "pass" => "\x1b[32m",
+            "no input" | "interactive" => "\x1b[33m",
+            _ => "\x1b[31m",
+        };
+        println!("{}  {colour}{}\x1b[m", result.name, result.status);
+        results.push(result);
+    }
+
+    let history = update_history(out_dir, &results);
+    let path = out_dir.join("index.html");
+    fs::write(&path, to_html(&results, &history)).expect("Failed to write report");
+    println!("\x1b[32;1mReport written to {}\x1b[m", path.display());
+
+    results
+        .iter()
+        .all(|result| !matches!(result.status, "fail" | "timeout"))
+}
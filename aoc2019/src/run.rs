@@ -0,0 +1,179 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread::{self, sleep},
+    time::{Duration, Instant},
+};
+
+// A solver still running after this long is killed, so one stuck day can't hang a run
+const RUN_TIMEOUT: Duration = Duration::from_secs(120);
+// Asks the solver to finish with the line below, see utils::runner::report_instructions
+const INSTRUCTIONS_VAR: &str = "AOC_REPORT_INSTRUCTIONS";
+const INSTRUCTIONS_PREFIX: &str = "instructions: ";
+
+pub struct RunResult {
+    pub success: bool,
+    pub timed_out: bool,
+    pub elapsed: Duration,
+    /// Everything the solver printed except its instruction count line
+    pub stdout: String,
+    /// Intcode instructions retired, for solvers that report them
+    pub instructions: Option<u64>,
+}
+
+pub fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("Runner crate has no parent directory")
+        .to_path_buf()
+}
+
+pub fn day_crate(day: u32) -> String {
+    format!("day{day:02}")
+}
+
+pub fn default_input(day: u32) -> PathBuf {
+    workspace_root().join(day_crate(day)).join("input.txt")
+}
+
+fn cargo(args: &[&str]) -> bool {
+    Command::new(env!("CARGO"))
+        .args(args)
+        .current_dir(workspace_root())
+        .stdout(Stdio::null())
+        .status()
+        .expect("Failed to launch cargo")
+        .success()
+}
+
+pub fn build_day(day: u32) -> bool {
+    cargo(&["build", "--release", "-q", "-p", &day_crate(day)])
+}
+
+// The example tests, if the day has any
+pub fn test_day(day: u32) -> bool {
+    cargo(&["test", "-q", "-p", &day_crate(day)])
+}
+
+// What to pass after the input so a day runs with nobody at the terminal, or `None`
+// for a day that can only be played by hand
+pub fn batch_args(day: u32) -> Option<&'static [&'static str]> {
+    match day {
+        // Without a system ID day 5 asks for one
+        5 => Some(&["5"]),
+        // The adventure game has no way to play itself
+        25 => None,
+        // Everything else, day 13 included, plays itself unless asked not to
+        _ => Some(&[]),
+    }
+}
+
+pub fn run_day(day: u32, input: &Path, args: &[&str]) -> RunResult {
+    let binary = workspace_root().join("target/release").join(day_crate(day));
+    let start = Instant::now();
+    let mut child = Command::new(binary)
+        .arg(input)
+        .args(args)
+        .env(INSTRUCTIONS_VAR, "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to launch solver");
+
+    // Drained on another thread so a chatty solver can't block on a full pipe
+    let mut pipe = child.stdout.take().expect("Solver stdout is piped");
+    let reader = thread::spawn(move || {
+        let mut bytes: Vec<u8> = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    });
+
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait().expect("Failed to wait on solver") {
+            break status;
+        }
+        if start.elapsed() > RUN_TIMEOUT {
+            let _ = child.kill();
+            timed_out = true;
+            break child.wait().expect("Failed to wait on solver");
+        }
+        sleep(Duration::from_millis(10));
+    };
+    let elapsed = start.elapsed();
+    let bytes = reader.join().expect("Solver output reader panicked");
+
+    let mut stdout = String::new();
+    let mut instructions: Option<u64> = None;
+    for line in String::from_utf8_lossy(&bytes).lines() {
+        let count = line
+            .strip_prefix(INSTRUCTIONS_PREFIX)
+            .and_then(|count| count.parse().ok());
+        if count.is_some() {
+            instructions = count;
+        } else {
+            stdout.push_str(line);
+            stdout.push('\n');
+        }
+    }
+
+    RunResult {
+        success: status.success() && !timed_out,
+        timed_out,
+        elapsed,
+        stdout,
+        instructions,
+    }
+}
+
+// Build, test and run one day, printing a one-line summary plus the answers
+pub fn run_and_report(day: u32, input: &Path) -> bool {
+    let name = day_crate(day);
+    if !build_day(day) {
+        println!("\x1b[31;1m{name}  BUILD FAILED\x1b[m");
+        return false;
+    }
+    let tests = if test_day(day) {
+        "\x1b[32mtests ok\x1b[m"
+    } else {
+        "\x1b[31mtests failed\x1b[m"
+    };
+    if !input.exists() {
+        println!(
+            "{name}  {tests}  \x1b[33mno input at {}\x1b[m",
+            input.display()
+        );
+        return false;
+    }
+    let Some(args) = batch_args(day) else {
+        println!(
+            "{name}  \x1b[33mINTERACTIVE\x1b[m  {tests}  play it with `cargo run --release -p {name} -- {}`",
+            input.display()
+        );
+        return true;
+    };
+
+    let result = run_day(day, input, args);
+    let status = if result.timed_out {
+        "\x1b[31;1mTIMEOUT\x1b[m"
+    } else if result.success {
+        "\x1b[32;1mPASS\x1b[m"
+    } else {
+        "\x1b[31;1mFAIL\x1b[m"
+    };
+    let instructions = result
+        .instructions
+        .map(|count| format!("  {count} instructions"))
+        .unwrap_or_default();
+    println!(
+        "{name}  {status}  {tests}  {:?}{instructions}",
+        result.elapsed
+    );
+    // Solvers finish by printing their answers, so the tail is what matters
+    let lines: Vec<&str> = result.stdout.lines().collect();
+    for line in &lines[lines.len().saturating_sub(2)..] {
+        println!("    {line}");
+    }
+    result.success
+}
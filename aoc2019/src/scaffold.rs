@@ -0,0 +1,118 @@
+use std::{fs, path::Path};
+
+use crate::run::{day_crate, workspace_root};
+
+const CARGO_TEMPLATE: &str = r#"[package]
+name = "{NAME}"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+grid = { workspace = true }
+intcode = { workspace = true }
+utils = { workspace = true }
+
+[features]
+default = []
+tui = ["intcode/tui"]
+"#;
+
+const MAIN_TEMPLATE: &str = r#"use std::env;
+
+use intcode::{Cpu, IntcodeError};
+use utils::error::AocError;
+use utils::parse::read_intcode;
+
+fn part1(program: &[i64]) -> Result<i64, IntcodeError> {
+    let mut cpu = Cpu::builder().program(program).build()?;
+    cpu.run()?;
+    cpu.read_output()
+}
+
+fn part2(_program: &[i64]) -> Result<i64, IntcodeError> {
+    Ok(0)
+}
+
+fn solve(program: &[i64]) -> Result<(), AocError> {
+    println!("part 1: {}", part1(program)?);
+    println!("part 2: {}", part2(program)?);
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), AocError> {
+    let path = args
+        .get(1)
+        .ok_or_else(|| AocError::Usage(String::from("no input provided!")))?;
+    let program = read_intcode(path)?;
+    solve(&program)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(err) = run(&args) {
+        err.exit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::parse::parse_intcode;
+
+    #[test]
+    fn part1_example() {
+        let program = parse_intcode("104,42,99").unwrap();
+        assert_eq!(part1(&program), Ok(42));
+    }
+}
+"#;
+
+// Inserts `name` into the workspace members list, keeping the day crates in order
+fn add_workspace_member(name: &str) {
+    let path = workspace_root().join("Cargo.toml");
+    let manifest = fs::read_to_string(&path).expect("Failed to read workspace manifest");
+    let entry = format!("    \"{name}\",");
+
+    let mut lines: Vec<String> = manifest.lines().map(String::from).collect();
+    let start = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with("members"))
+        .expect("No members list in workspace manifest");
+    let end = start
+        + lines[start..]
+            .iter()
+            .position(|line| line.trim() == "]")
+            .expect("Unterminated members list");
+    let at = lines[start + 1..end]
+        .iter()
+        .position(|line| line.trim().starts_with("\"day") && line.as_str() > entry.as_str())
+        .map_or(end, |i| start + 1 + i);
+    lines.insert(at, entry);
+
+    fs::write(&path, lines.join("\n") + "\n").expect("Failed to write workspace manifest");
+}
+
+pub fn new_day(day: u32) -> bool {
+    let name = day_crate(day);
+    let dir = workspace_root().join(&name);
+    if dir.exists() {
+        println!("\x1b[31;1m{name} already exists\x1b[m");
+        return false;
+    }
+
+    fs::create_dir_all(dir.join("src")).expect("Failed to create crate directory");
+    write_file(
+        &dir.join("Cargo.toml"),
+        &CARGO_TEMPLATE.replace("{NAME}", &name),
+    );
+    write_file(&dir.join("src/main.rs"), MAIN_TEMPLATE);
+    add_workspace_member(&name);
+
+    println!("\x1b[32;1mcreated {name}\x1b[m");
+    println!("put the puzzle input in {name}/input.txt and run `aoc2019 watch --day {day}`");
+    true
+}
+
+fn write_file(path: &Path, contents: &str) {
+    fs::write(path, contents).expect("Failed to write template file");
+}
@@ -0,0 +1,59 @@
+use std::path::Path;
+
+#[cfg(feature = "watch")]
+pub fn watch_day(day: u32, input: &Path) {
+    use std::{sync::mpsc, time::Duration};
+
+    use notify::{Event, RecursiveMode, Watcher};
+
+    use crate::run::{day_crate, run_and_report, workspace_root};
+
+    // Changes land as bursts of events (editors write several files), so wait for
+    // things to go quiet before re-running.
+    const DEBOUNCE: Duration = Duration::from_millis(200);
+
+    let root = workspace_root();
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).expect("Failed to create file watcher");
+    for dir in [day_crate(day).as_str(), "intcode", "grid", "utils"] {
+        let src = root.join(dir).join("src");
+        if src.exists() {
+            watcher
+                .watch(&src, RecursiveMode::Recursive)
+                .expect("Failed to watch source directory");
+        }
+    }
+    if input.exists() {
+        watcher
+            .watch(input, RecursiveMode::NonRecursive)
+            .expect("Failed to watch input file");
+    }
+
+    // Building reads the sources too, so only count events that change something
+    let is_change = |event: &notify::Result<Event>| {
+        event
+            .as_ref()
+            .is_ok_and(|e| e.kind.is_create() || e.kind.is_modify() || e.kind.is_remove())
+    };
+
+    loop {
+        print!("\x1b[2J\x1b[H");
+        run_and_report(day, input);
+        println!("\x1b[90mwatching for changes...\x1b[m");
+
+        loop {
+            let Ok(event) = rx.recv() else {
+                return;
+            };
+            if is_change(&event) {
+                break;
+            }
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
+
+#[cfg(not(feature = "watch"))]
+pub fn watch_day(_day: u32, _input: &Path) {
+    println!("\x1b[31;1mwatch needs the runner built with `--features watch`\x1b[m");
+}
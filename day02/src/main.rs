@@ -1,4 +1,7 @@
-use std::{env, fs};
+use std::env;
+
+use utils::error::AocError;
+use utils::parse::read_intcode;
 
 enum State {
     Cmd,
@@ -13,21 +16,6 @@ enum Ops {
     Halt,
 }
 
-fn get_input(filename: &str) -> String {
-    fs::read_to_string(filename).expect("Failed to open input.")
-}
-
-fn parse_ops(input: String) -> Vec<i64> {
-    let mut ops: Vec<i64> = Vec::new();
-
-    for num in input.trim().split(",") {
-        // println!("{num}");
-        ops.push(num.parse().expect("failed to parse number"));
-    }
-
-    ops
-}
-
 fn print_prog(ops: &[i64]) {
     for op in ops {
         print!("[{op}]");
@@ -35,8 +23,8 @@ fn print_prog(ops: &[i64]) {
     println!();
 }
 
-fn execute(program: &Vec<i64>, input1: i64, input2: i64) -> i64 {
-    let mut memory = program.clone();
+fn execute(program: &[i64], input1: i64, input2: i64) -> i64 {
+    let mut memory = program.to_vec();
     let mut state = State::Cmd;
     let mut cmd = Ops::Halt;
     let mut val1: i64 = 0;
@@ -79,7 +67,7 @@ fn execute(program: &Vec<i64>, input1: i64, input2: i64) -> i64 {
     memory[0]
 }
 
-fn find_inputs(program: &Vec<i64>) -> Option<(i64, i64)> {
+fn find_inputs(program: &[i64]) -> Option<(i64, i64)> {
     for x in 0..100 {
         for y in 0..100 {
             let answer = execute(program, x, y);
@@ -92,22 +80,25 @@ fn find_inputs(program: &Vec<i64>) -> Option<(i64, i64)> {
     None
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        println!("no input provided!");
-        return;
-    }
-
-    let input = get_input(&args[1]);
-
-    let program = parse_ops(input);
+fn run(args: &[String]) -> Result<(), AocError> {
+    let path = args
+        .get(1)
+        .ok_or_else(|| AocError::Usage(String::from("no input provided!")))?;
+    let program = read_intcode(path)?;
 
     print_prog(&program);
 
-    let inputs: (i64, i64) =
-        find_inputs(&program).expect("No valid inputs to produce desired output");
+    let inputs: (i64, i64) = find_inputs(&program)
+        .ok_or_else(|| AocError::solver("no inputs produce the desired output"))?;
 
     println!("inputs: {} {}", inputs.0, inputs.1);
     println!("answer: {}", 100 * inputs.0 + inputs.1);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(err) = run(&args) {
+        err.exit();
+    }
 }
@@ -1,218 +1,76 @@
 use std::{
-    env, fs,
+    env,
     io::{Write, stdin, stdout},
 };
 
-#[derive(PartialEq)]
-enum Op {
-    ADD,
-    MUL,
-    IN,
-    OUT,
-    JNZ,
-    JZ,
-    LT,
-    CMP,
-    HLT,
-}
-
-struct Cmd {
-    op: Op,
-    n_operands: usize,
-    writes: bool,
-}
-
-struct Cpu {
-    ip: usize,
-    reg: [i64; 8],
-    mode: [i64; 8],
-}
+use intcode::{Cpu, RunResult, run_diagnostic};
+use utils::error::AocError;
+use utils::parse::read_intcode;
+use utils::runner::report_instructions;
 
-fn get_input(filename: &str) -> String {
-    fs::read_to_string(filename).expect("Failed to open input.")
-}
-
-fn get_program(input: String) -> Vec<i64> {
-    let mut program: Vec<i64> = Vec::new();
+fn read_input() -> Result<i64, AocError> {
+    print!("\x1b[1;32mINPUT  <\x1b[m ");
+    stdout()
+        .flush()
+        .map_err(|err| AocError::io("failed to write prompt", err))?;
 
-    for num in input.trim().split(",") {
-        // println!("{num}");
-        program.push(num.parse().expect("failed to parse number"));
-    }
+    let mut input = String::new();
 
-    program
-}
+    stdin()
+        .read_line(&mut input)
+        .map_err(|err| AocError::io("failed to read input", err))?;
 
-fn print_prog(program: &[i64], ip: usize) {
-    for i in 0..program.len() {
-        if i == ip {
-            print!("\x1b[31m");
-        }
-        print!("[{}]\x1b[m", program[i]);
-    }
-    println!();
+    let input = input.trim();
+    input
+        .parse()
+        .map_err(|_| AocError::Usage(format!("{input:?} is not a number")))
 }
 
-fn get_cmd(instruction: i64) -> Option<Cmd> {
-    let opcode = instruction % 100;
-    match opcode {
-        1 => Some(Cmd {
-            op: Op::ADD,
-            n_operands: 3,
-            writes: true,
-        }),
-        2 => Some(Cmd {
-            op: Op::MUL,
-            n_operands: 3,
-            writes: true,
-        }),
-        3 => Some(Cmd {
-            op: Op::IN,
-            n_operands: 1,
-            writes: true,
-        }),
-        4 => Some(Cmd {
-            op: Op::OUT,
-            n_operands: 1,
-            writes: false,
-        }),
-        5 => Some(Cmd {
-            op: Op::JNZ,
-            n_operands: 2,
-            writes: false,
-        }),
-        6 => Some(Cmd {
-            op: Op::JZ,
-            n_operands: 2,
-            writes: false,
-        }),
-        7 => Some(Cmd {
-            op: Op::LT,
-            n_operands: 3,
-            writes: true,
-        }),
-        8 => Some(Cmd {
-            op: Op::CMP,
-            n_operands: 3,
-            writes: true,
-        }),
-        99 => Some(Cmd {
-            op: Op::HLT,
-            n_operands: 0,
-            writes: false,
-        }),
-        _ => None,
+fn execute_program(cpu: &mut Cpu, program: &[i64]) -> Result<i64, AocError> {
+    cpu.load_program(program)?;
+    while let RunResult::NeedsInput = cpu.run()? {
+        cpu.io_in.push_front(read_input()?);
     }
+    Ok(cpu.peek(0)?)
 }
 
-fn get_mode(mode: &mut [i64], instruction: i64, n_operands: usize) {
-    let mut digits = instruction / 100;
+// Returns the number of instructions the program ran
+fn solve(program: &[i64], system_id: Option<i64>) -> Result<u64, AocError> {
+    let mut cpu = Cpu::new();
 
-    for i in 0..n_operands {
-        mode[i] = digits % 10;
-        digits /= 10;
+    if let Some(system_id) = system_id {
+        let report = run_diagnostic(&mut cpu, program, system_id)?;
+        report.print();
+        return Ok(cpu.retired());
     }
-}
-
-fn read_input() -> i64 {
-    print!("\x1b[1;32mINPUT  <\x1b[m ");
-    stdout().flush().unwrap();
-
-    let mut input = String::new();
 
-    stdin().read_line(&mut input).expect("Failed to read line");
+    let output = execute_program(&mut cpu, program)?;
 
-    input.trim().parse().expect("Failed to read input number")
+    println!("output: {output}");
+    Ok(cpu.retired())
 }
 
-fn execute_cmd(cpu: &mut Cpu, memory: &mut [i64], cmd: Cmd) {
-    let boundary = if cmd.writes { 1 } else { 0 };
-    for i in 0..cmd.n_operands - boundary {
-        match cpu.mode[i] {
-            0 => cpu.reg[i] = memory[cpu.reg[i] as usize],
-            1 => (),
-            _ => (),
-        }
-    }
-
-    match cmd.op {
-        Op::ADD => memory[cpu.reg[2] as usize] = cpu.reg[0] + cpu.reg[1],
-        Op::MUL => memory[cpu.reg[2] as usize] = cpu.reg[0] * cpu.reg[1],
-        Op::IN => memory[cpu.reg[0] as usize] = read_input(),
-        Op::OUT => println!("\x1b[1;31mOUTPUT >\x1b[m {}", cpu.reg[0]),
-        Op::JNZ => {
-            if cpu.reg[0] != 0 {
-                cpu.ip = cpu.reg[1] as usize
-            }
-        }
-        Op::JZ => {
-            if cpu.reg[0] == 0 {
-                cpu.ip = cpu.reg[1] as usize
-            }
-        }
-        Op::LT => {
-            if cpu.reg[0] < cpu.reg[1] {
-                memory[cpu.reg[2] as usize] = 1;
-            } else {
-                memory[cpu.reg[2] as usize] = 0;
-            }
-        }
-        Op::CMP => {
-            if cpu.reg[0] == cpu.reg[1] {
-                memory[cpu.reg[2] as usize] = 1;
-            } else {
-                memory[cpu.reg[2] as usize] = 0;
-            }
-        }
-        Op::HLT => (),
-    }
-}
+fn run(args: &[String]) -> Result<u64, AocError> {
+    let path = args
+        .get(1)
+        .ok_or_else(|| AocError::Usage(String::from("no input provided!")))?;
+    let program = read_intcode(path)?;
+    // print_prog(&program, 0);
 
-fn execute_program(program: &Vec<i64>, noun: i64, verb: i64) -> i64 {
-    let mut cpu = Cpu {
-        ip: 0,
-        reg: [0; 8],
-        mode: [0; 8],
+    let system_id: Option<i64> = match args.get(2) {
+        Some(id) => Some(
+            id.parse()
+                .map_err(|_| AocError::Usage(format!("invalid system ID {id:?}")))?,
+        ),
+        None => None,
     };
-    let mut memory = program.clone();
-
-    // memory[1] = noun;
-    // memory[2] = verb;
-    loop {
-        // print_prog(&memory, cpu.ip);
-        let instruction = memory[cpu.ip];
-        let cmd: Cmd = get_cmd(memory[cpu.ip]).expect("Invalid opcode encountered!");
-        get_mode(&mut cpu.mode, instruction, cmd.n_operands);
-
-        if cmd.op == Op::HLT {
-            break;
-        }
-
-        cpu.ip += 1;
-        for i in 0..cmd.n_operands {
-            cpu.reg[i] = memory[cpu.ip];
-            cpu.ip += 1;
-            // println!("{}", cpu.reg[i]);
-        }
-
-        execute_cmd(&mut cpu, &mut memory, cmd);
-    }
-    memory[0]
+    solve(&program, system_id)
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        println!("no input provided!");
-        return;
+    match run(&args) {
+        Ok(instructions) => report_instructions(instructions),
+        Err(err) => err.exit(),
     }
-
-    let input = get_input(&args[1]);
-
-    let program = get_program(input);
-    // print_prog(&program, 0);
-
-    let output = execute_program(&program, 12, 2);
-
-    println!("output: {output}");
 }
@@ -1,461 +1,271 @@
-use std::{
-    collections::VecDeque,
-    env, fs,
-    io::{Write, stdin, stdout},
-    process::Output,
-};
-
-#[derive(PartialEq)]
-enum Op {
-    Add,
-    Mul,
-    In,
-    Out,
-    Jnz,
-    Jz,
-    Lt,
-    Cmp,
-    AdjBp,
-    Hlt,
-}
-
-#[derive(Default)]
-enum CpuMode {
-    #[default]
-    Normal,
-    BreakOnOutput,
-}
-
-#[derive(Copy, Clone)]
-enum RegMode {
-    Pos,
-    Imm,
-    Rel,
-}
-
-#[derive(Default)]
-enum State {
-    Active,
-    Ready,
-    #[default]
-    Halted,
-}
-
-struct Cmd {
-    op: Op,
-    n_operands: usize,
-    writes: bool,
+use std::{env, ops::Range, panic, sync::mpsc, thread};
+
+use intcode::{ChannelInput, ChannelOutput, Cpu, IntcodeError, Verbosity, Yield, par_map};
+use utils::combinatorics::{combinations, permutations};
+use utils::error::AocError;
+use utils::parse::read_intcode;
+use utils::runner::report_instructions;
+
+// The puzzle chains 5 amps with phases 0..5, then 5..10 in a feedback loop. Any of
+// these can be changed from the command line, e.g. `amps=3 phases=0..8 feedback=4..12`.
+// `threads` tries the chains of amps in parallel, and runs each feedback amp on its own
+// thread.
+struct AmpConfig {
+    amps: usize,
+    phases: Range<i64>,
+    feedback_phases: Range<i64>,
+    threads: bool,
 }
 
-struct Cpu {
-    ip: usize,
-    bp: i64,
-    reg: [i64; 8],
-    reg_mode: [RegMode; 8],
-    memory: Vec<i64>,
-    io_in: VecDeque<i64>,
-    io_out: VecDeque<i64>,
-    mode: CpuMode,
-    state: State,
-}
-
-impl Cpu {
-    fn new() -> Self {
-        let mut new = Self {
-            ip: 0,
-            bp: 0,
-            reg: [0; 8],
-            reg_mode: [RegMode::Pos; 8],
-            memory: Vec::new(),
-            io_in: VecDeque::new(),
-            io_out: VecDeque::new(),
-            mode: CpuMode::Normal,
-            state: State::Halted,
-        };
-        new.memory.resize(1_000_000, 0);
-        new
-    }
-}
-
-impl Default for Cpu {
+impl Default for AmpConfig {
     fn default() -> Self {
-        Self::new()
-    }
-}
-
-fn get_input(filename: &str) -> String {
-    fs::read_to_string(filename).expect("Failed to open input.")
-}
-
-fn get_program(input: String) -> Vec<i64> {
-    let mut program: Vec<i64> = Vec::new();
-
-    for num in input.trim().split(",") {
-        // println!("{num}");
-        program.push(num.parse().expect("failed to parse number"));
-    }
-
-    program
-}
-
-fn print_prog(program: &[i64], ip: usize) {
-    for i in 0..program.len() {
-        if i == ip {
-            print!("\x1b[31m");
+        Self {
+            amps: 5,
+            phases: 0..5,
+            feedback_phases: 5..10,
+            threads: false,
         }
-        print!("[{}]\x1b[m", program[i]);
-    }
-    println!();
-}
-
-fn get_cmd(instruction: i64) -> Option<Cmd> {
-    let opcode = instruction % 100;
-    match opcode {
-        1 => Some(Cmd {
-            op: Op::Add,
-            n_operands: 3,
-            writes: true,
-        }),
-        2 => Some(Cmd {
-            op: Op::Mul,
-            n_operands: 3,
-            writes: true,
-        }),
-        3 => Some(Cmd {
-            op: Op::In,
-            n_operands: 1,
-            writes: true,
-        }),
-        4 => Some(Cmd {
-            op: Op::Out,
-            n_operands: 1,
-            writes: false,
-        }),
-        5 => Some(Cmd {
-            op: Op::Jnz,
-            n_operands: 2,
-            writes: false,
-        }),
-        6 => Some(Cmd {
-            op: Op::Jz,
-            n_operands: 2,
-            writes: false,
-        }),
-        7 => Some(Cmd {
-            op: Op::Lt,
-            n_operands: 3,
-            writes: true,
-        }),
-        8 => Some(Cmd {
-            op: Op::Cmp,
-            n_operands: 3,
-            writes: true,
-        }),
-        9 => Some(Cmd {
-            op: Op::AdjBp,
-            n_operands: 1,
-            writes: false,
-        }),
-        99 => Some(Cmd {
-            op: Op::Hlt,
-            n_operands: 0,
-            writes: false,
-        }),
-        _ => None,
-    }
-}
-
-fn get_mode(mode: &mut [RegMode], instruction: i64, n_operands: usize) {
-    let mut digits = instruction / 100;
-
-    for i in 0..n_operands {
-        mode[i] = match digits % 10 {
-            0 => RegMode::Pos,
-            1 => RegMode::Imm,
-            2 => RegMode::Rel,
-            _ => panic!("Register mode not implemented!"),
-        };
-        digits /= 10;
     }
 }
 
-fn read_input() -> i64 {
-    print!("\x1b[1;32mINPUT  <\x1b[m ");
-    stdout().flush().unwrap();
-
-    let mut input = String::new();
-
-    stdin().read_line(&mut input).expect("Failed to read line");
-
-    input.trim().parse().expect("Failed to read input number")
-}
-
-fn execute_cmd(cpu: &mut Cpu, cmd: Cmd) {
-    let boundary = if cmd.writes { 1 } else { 0 };
-    for i in 0..cmd.n_operands - boundary {
-        match cpu.reg_mode[i] {
-            RegMode::Pos => cpu.reg[i] = cpu.memory[cpu.reg[i] as usize],
-            RegMode::Imm => (),
-            RegMode::Rel => cpu.reg[i] = cpu.memory[(cpu.bp + cpu.reg[i]) as usize],
-        }
-    }
-
-    match cmd.op {
-        Op::Add => cpu.memory[cpu.reg[2] as usize] = cpu.reg[0] + cpu.reg[1],
-        Op::Mul => cpu.memory[cpu.reg[2] as usize] = cpu.reg[0] * cpu.reg[1],
-        Op::In => {
-            let input = cpu.io_in.pop_back().expect("No io available to read!");
-            cpu.memory[cpu.reg[0] as usize] = input;
-            println!("\x1b[1;32mINPUT  <\x1b[m {}", input);
-        }
-        Op::Out => {
-            println!("\x1b[1;31mOUTPUT >\x1b[m {}", cpu.reg[0]);
-            cpu.io_out.push_front(cpu.reg[0]);
-            if let CpuMode::BreakOnOutput = cpu.mode {
-                cpu.state = State::Ready;
-            }
-        }
-        Op::Jnz => {
-            if cpu.reg[0] != 0 {
-                cpu.ip = cpu.reg[1] as usize
-            }
-        }
-        Op::Jz => {
-            if cpu.reg[0] == 0 {
-                cpu.ip = cpu.reg[1] as usize
+impl AmpConfig {
+    fn from_args(args: &[String]) -> Result<Self, AocError> {
+        let mut config = Self::default();
+        for arg in args {
+            if arg == "threads" {
+                config.threads = true;
+                continue;
             }
-        }
-        Op::Lt => {
-            if cpu.reg[0] < cpu.reg[1] {
-                cpu.memory[cpu.reg[2] as usize] = 1;
-            } else {
-                cpu.memory[cpu.reg[2] as usize] = 0;
+            let usage = || AocError::Usage(format!("invalid option {arg}"));
+            let (key, value) = arg.split_once('=').ok_or_else(usage)?;
+            match key {
+                "amps" => config.amps = value.parse().map_err(|_| usage())?,
+                "phases" => config.phases = parse_range(value).ok_or_else(usage)?,
+                "feedback" => config.feedback_phases = parse_range(value).ok_or_else(usage)?,
+                _ => return Err(usage()),
             }
         }
-        Op::Cmp => {
-            if cpu.reg[0] == cpu.reg[1] {
-                cpu.memory[cpu.reg[2] as usize] = 1;
-            } else {
-                cpu.memory[cpu.reg[2] as usize] = 0;
+
+        // Each amp needs a phase of its own
+        for range in [&config.phases, &config.feedback_phases] {
+            if config.amps == 0 || (range.end - range.start) < config.amps as i64 {
+                return Err(AocError::Usage(format!(
+                    "can't give {} amps distinct phases from {range:?}",
+                    config.amps
+                )));
             }
         }
-        Op::AdjBp => cpu.bp += cpu.reg[0],
-        Op::Hlt => cpu.state = State::Halted,
+        Ok(config)
     }
 }
 
-fn load_program(cpu: &mut Cpu, program: &[i64]) {
-    cpu.ip = 0;
-    cpu.io_in.clear();
-    cpu.io_out.clear();
-    cpu.state = State::Ready;
-    cpu.memory.fill(0);
-    cpu.memory[0..program.len()].copy_from_slice(program);
+fn parse_range(text: &str) -> Option<Range<i64>> {
+    let (start, end) = text.split_once("..")?;
+    Some(start.parse().ok()?..end.parse().ok()?)
 }
 
-fn run_cpu(cpu: &mut Cpu) {
-    cpu.state = State::Active;
-    loop {
-        // print_prog(&memory, cpu.ip);
-        let instruction = cpu.memory[cpu.ip];
-        let cmd: Cmd = get_cmd(cpu.memory[cpu.ip]).expect("Invalid opcode encountered!");
-        get_mode(&mut cpu.reg_mode, instruction, cmd.n_operands);
-
-        for i in 0..cmd.n_operands {
-            cpu.reg[i] = cpu.memory[cpu.ip + i + 1];
-            // println!("{}", cpu.reg[i]);
-        }
-
-        cpu.ip += cmd.n_operands + 1;
-        execute_cmd(cpu, cmd);
-
-        let State::Active = cpu.state else {
-            break;
-        };
-    }
+// Every ordering of every choice of `amps` distinct phases from `range`
+fn phase_settings(amps: usize, range: Range<i64>) -> impl Iterator<Item = Vec<i64>> {
+    let phases: Vec<i64> = range.collect();
+    combinations(&phases, amps).flat_map(|chosen| permutations(&chosen))
 }
 
-fn execute_program(cpu: &mut Cpu, program: &[i64]) {
-    load_program(cpu, program);
-    run_cpu(cpu);
+// Returns the last amp's output and the instructions the amps ran between them
+fn run_chain(
+    program: &[i64],
+    phases: &[i64],
+    verbosity: Verbosity,
+) -> Result<(i64, u64), IntcodeError> {
+    let mut output = 0;
+    let mut instructions = 0;
+    for phase in phases {
+        let mut amp = Cpu::builder()
+            .program(program)
+            .verbosity(verbosity)
+            .input(&[*phase, output])
+            .build()?;
+        amp.run()?;
+        output = amp.read_output()?;
+        instructions += amp.retired();
+    }
+    Ok((output, instructions))
 }
 
-fn get_max_output(program: &[i64]) -> i64 {
+fn get_max_output(program: &[i64], config: &AmpConfig) -> Result<(i64, u64), IntcodeError> {
     let mut max_output = i64::MIN;
-    let mut phases: [i64; 5] = [-1; 5];
-    let mut max_phases: [i64; 5] = [0; 5];
-
-    let mut amps: [Cpu; 5] = std::array::from_fn(|_| Cpu::new());
+    let mut max_phases: &[i64] = &[];
 
     println!("-----------------------");
-    for phase_a in 0..5 {
-        phases[0] = phase_a;
-        for phase_b in 0..5 {
-            if phases.contains(&phase_b) {
-                continue;
-            }
-            phases[1] = phase_b;
-            for phase_c in 0..5 {
-                if phases.contains(&phase_c) {
-                    continue;
-                }
-                phases[2] = phase_c;
-                for phase_d in 0..5 {
-                    if phases.contains(&phase_d) {
-                        continue;
-                    }
-                    phases[3] = phase_d;
-                    for phase_e in 0..5 {
-                        if phases.contains(&phase_e) {
-                            continue;
-                        }
-                        phases[4] = phase_e;
-
-                        println!("\x1b[35m{:?}\x1b[m", phases);
-                        load_program(&mut amps[0], program);
-                        amps[0].io_in.push_front(phases[0]);
-                        amps[0].io_in.push_front(0);
-                        run_cpu(&mut amps[0]);
-                        for i in 1..phases.len() {
-                            load_program(&mut amps[i], program);
-                            amps[i].io_in.push_front(phases[i]);
-                            amps[i].io_in.push_front(
-                                amps[i - 1].io_out.pop_back().expect("No io out from cpu"),
-                            );
-                            run_cpu(&mut amps[i]);
-                        }
-
-                        let output = amps[4]
-                            .io_out
-                            .pop_back()
-                            .expect("No final output from program.");
-                        if output > max_output {
-                            max_output = output;
-                            max_phases = phases;
-                        }
-                    }
-                    phases[4] = -1;
-                }
-                phases[3] = -1;
-            }
-            phases[2] = -1;
+    let settings: Vec<Vec<i64>> = phase_settings(config.amps, config.phases.clone()).collect();
+    let outputs = if config.threads {
+        par_map(&settings, |phases| {
+            run_chain(program, phases, Verbosity::Silent)
+        })?
+    } else {
+        let mut outputs = Vec::with_capacity(settings.len());
+        for phases in &settings {
+            println!("\x1b[35m{:?}\x1b[m", phases);
+            outputs.push(run_chain(program, phases, Verbosity::Io)?);
+        }
+        outputs
+    };
+
+    let mut instructions = 0;
+    for ((output, retired), phases) in outputs.into_iter().zip(&settings) {
+        instructions += retired;
+        if output > max_output {
+            max_output = output;
+            max_phases = phases;
         }
-        phases[1] = -1;
     }
 
     println!("\x1b[34m{:?}\x1b[m", max_phases);
-    max_output
+    Ok((max_output, instructions))
 }
 
-fn run_feedback_loop(amps: &mut [Cpu], output: &mut i64) {
-    amps[4].io_out.push_front(0);
-    while let State::Ready = amps[4].state {
-        println!("\x1b[34m### Amp A ###\x1b[m");
-
-        let Some(input) = amps[4].io_out.pop_back() else {
-            println!("\x1b[1;31mNo input available: exiting loop...");
-            return;
-        };
-        amps[0].io_in.push_front(input);
-        run_cpu(&mut amps[0]);
-
-        for i in 1..amps.len() {
-            println!(
-                "\x1b[34m### Amp {} ###\x1b[m",
-                ('A' as u8 + i as u8) as char
-            );
+fn run_feedback_loop(amps: &mut [Cpu], output: &mut i64) -> Result<(), IntcodeError> {
+    let mut signal = Some(0);
+    loop {
+        for (i, amp) in amps.iter_mut().enumerate() {
+            println!("\x1b[34m### Amp {} ###\x1b[m", amp_name(i));
 
-            let Some(input) = amps[i - 1].io_out.pop_back() else {
+            let Some(input) = signal else {
                 println!("\x1b[1;31mNo input available: exiting loop...");
-                return;
+                return Ok(());
+            };
+            signal = match amp.resume(Some(input))? {
+                Yield::Output(value) => Some(value),
+                Yield::NeedsInput | Yield::Done => None,
             };
-            amps[i].io_in.push_front(input);
-            run_cpu(&mut amps[i]);
         }
-        *output = *amps[4]
-            .io_out
-            .back()
-            .expect("No final output from program.");
+        if let Some(signal) = signal {
+            *output = signal;
+        }
+    }
+}
+
+// A to Z, then numbered past that
+fn amp_name(i: usize) -> String {
+    match u8::try_from(i) {
+        Ok(i) if i < 26 => ((b'A' + i) as char).to_string(),
+        _ => format!("{}", i + 1),
+    }
+}
+
+// Each amp reads from a channel written by the one before it. The wire from the last
+// amp back to the first runs through here, to keep the final signal.
+fn run_feedback_threads(program: &[i64], phases: &[i64]) -> Result<(i64, u64), IntcodeError> {
+    let mut senders = Vec::with_capacity(phases.len());
+    let mut receivers = Vec::with_capacity(phases.len());
+    for phase in phases {
+        let (tx, rx) = mpsc::channel();
+        tx.send(*phase).expect("Receiver is still here");
+        senders.push(tx);
+        receivers.push(rx);
     }
+    let first = senders[0].clone();
+    let (last, signals) = mpsc::channel();
+    let outputs = senders.into_iter().skip(1).chain([last]);
+
+    thread::scope(|scope| {
+        let amps: Vec<_> = receivers
+            .into_iter()
+            .zip(outputs)
+            .map(|(rx, tx)| {
+                scope.spawn(move || {
+                    let mut amp = Cpu::builder()
+                        .program(program)
+                        .verbosity(Verbosity::Silent)
+                        .input_source(ChannelInput::new(rx))
+                        .output_sink(ChannelOutput::new(tx))
+                        .build()?;
+                    amp.run()?;
+                    Ok((amp.ip, amp.retired()))
+                })
+            })
+            .collect();
+
+        // Amp A stops listening once it halts, so the last signal goes nowhere
+        let _ = first.send(0);
+        let mut output = None;
+        for signal in signals {
+            output = Some(signal);
+            let _ = first.send(signal);
+        }
+
+        let mut ip = 0;
+        let mut instructions = 0;
+        for amp in amps {
+            let (amp_ip, retired) = amp.join().unwrap_or_else(|err| panic::resume_unwind(err))?;
+            ip = amp_ip;
+            instructions += retired;
+        }
+        let output = output.ok_or(IntcodeError::MissingOutput { ip })?;
+        Ok((output, instructions))
+    })
 }
 
 // fn get_max_feedback_phase(amps: &mut [Cpu], phases: &[i64], )
 
-fn get_max_feedback(program: &[i64]) -> i64 {
+fn get_max_feedback(program: &[i64], config: &AmpConfig) -> Result<(i64, u64), IntcodeError> {
     let mut max_output = i64::MIN;
-    let mut phases: [i64; 5] = [-1; 5];
-    let mut max_phases: [i64; 5] = [0; 5];
+    let mut max_phases: Vec<i64> = Vec::new();
     let mut output = 0;
-
-    let mut amps: [Cpu; 5] = std::array::from_fn(|_| Cpu::new());
-    for amp in &mut amps {
-        amp.mode = CpuMode::BreakOnOutput;
-    }
+    let mut instructions = 0;
 
     println!("-----------------------");
-    for phase_a in 5..10 {
-        phases[0] = phase_a;
-        for phase_b in 5..10 {
-            if phases.contains(&phase_b) {
-                continue;
+    for phases in phase_settings(config.amps, config.feedback_phases.clone()) {
+        println!("\x1b[35m{:?}\x1b[m", phases);
+        if config.threads {
+            let (signal, retired) = run_feedback_threads(program, &phases)?;
+            output = signal;
+            instructions += retired;
+        } else {
+            let mut amps: Vec<Cpu> = Vec::with_capacity(phases.len());
+            for phase in &phases {
+                let amp = Cpu::builder().program(program).input(&[*phase]).build()?;
+                amps.push(amp);
             }
-            phases[1] = phase_b;
-            for phase_c in 5..10 {
-                if phases.contains(&phase_c) {
-                    continue;
-                }
-                phases[2] = phase_c;
-                for phase_d in 5..10 {
-                    if phases.contains(&phase_d) {
-                        continue;
-                    }
-                    phases[3] = phase_d;
-                    for phase_e in 5..10 {
-                        if phases.contains(&phase_e) {
-                            continue;
-                        }
-                        phases[4] = phase_e;
-
-                        println!("\x1b[35m{:?}\x1b[m", phases);
-                        for i in 0..5 {
-                            load_program(&mut amps[i], program);
-                            amps[i].io_in.push_front(phases[i]);
-                        }
 
-                        run_feedback_loop(&mut amps, &mut output);
+            run_feedback_loop(&mut amps, &mut output)?;
+            instructions += amps.iter().map(Cpu::retired).sum::<u64>();
+        }
 
-                        if output > max_output {
-                            max_output = output;
-                            max_phases = phases;
-                        }
-                    }
-                    phases[4] = -1;
-                }
-                phases[3] = -1;
-            }
-            phases[2] = -1;
+        if output > max_output {
+            max_output = output;
+            max_phases = phases;
         }
-        phases[1] = -1;
     }
 
     println!("\x1b[34m{:?}\x1b[m", max_phases);
-    max_output
+    Ok((max_output, instructions))
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        println!("no input provided!");
-        return;
-    }
+// Returns the number of instructions every amp ran between them
+fn solve(program: &[i64], config: &AmpConfig) -> Result<u64, AocError> {
+    let (output, chain_instructions) = get_max_output(program, config)?;
+    println!("output: {output}");
 
-    let input = get_input(&args[1]);
+    let (output, feedback_instructions) = get_max_feedback(program, config)?;
+    println!("feedback output: {output}");
+    Ok(chain_instructions + feedback_instructions)
+}
 
-    let program = get_program(input);
+fn run(args: &[String]) -> Result<u64, AocError> {
+    let path = args
+        .get(1)
+        .ok_or_else(|| AocError::Usage(String::from("no input provided!")))?;
+    let program = read_intcode(path)?;
     // print_prog(&program, 0);
+    let config = AmpConfig::from_args(&args[2..])?;
 
-    // let output = get_max_output(&program);
-    let output = get_max_feedback(&program);
+    solve(&program, &config)
+}
 
-    println!("output: {output}");
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    match run(&args) {
+        Ok(instructions) => report_instructions(instructions),
+        Err(err) => err.exit(),
+    }
 }
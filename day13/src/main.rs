@@ -1,17 +1,23 @@
-use core::panic;
-use std::{
-    collections::{HashMap, VecDeque},
-    env, fs,
-    hash::Hash,
-    io::{Read, Write, stdin, stdout},
-    thread::sleep,
-    time::Duration,
-};
+mod strategy;
 
-use crossterm::{
-    event::{self, Event, KeyCode, read},
-    terminal,
-};
+use std::{env, error::Error, thread::sleep, time::Duration};
+
+use grid::{Point2, SparseGrid, print_canvas};
+use intcode::{Cpu, State, Verbosity};
+use render::{Frame, Recorder, Renderer};
+use strategy::{Manual, MemoryHack, PaddleTracking, Scripted, Strategy};
+use utils::error::AocError;
+use utils::parse::read_intcode;
+use utils::runner::report_instructions;
+
+const PALETTE: [(char, &str); 4] = [
+    ('#', "\x1b[34;44m"),
+    ('X', "\x1b[35;45m"),
+    ('═', "\x1b[1;31m"),
+    ('o', "\x1b[1;32m"),
+];
+
+const PROFILE_WINDOW: usize = 16;
 
 enum Tile {
     Empty,
@@ -21,409 +27,24 @@ enum Tile {
     Ball,
 }
 
-#[derive(PartialEq, Debug)]
-enum Op {
-    Add,
-    Mul,
-    In,
-    Out,
-    Jnz,
-    Jz,
-    Lt,
-    Cmp,
-    AdjBp,
-    Hlt,
-}
-
-#[derive(Default)]
-enum CpuMode {
-    #[default]
-    Normal,
-    ReadStdin,
-}
-
-#[derive(Copy, Clone)]
-enum RegMode {
-    Pos,
-    Imm,
-    Rel,
-}
-
-#[derive(Default)]
-enum State {
-    Active,
-    Ready,
-    #[default]
-    Halted,
-}
-
-struct Cmd {
-    op: Op,
-    n_operands: usize,
-    writes: bool,
-}
-
-struct Cpu {
-    ip: usize,
-    bp: i64,
-    reg: [i64; 8],
-    reg_mode: [RegMode; 8],
-    memory: Vec<i64>,
-    io_in: VecDeque<i64>,
-    io_out: VecDeque<i64>,
-    mode: CpuMode,
-    state: State,
+fn draw_canvas(tiles: &SparseGrid<Tile>) -> Vec<Vec<char>> {
+    tiles.render(' ', |tile| match tile {
+        Tile::Empty => ' ',
+        Tile::Wall => '#',
+        Tile::Block => 'X',
+        Tile::Paddle => '═',
+        Tile::Ball => 'o',
+    })
 }
 
-impl Cpu {
-    fn new() -> Self {
-        let mut new = Self {
-            ip: 0,
-            bp: 0,
-            reg: [0; 8],
-            reg_mode: [RegMode::Pos; 8],
-            memory: Vec::new(),
-            io_in: VecDeque::new(),
-            io_out: VecDeque::new(),
-            mode: CpuMode::Normal,
-            state: State::Halted,
+// Runs until the game wants the joystick, drawing tiles as they come
+fn get_tiles(cpu: &mut Cpu, tiles: &mut SparseGrid<Tile>, score: &mut i64) -> Result<(), AocError> {
+    let mut outputs = cpu.outputs();
+    while let Some(x) = outputs.next() {
+        let (Some(y), Some(z)) = (outputs.next(), outputs.next()) else {
+            return Err(AocError::solver("game stopped partway through a tile"));
         };
-        new.memory.resize(1_000_000, 0);
-        new
-    }
-
-    fn load_program(&mut self, program: &[i64]) {
-        self.ip = 0;
-        self.bp = 0;
-        self.io_in.clear();
-        self.io_out.clear();
-        self.state = State::Ready;
-        self.memory.fill(0);
-        self.memory[0..program.len()].copy_from_slice(program);
-    }
-
-    fn print_cmd(&self, cmd: &Cmd) {
-        print!(
-            "\x1b[33m{:4}\x1b[m : \x1b[34m{:4}\x1b[m   ",
-            self.bp, self.ip
-        );
-        print!("\x1b[31m{:?}\x1b[m\t", cmd.op);
-        for i in 0..=cmd.n_operands {
-            print!("[{}]", self.memory[self.ip + i]);
-        }
-        println!();
-    }
-
-    fn get_mode(&mut self, instruction: i64, n_operands: usize) {
-        let mut digits = instruction / 100;
-
-        let mode: &mut [RegMode] = &mut self.reg_mode;
-        for i in 0..n_operands {
-            mode[i] = match digits % 10 {
-                0 => RegMode::Pos,
-                1 => RegMode::Imm,
-                2 => RegMode::Rel,
-                _ => panic!("Register mode not implemented!"),
-            };
-            digits /= 10;
-        }
-    }
-
-    fn execute_cmd(&mut self, cmd: Cmd) {
-        let boundary = if cmd.writes { 1 } else { 0 };
-        for i in 0..cmd.n_operands - boundary {
-            match self.reg_mode[i] {
-                RegMode::Pos => self.reg[i] = self.memory[self.reg[i] as usize],
-                RegMode::Imm => (),
-                RegMode::Rel => self.reg[i] = self.memory[(self.bp + self.reg[i]) as usize],
-            }
-        }
-
-        match cmd.op {
-            Op::Add => {
-                if let RegMode::Rel = self.reg_mode[2] {
-                    self.reg[2] += self.bp;
-                }
-                self.memory[self.reg[2] as usize] = self.reg[0] + self.reg[1]
-            }
-            Op::Mul => {
-                if let RegMode::Rel = self.reg_mode[2] {
-                    self.reg[2] += self.bp;
-                }
-                self.memory[self.reg[2] as usize] = self.reg[0] * self.reg[1]
-            }
-            Op::In => {
-                let input: i64;
-                if let CpuMode::ReadStdin = self.mode {
-                    input = read_input();
-                } else {
-                    if self.io_in.is_empty() {
-                        self.state = State::Ready;
-                        println!("\x1b[35;1mWaiting for IO in...\x1b[m");
-                        return;
-                    }
-                    input = self.io_in.pop_back().expect("No io available to read!");
-                    println!("\x1b[1;32mINPUT  <\x1b[m {}", input);
-                }
-                if let RegMode::Rel = self.reg_mode[0] {
-                    self.reg[0] += self.bp;
-                }
-                self.memory[self.reg[0] as usize] = input;
-            }
-            Op::Out => {
-                println!("\x1b[1;31mOUTPUT >\x1b[m {}", self.reg[0]);
-                self.io_out.push_front(self.reg[0]);
-            }
-            Op::Jnz => {
-                if self.reg[0] != 0 {
-                    self.ip = self.reg[1] as usize;
-                    return;
-                }
-            }
-            Op::Jz => {
-                if self.reg[0] == 0 {
-                    self.ip = self.reg[1] as usize;
-                    return;
-                }
-            }
-            Op::Lt => {
-                if let RegMode::Rel = self.reg_mode[2] {
-                    self.reg[2] += self.bp;
-                }
-                if self.reg[0] < self.reg[1] {
-                    self.memory[self.reg[2] as usize] = 1;
-                } else {
-                    self.memory[self.reg[2] as usize] = 0;
-                }
-            }
-            Op::Cmp => {
-                if let RegMode::Rel = self.reg_mode[2] {
-                    self.reg[2] += self.bp;
-                }
-                if self.reg[0] == self.reg[1] {
-                    self.memory[self.reg[2] as usize] = 1;
-                } else {
-                    self.memory[self.reg[2] as usize] = 0;
-                }
-            }
-            Op::AdjBp => self.bp += self.reg[0],
-            Op::Hlt => {
-                self.state = State::Halted;
-                return;
-            }
-        }
-        self.ip += cmd.n_operands + 1;
-    }
-
-    fn run(&mut self) {
-        self.state = State::Active;
-        loop {
-            // print_prog(&self.memory, self.ip);
-            let instruction = self.memory[self.ip];
-            let cmd: Cmd = get_cmd(self.memory[self.ip]).expect("Invalid opcode encountered!");
-            self.get_mode(instruction, cmd.n_operands);
-            // self.print_cmd(&cmd);
-
-            for i in 0..cmd.n_operands {
-                self.reg[i] = self.memory[self.ip + i + 1];
-                // println!("{}", cpu.reg[i]);
-            }
-
-            self.execute_cmd(cmd);
-
-            let State::Active = self.state else {
-                break;
-            };
-        }
-    }
-}
-
-fn get_cmd(instruction: i64) -> Option<Cmd> {
-    let opcode = instruction % 100;
-    match opcode {
-        1 => Some(Cmd {
-            op: Op::Add,
-            n_operands: 3,
-            writes: true,
-        }),
-        2 => Some(Cmd {
-            op: Op::Mul,
-            n_operands: 3,
-            writes: true,
-        }),
-        3 => Some(Cmd {
-            op: Op::In,
-            n_operands: 1,
-            writes: true,
-        }),
-        4 => Some(Cmd {
-            op: Op::Out,
-            n_operands: 1,
-            writes: false,
-        }),
-        5 => Some(Cmd {
-            op: Op::Jnz,
-            n_operands: 2,
-            writes: false,
-        }),
-        6 => Some(Cmd {
-            op: Op::Jz,
-            n_operands: 2,
-            writes: false,
-        }),
-        7 => Some(Cmd {
-            op: Op::Lt,
-            n_operands: 3,
-            writes: true,
-        }),
-        8 => Some(Cmd {
-            op: Op::Cmp,
-            n_operands: 3,
-            writes: true,
-        }),
-        9 => Some(Cmd {
-            op: Op::AdjBp,
-            n_operands: 1,
-            writes: false,
-        }),
-        99 => Some(Cmd {
-            op: Op::Hlt,
-            n_operands: 0,
-            writes: false,
-        }),
-        _ => None,
-    }
-}
-
-fn read_input() -> i64 {
-    print!("\x1b[1;32mINPUT  <\x1b[m ");
-    stdout().flush().unwrap();
-
-    let mut input = [0u8; 1];
-
-    terminal::enable_raw_mode().expect("Failed to enter raw mode");
-    stdin().read_exact(&mut input).expect("Failed to read char");
-    terminal::disable_raw_mode().expect("Failed to exit raw mode");
-    println!();
-
-    let input = input[0] as char;
-    match input {
-        'a' => -1,
-        'd' => 1,
-        ' ' => 2,
-        _ => 0,
-    }
-}
-
-fn get_input(filename: &str) -> String {
-    fs::read_to_string(filename).expect("Failed to open input.")
-}
-
-fn get_program(input: String) -> Vec<i64> {
-    let mut program: Vec<i64> = Vec::new();
-
-    for num in input.trim().split(",") {
-        // println!("{num}");
-        program.push(num.parse().expect("failed to parse number"));
-    }
-
-    program
-}
-
-fn dump_program(program: &[i64]) {
-    for (i, num) in program.iter().enumerate() {
-        println!("{i} : {num}");
-    }
-}
-
-fn print_prog(program: &[i64], ip: usize) {
-    for i in 0..program.len() {
-        if i == ip {
-            print!("\x1b[31m");
-        }
-        print!("[{}]\x1b[m", program[i]);
-    }
-    println!();
-}
-
-fn find_boundaries(tiles: &HashMap<(i64, i64), Tile>) -> (i64, i64, i64, i64) {
-    let mut min_x = i64::MAX;
-    let mut min_y = i64::MAX;
-    let mut max_x = i64::MIN;
-    let mut max_y = i64::MIN;
-
-    for (key, _) in tiles {
-        let (x, y) = *key;
-        if x < min_x {
-            min_x = x;
-        } else if x > max_x {
-            max_x = x;
-        }
-        if y < min_y {
-            min_y = y;
-        } else if y > max_y {
-            max_y = y;
-        }
-    }
-
-    (min_x, min_y, max_x, max_y)
-}
-
-fn get_canvas(tiles: &HashMap<(i64, i64), Tile>) -> Vec<Vec<char>> {
-    let (min_x, min_y, max_x, max_y) = find_boundaries(tiles);
-    let n_rows = max_y - min_y + 1;
-    let n_cols = max_x - min_x + 1;
-    let mut canvas: Vec<Vec<char>> = Vec::new();
-
-    for _ in 0..n_rows {
-        let mut row: Vec<char> = Vec::new();
-        row.resize(n_cols as usize, ' ');
-        canvas.push(row);
-    }
-
-    canvas
-}
-
-fn draw_canvas(tiles: &HashMap<(i64, i64), Tile>, canvas: &mut Vec<Vec<char>>) {
-    let (min_x, min_y, max_x, max_y) = find_boundaries(tiles);
-    // println!("min: ({},{})", min_x, min_y);
-    // println!("max: ({},{})", max_x, max_y);
-
-    for (key, val) in tiles {
-        let (x, y) = ((key.0 - min_x) as usize, (key.1 - min_y) as usize);
-        canvas[y][x] = match val {
-            Tile::Empty => ' ',
-            Tile::Wall => '#',
-            Tile::Block => 'X',
-            Tile::Paddle => '═',
-            Tile::Ball => 'o',
-        }
-    }
-}
-
-fn print_canvas(canvas: &Vec<Vec<char>>) {
-    for row in canvas {
-        for c in row {
-            match c {
-                '#' => print!("\x1b[34;44m"),
-                'X' => print!("\x1b[35;45m"),
-                '═' => print!("\x1b[1;31m"),
-                'o' => print!("\x1b[1;32m"),
-                _ => (),
-            }
-            print!("{c}\x1b[m");
-        }
-        println!();
-    }
-}
-
-fn get_tiles(cpu: &mut Cpu, tiles: &mut HashMap<(i64, i64), Tile>, score: &mut i64) {
-    cpu.run();
-
-    while let Some(val) = cpu.io_out.pop_back() {
-        let x = val;
-        let y = cpu.io_out.pop_back().expect("No value to read from io_out");
-        let z = cpu.io_out.pop_back().expect("No value to read from io_out");
+        let (x, y, z) = (x?, y?, z?);
         if x == -1 && y == 0 {
             *score = z;
             continue;
@@ -434,15 +55,16 @@ fn get_tiles(cpu: &mut Cpu, tiles: &mut HashMap<(i64, i64), Tile>, score: &mut i
             2 => Tile::Block,
             3 => Tile::Paddle,
             4 => Tile::Ball,
-            _ => panic!("Invalid tile code provided"),
+            _ => return Err(AocError::solver(format!("game drew invalid tile {z}"))),
         };
-        tiles.insert((x, y), tile);
+        tiles.insert(Point2::new(x, y), tile);
     }
+    Ok(())
 }
 
-fn count_blocks(tiles: &HashMap<(i64, i64), Tile>) -> i64 {
+fn count_blocks(tiles: &SparseGrid<Tile>) -> i64 {
     let mut count = 0;
-    for (_, tile) in tiles {
+    for tile in tiles.values() {
         if let Tile::Block = tile {
             count += 1;
         }
@@ -450,9 +72,9 @@ fn count_blocks(tiles: &HashMap<(i64, i64), Tile>) -> i64 {
     count
 }
 
-fn get_optimal_input(tiles: &HashMap<(i64, i64), Tile>) -> i64 {
-    let mut ballpos: (i64, i64) = (0, 0);
-    let mut paddlepos: (i64, i64) = (0, 0);
+fn get_optimal_input(tiles: &SparseGrid<Tile>) -> i64 {
+    let mut ballpos = Point2::ORIGIN;
+    let mut paddlepos = Point2::ORIGIN;
 
     for (key, val) in tiles {
         if let Tile::Ball = val {
@@ -462,69 +84,188 @@ fn get_optimal_input(tiles: &HashMap<(i64, i64), Tile>) -> i64 {
         }
     }
 
-    if paddlepos.0 < ballpos.0 {
+    if paddlepos.x < ballpos.x {
         return 1;
-    } else if paddlepos.0 > ballpos.0 {
+    } else if paddlepos.x > ballpos.x {
         return -1;
     }
     0
 }
 
-fn get_control_input(tiles: &HashMap<(i64, i64), Tile>) -> i64 {
-    let mut input = read_input();
+fn game_frame(tiles: &SparseGrid<Tile>, score: i64) -> Frame {
+    let mut frame = Frame::from_canvas(&draw_canvas(tiles), &PALETTE);
+    frame.push_line(&format!("Score: {score}"));
+    frame
+}
 
-    if input == 2 {
-        input = get_optimal_input(tiles);
-    }
+fn print_game(tiles: &SparseGrid<Tile>, score: i64) {
+    let canvas = draw_canvas(tiles);
+    print_canvas(&canvas, &PALETTE);
+    println!("Score: {score}");
+}
 
-    input
+struct Outcome {
+    score: i64,
+    // Number of times the game asked for the joystick
+    frames: u64,
 }
 
-fn run_game(cpu: &mut Cpu, tiles: &mut HashMap<(i64, i64), Tile>) -> i64 {
+// Playing by hand or profiling needs the screen as the game runs; otherwise frames are
+// only recorded, if a recorder is given, to be replayed once the game is over.
+fn run_game(
+    cpu: &mut Cpu,
+    tiles: &mut SparseGrid<Tile>,
+    strategy: &mut dyn Strategy,
+    live: bool,
+    mut recorder: Option<&mut Recorder>,
+) -> Result<Outcome, AocError> {
     let mut score = 0;
-    cpu.run();
+    let mut frames = 0;
 
-    get_tiles(cpu, tiles, &mut score);
-    let mut canvas = get_canvas(tiles);
-    draw_canvas(tiles, &mut canvas);
-    print_canvas(&canvas);
-    println!("Score: {score}");
+    get_tiles(cpu, tiles, &mut score)?;
+    strategy.prepare(cpu, tiles)?;
+    if live {
+        print_game(tiles, score);
+    }
 
     loop {
-        // cpu.io_in.push_front(get_control_input(tiles));
-        cpu.io_in.push_front(get_optimal_input(tiles));
-        cpu.run();
-        get_tiles(cpu, tiles, &mut score);
-        // print!("\x1b[2J\x1b[H");
-        draw_canvas(tiles, &mut canvas);
-        print_canvas(&canvas);
-        println!("Score: {score}");
-        sleep(Duration::from_millis(20));
+        cpu.io_in.push_front(strategy.joystick(tiles));
+        frames += 1;
+        get_tiles(cpu, tiles, &mut score)?;
+        if let Some(recorder) = recorder.as_deref_mut() {
+            recorder.capture(game_frame(tiles, score));
+        }
+        if live {
+            if cpu.profile.is_some() {
+                print!("\x1b[2J\x1b[H");
+            }
+            print_game(tiles, score);
+            if let Some(profile) = &cpu.profile {
+                profile.print(cpu.memory(), PROFILE_WINDOW);
+            }
+            sleep(Duration::from_millis(20));
+        }
         if let State::Halted = cpu.state {
             break;
         }
     }
-    score
+    Ok(Outcome { score, frames })
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        println!("no input provided!");
-        return;
+// Plays a whole game without drawing it, returning how it went along with the number
+// of instructions executed and the blocks left standing
+fn play_quietly(
+    program: &[i64],
+    strategy: &mut dyn Strategy,
+) -> Result<(Outcome, u64, i64), AocError> {
+    let mut cpu = Cpu::builder()
+        .program(program)
+        .verbosity(Verbosity::Silent)
+        .build()?;
+    cpu.poke(0, 2)?;
+    let mut tiles: SparseGrid<Tile> = SparseGrid::new();
+    let outcome = run_game(&mut cpu, &mut tiles, strategy, false, None)?;
+    Ok((outcome, cpu.retired(), count_blocks(&tiles)))
+}
+
+// Prints a row of the tournament table and returns the instructions the game ran. A
+// strategy that fails gets its error instead, and the rest still play.
+fn compete(program: &[i64], strategy: &mut dyn Strategy) -> u64 {
+    let result = play_quietly(program, strategy);
+    let name = strategy.name();
+    match result {
+        Ok((outcome, instructions, blocks)) => {
+            println!(
+                "{name:<16} {:>8} {:>8} {instructions:>13} {blocks:>12}",
+                outcome.score, outcome.frames
+            );
+            instructions
+        }
+        Err(err) => {
+            match err.source() {
+                Some(cause) => println!("{name:<16} \x1b[31m{err}: {cause}\x1b[m"),
+                None => println!("{name:<16} \x1b[31m{err}\x1b[m"),
+            }
+            0
+        }
     }
+}
+
+// Every strategy plays its own game. The scripted player replays the moves paddle
+// tracking made, so the two should agree. Returns the instructions every game ran.
+fn tournament(program: &[i64]) -> u64 {
+    println!(
+        "\x1b[34;1m{:<16} {:>8} {:>8} {:>13} {:>12}\x1b[m",
+        "strategy", "score", "frames", "instructions", "blocks left"
+    );
+    let mut tracker = PaddleTracking::default();
+    let mut instructions = compete(program, &mut tracker);
+    instructions += compete(program, &mut Scripted::new(tracker.moves));
+    instructions + compete(program, &mut MemoryHack)
+}
 
-    let input = get_input(&args[1]);
+// Returns the number of instructions run over every game played
+fn solve(program: &[i64], flags: &[String]) -> Result<u64, AocError> {
+    let manual = flags.iter().any(|arg| arg == "play");
+    let profile = flags.iter().any(|arg| arg == "profile");
+    let animate = flags.iter().any(|arg| arg == "animate");
+    let mut cpu = Cpu::builder().program(program).build()?;
+    let mut tiles: SparseGrid<Tile> = SparseGrid::new();
+    let mut score = 0;
+
+    get_tiles(&mut cpu, &mut tiles, &mut score)?;
+    let blocks = count_blocks(&tiles);
+    println!("blocks: {}", blocks);
+    let drawing_instructions = cpu.retired();
+
+    if flags.iter().any(|arg| arg == "tournament") {
+        return Ok(drawing_instructions + tournament(program));
+    }
 
-    let program = get_program(input);
-    let mut cpu = Cpu::new();
-    cpu.load_program(&program);
-    cpu.memory[0] = 2;
+    let mut cpu = Cpu::builder()
+        .program(program)
+        .verbosity(if profile {
+            Verbosity::Silent
+        } else {
+            Verbosity::Io
+        })
+        .profile(profile)
+        .build()?;
+    cpu.poke(0, 2)?;
     // cpu.mode = CpuMode::ReadStdin;
-    let mut tiles: HashMap<(i64, i64), Tile> = HashMap::new();
+    tiles.clear();
+    let mut recorder = animate.then(Recorder::new);
+    let mut strategy: Box<dyn Strategy> = match manual {
+        true => Box::new(Manual),
+        false => Box::new(PaddleTracking::default()),
+    };
+    let outcome = run_game(
+        &mut cpu,
+        &mut tiles,
+        strategy.as_mut(),
+        manual || profile,
+        recorder.as_mut(),
+    )?;
+    if let Some(recording) = recorder {
+        recording.replay(&mut Renderer::stdout(), Duration::from_millis(20));
+    }
+
+    println!("output: {}", outcome.score);
+    Ok(drawing_instructions + cpu.retired())
+}
 
-    // let output = count_blocks(&tiles);
-    let score = run_game(&mut cpu, &mut tiles);
+fn run(args: &[String]) -> Result<u64, AocError> {
+    let path = args
+        .get(1)
+        .ok_or_else(|| AocError::Usage(String::from("no input provided!")))?;
+    let program = read_intcode(path)?;
+    solve(&program, &args[2..])
+}
 
-    println!("output: {}", score);
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    match run(&args) {
+        Ok(instructions) => report_instructions(instructions),
+        Err(err) => err.exit(),
+    }
 }
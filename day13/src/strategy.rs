@@ -0,0 +1,117 @@
+use grid::SparseGrid;
+use intcode::{Cpu, read_input};
+use utils::error::AocError;
+
+use crate::{Tile, get_optimal_input};
+
+// Something that can play the game: it picks a joystick position (-1 left, 0 still,
+// 1 right) each time the game asks for one
+pub trait Strategy {
+    fn name(&self) -> &str;
+
+    // Called once with the first screen drawn, before the game asks for input
+    fn prepare(&mut self, _cpu: &mut Cpu, _tiles: &SparseGrid<Tile>) -> Result<(), AocError> {
+        Ok(())
+    }
+
+    fn joystick(&mut self, tiles: &SparseGrid<Tile>) -> i64;
+}
+
+// Keeps the paddle under the ball, remembering every move it made
+#[derive(Default)]
+pub struct PaddleTracking {
+    pub moves: Vec<i64>,
+}
+
+impl Strategy for PaddleTracking {
+    fn name(&self) -> &str {
+        "paddle tracking"
+    }
+
+    fn joystick(&mut self, tiles: &SparseGrid<Tile>) -> i64 {
+        let input = get_optimal_input(tiles);
+        self.moves.push(input);
+        input
+    }
+}
+
+// Asks at the terminal; space hands the move to paddle tracking
+pub struct Manual;
+
+impl Strategy for Manual {
+    fn name(&self) -> &str {
+        "manual"
+    }
+
+    fn joystick(&mut self, tiles: &SparseGrid<Tile>) -> i64 {
+        match read_input() {
+            2 => get_optimal_input(tiles),
+            input => input,
+        }
+    }
+}
+
+// Plays back a fixed list of moves, then leaves the joystick alone
+pub struct Scripted {
+    moves: Vec<i64>,
+    next: usize,
+}
+
+impl Scripted {
+    pub fn new(moves: Vec<i64>) -> Self {
+        Self { moves, next: 0 }
+    }
+}
+
+impl Strategy for Scripted {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn joystick(&mut self, _tiles: &SparseGrid<Tile>) -> i64 {
+        let input = self.moves.get(self.next).copied().unwrap_or(0);
+        self.next += 1;
+        input
+    }
+}
+
+// Finds the paddle's row of the screen in the game's memory and fills it with paddle,
+// so the ball can never get past and the joystick isn't needed
+pub struct MemoryHack;
+
+impl Strategy for MemoryHack {
+    fn name(&self) -> &str {
+        "memory hack"
+    }
+
+    fn prepare(&mut self, cpu: &mut Cpu, tiles: &SparseGrid<Tile>) -> Result<(), AocError> {
+        let width = tiles.bounds().map_or(0, |bounds| bounds.width()) as usize;
+        let row = find_paddle_row(cpu, width)
+            .ok_or_else(|| AocError::solver("no paddle row in the game's memory"))?;
+        for addr in row + 1..row + width - 1 {
+            cpu.poke(addr, 3)?;
+        }
+        Ok(())
+    }
+
+    fn joystick(&mut self, _tiles: &SparseGrid<Tile>) -> i64 {
+        0
+    }
+}
+
+// A run of `width` cells with a wall at each end and a single paddle between them
+fn find_paddle_row(cpu: &Cpu, width: usize) -> Option<usize> {
+    if width < 3 {
+        return None;
+    }
+    let cells: Vec<i64> = (0..cpu.memory().allocated())
+        .map_while(|addr| cpu.peek(addr).ok())
+        .collect();
+    cells.windows(width).position(|row| {
+        let inner = &row[1..width - 1];
+        row[0] == 1
+            && row[width - 1] == 1
+            && inner.iter().filter(|cell| **cell == 3).count() == 1
+            && inner.iter().all(|cell| *cell == 0 || *cell == 3)
+    })
+}
@@ -0,0 +1,111 @@
+use std::{fs, io};
+
+const FAILURE_MARKER: &str = "Didn't make it across:";
+
+// What the springdroid saw in its last moments, plus the script that got it there.
+// The text file is stable line-by-line so two attempts can be compared with diff.
+pub struct FailureReport {
+    pub mode: String,
+    pub script: Vec<String>,
+    pub frames: Vec<Vec<String>>,
+}
+
+impl FailureReport {
+    // None if the output doesn't contain a failure animation
+    pub fn from_output(mode: &str, script: &[&str], output: &str) -> Option<Self> {
+        let start = output.find(FAILURE_MARKER)? + FAILURE_MARKER.len();
+        let mut frames: Vec<Vec<String>> = Vec::new();
+        let mut frame: Vec<String> = Vec::new();
+
+        for line in output[start..].lines() {
+            if line.trim().is_empty() {
+                if !frame.is_empty() {
+                    frames.push(std::mem::take(&mut frame));
+                }
+            } else {
+                frame.push(line.to_string());
+            }
+        }
+        if !frame.is_empty() {
+            frames.push(frame);
+        }
+
+        Some(Self {
+            mode: mode.to_string(),
+            script: script.iter().map(|line| line.to_string()).collect(),
+            frames,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("# mode\n{}\n\n# script\n", self.mode);
+        for line in &self.script {
+            text.push_str(line);
+            text.push('\n');
+        }
+        for (i, frame) in self.frames.iter().enumerate() {
+            text.push_str(&format!("\n# frame {}\n", i + 1));
+            for line in frame {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    // Frames stacked top to bottom, one blank row apart
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, path: &str) -> io::Result<()> {
+        use std::{fs::File, io::BufWriter};
+
+        const SCALE: usize = 4;
+
+        let n_cols = self
+            .frames
+            .iter()
+            .flatten()
+            .map(|line| line.len())
+            .max()
+            .unwrap_or(0);
+        let mut rows: Vec<&str> = Vec::new();
+        for frame in &self.frames {
+            rows.extend(frame.iter().map(|line| line.as_str()));
+            rows.push("");
+        }
+        if n_cols == 0 || rows.is_empty() {
+            return Ok(());
+        }
+
+        let width = n_cols * SCALE;
+        let height = rows.len() * SCALE;
+        let mut pixels: Vec<u8> = Vec::with_capacity(width * height * 3);
+        for row in &rows {
+            let colours: Vec<[u8; 3]> = (0..n_cols)
+                .map(|x| match row.as_bytes().get(x) {
+                    Some(b'#') => [40, 80, 200],
+                    Some(b'@') => [220, 40, 40],
+                    Some(b'.') => [30, 30, 30],
+                    _ => [0, 0, 0],
+                })
+                .collect();
+            for _ in 0..SCALE {
+                for colour in &colours {
+                    for _ in 0..SCALE {
+                        pixels.extend_from_slice(colour);
+                    }
+                }
+            }
+        }
+
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&pixels).map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,182 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+// Picks up every item that is safe to carry and ends at the security checkpoint
+const COLLECT_ROUTE: [&str; 38] = [
+    "east",
+    "take food ration",
+    "south",
+    "take prime number",
+    "north",
+    "east",
+    "take manifold",
+    "east",
+    "north",
+    "north",
+    "take fuel cell",
+    "south",
+    "east",
+    "take spool of cat6",
+    "west",
+    "south",
+    "east",
+    "take jam",
+    "west",
+    "west",
+    "west",
+    "west",
+    "north",
+    "north",
+    "west",
+    "take mug",
+    "east",
+    "north",
+    "east",
+    "east",
+    "take loom",
+    "west",
+    "west",
+    "south",
+    "south",
+    "west",
+    "north",
+    "west",
+];
+
+// Named sequences of adventure commands. They are saved as plain text, one command per
+// line under a `# name` header, so they can be written by hand as well as recorded:
+//
+//     # collect
+//     east
+//     take food ration
+pub struct Macros {
+    macros: BTreeMap<String, Vec<String>>,
+}
+
+impl Macros {
+    // Just the built in `collect` route
+    pub fn new() -> Self {
+        let mut macros = BTreeMap::new();
+        let route = COLLECT_ROUTE.iter().map(|cmd| cmd.to_string()).collect();
+        macros.insert(String::from("collect"), route);
+        Self { macros }
+    }
+
+    // Macros in the file replace built in ones of the same name. A missing file is
+    // the same as an empty one.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut macros = Self::new();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(macros),
+            Err(err) => return Err(format!("{}: {err}", path.display())),
+        };
+
+        let mut current: Option<&str> = None;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('#') {
+                let name = name.trim();
+                macros.insert(name, Vec::new());
+                current = Some(name);
+                continue;
+            }
+            let Some(name) = current else {
+                let path = path.display();
+                return Err(format!("{path}:{}: command outside a macro", i + 1));
+            };
+            if let Some(commands) = macros.macros.get_mut(name) {
+                commands.push(line.to_string());
+            }
+        }
+
+        Ok(macros)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (name, commands) in &self.macros {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&format!("# {name}\n"));
+            for cmd in commands {
+                text.push_str(cmd);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.macros.get(name).map(|commands| commands.as_slice())
+    }
+
+    pub fn insert(&mut self, name: &str, commands: Vec<String>) {
+        self.macros.insert(name.to_string(), commands);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.macros
+            .iter()
+            .map(|(name, commands)| (name.as_str(), commands.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let path = env::temp_dir().join(format!("day25-macros-{}.txt", process::id()));
+        let mut macros = Macros::new();
+        macros.insert("loop", vec![String::from("north"), String::from("south")]);
+        macros.insert("grab", vec![String::from("take mug")]);
+        macros.save(&path).unwrap();
+
+        let loaded = Macros::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.to_text(), macros.to_text());
+        assert_eq!(loaded.get("grab"), Some(&[String::from("take mug")][..]));
+        assert_eq!(loaded.get("collect").map(|route| route.len()), Some(38));
+    }
+
+    #[test]
+    fn missing_file_has_just_the_built_in_macros() {
+        let path = env::temp_dir().join("day25-macros-that-do-not-exist.txt");
+        let macros = Macros::load(&path).unwrap();
+
+        let names: Vec<&str> = macros.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["collect"]);
+    }
+
+    #[test]
+    fn file_macros_replace_built_in_ones() {
+        let path = env::temp_dir().join(format!("day25-macros-edit-{}.txt", process::id()));
+        fs::write(&path, "# collect\n  west \n\n# drop\ndrop mug\n").unwrap();
+
+        let macros = Macros::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(macros.get("collect"), Some(&[String::from("west")][..]));
+        assert_eq!(macros.get("drop"), Some(&[String::from("drop mug")][..]));
+    }
+
+    #[test]
+    fn command_before_a_header_is_an_error() {
+        let path = env::temp_dir().join(format!("day25-macros-bad-{}.txt", process::id()));
+        fs::write(&path, "north\n# walk\nsouth\n").unwrap();
+
+        let err = Macros::load(&path).err();
+        fs::remove_file(&path).unwrap();
+        assert!(err.is_some_and(|err| err.ends_with(":1: command outside a macro")));
+    }
+}
@@ -1,60 +1,43 @@
-use core::panic;
+mod macros;
+mod map;
+
 use std::{
-    collections::{HashMap, VecDeque},
     env, fs,
-    hash::Hash,
-    io::{Read, Write, stdin, stdout},
-    thread::sleep,
-    time::Duration,
-};
-
-use crossterm::{
-    event::{self, Event, KeyCode, read},
-    terminal,
+    io::stdin,
+    path::{Path, PathBuf},
 };
 
-#[derive(PartialEq, Debug)]
-enum Op {
-    Add,
-    Mul,
-    In,
-    Out,
-    Jnz,
-    Jz,
-    Lt,
-    Cmp,
-    AdjBp,
-    Hlt,
+use intcode::{AsciiComputer, Coverage, Cpu, IoLog, State, Verbosity, Watch};
+use macros::Macros;
+use map::{CHECKPOINT, RoomMap};
+use utils::combinatorics::gray_code;
+use utils::error::AocError;
+use utils::parse::{patch_from_args, read_intcode};
+use utils::runner::report_instructions;
+
+const PROFILE_WINDOW: usize = 16;
+const MACRO_FILE: &str = "day25-macros.txt";
+const SESSION_FILE: &str = "day25-session.txt";
+
+// Where macros and saved sessions are kept: beside the puzzle input, so they stay with
+// it whatever directory the game is started from. Input read from stdin keeps them in
+// the current directory.
+struct SaveFiles {
+    macros: PathBuf,
+    session: PathBuf,
 }
 
-enum Dir {
-    North,
-    South,
-    East,
-    West,
-}
-
-#[derive(Default)]
-enum CpuMode {
-    #[default]
-    Normal,
-    ReadChar,
-    Network(i64),
-}
-
-#[derive(Copy, Clone)]
-enum RegMode {
-    Pos,
-    Imm,
-    Rel,
-}
-
-#[derive(Default)]
-enum State {
-    Active,
-    Ready,
-    #[default]
-    Halted,
+impl SaveFiles {
+    fn beside(input: &str) -> Self {
+        let dir = match Path::new(input).parent() {
+            Some(dir) if input != "-" => dir,
+            _ => Path::new(""),
+        };
+        Self {
+            macros: dir.join(MACRO_FILE),
+            session: dir.join(SESSION_FILE),
+        }
+    }
 }
 
 enum Weight {
@@ -63,606 +46,353 @@ enum Weight {
     Exact,
 }
 
-struct Cmd {
-    op: Op,
-    n_operands: usize,
-    writes: bool,
-}
-
-struct Cpu {
-    ip: usize,
-    bp: i64,
-    reg: [i64; 8],
-    reg_mode: [RegMode; 8],
-    memory: Vec<i64>,
-    io_in: VecDeque<i64>,
-    io_out: VecDeque<i64>,
-    mode: CpuMode,
-    state: State,
-    verbose: bool,
-}
-
-impl Cpu {
-    fn new() -> Self {
-        let mut new = Self {
-            ip: 0,
-            bp: 0,
-            reg: [0; 8],
-            reg_mode: [RegMode::Pos; 8],
-            memory: Vec::new(),
-            io_in: VecDeque::new(),
-            io_out: VecDeque::new(),
-            mode: CpuMode::Normal,
-            state: State::Halted,
-            verbose: true,
-        };
-        new.memory.resize(1_000_000, 0);
-        new
+fn print_cpu_ouput(ascii: &mut AsciiComputer, output: &str) {
+    for c in output.chars() {
+        match c {
+            '#' => print!("\x1b[34m"),
+            '@' => print!("\x1b[31m"),
+            _ => (),
+        }
+        print!("{c}\x1b[m");
     }
-
-    fn load_program(&mut self, program: &[i64]) {
-        self.ip = 0;
-        self.bp = 0;
-        self.io_in.clear();
-        self.io_out.clear();
-        self.state = State::Ready;
-        self.memory.fill(0);
-        self.memory[0..program.len()].copy_from_slice(program);
+    for num in ascii.take_values() {
+        println!("\x1b[31;1mOutput > \x1b[m{num}");
     }
+}
 
-    fn print_cmd(&self, cmd: &Cmd) {
-        print!(
-            "\x1b[33m{:4}\x1b[m : \x1b[34m{:4}\x1b[m   ",
-            self.bp, self.ip
-        );
-        print!("\x1b[31m{:?}\x1b[m\t", cmd.op);
-        for i in 0..=cmd.n_operands {
-            print!("[{}]", self.memory[self.ip + i]);
-        }
-        println!();
-    }
+fn get_inv(ascii: &mut AsciiComputer, session: &mut Session) -> Result<Vec<String>, AocError> {
+    let mut inv: Vec<String> = Vec::new();
 
-    fn get_mode(&mut self, instruction: i64, n_operands: usize) {
-        let mut digits = instruction / 100;
+    let out = send(ascii, "inv", session)?;
+    let start = out
+        .find("inventory:")
+        .ok_or_else(|| AocError::solver("no inventory in the game's output"))?
+        + "inventory:\n".len();
+    let end = out
+        .rfind("\n\n")
+        .filter(|&end| end >= start)
+        .ok_or_else(|| AocError::solver("inventory list isn't terminated"))?;
+    let inv_string = &out[start..end];
 
-        let mode: &mut [RegMode] = &mut self.reg_mode;
-        for i in 0..n_operands {
-            mode[i] = match digits % 10 {
-                0 => RegMode::Pos,
-                1 => RegMode::Imm,
-                2 => RegMode::Rel,
-                _ => panic!("Register mode not implemented!"),
-            };
-            digits /= 10;
+    for line in inv_string.lines() {
+        if let Some(start) = line.find("- ") {
+            inv.push(line[(start + "- ".len())..].to_string());
         }
     }
 
-    fn execute_cmd(&mut self, cmd: Cmd) {
-        let boundary = if cmd.writes { 1 } else { 0 };
-        for i in 0..cmd.n_operands - boundary {
-            match self.reg_mode[i] {
-                RegMode::Pos => self.reg[i] = self.memory[self.reg[i] as usize],
-                RegMode::Imm => (),
-                RegMode::Rel => self.reg[i] = self.memory[(self.bp + self.reg[i]) as usize],
-            }
-        }
+    Ok(inv)
+}
 
-        match cmd.op {
-            Op::Add => {
-                if let RegMode::Rel = self.reg_mode[2] {
-                    self.reg[2] += self.bp;
-                }
-                self.memory[self.reg[2] as usize] = self.reg[0] + self.reg[1]
-            }
-            Op::Mul => {
-                if let RegMode::Rel = self.reg_mode[2] {
-                    self.reg[2] += self.bp;
-                }
-                self.memory[self.reg[2] as usize] = self.reg[0] * self.reg[1]
-            }
-            Op::In => {
-                let input: i64;
-                match self.mode {
-                    CpuMode::ReadChar => input = read_input(),
-                    CpuMode::Network(_) => {
-                        if self.io_in.is_empty() {
-                            input = -1;
-                            self.state = State::Ready;
-                        } else {
-                            input = self.io_in.pop_back().expect("No io available to read!");
-                        }
-                    }
-                    CpuMode::Normal => {
-                        if self.io_in.is_empty() {
-                            self.state = State::Ready;
-                            println!("\x1b[35;1mWaiting for IO in...\x1b[m");
-                            return;
-                        }
-                        input = self.io_in.pop_back().expect("No io available to read!");
-                    }
-                }
-                if self.verbose {
-                    println!("\x1b[1;32mINPUT  <\x1b[m {}", input);
-                }
-                if let RegMode::Rel = self.reg_mode[0] {
-                    self.reg[0] += self.bp;
-                }
-                self.memory[self.reg[0] as usize] = input;
-            }
-            Op::Out => {
-                if self.verbose {
-                    println!("\x1b[1;34mOUTPUT >\x1b[m {}", self.reg[0]);
-                }
-                self.io_out.push_front(self.reg[0]);
-                if let CpuMode::Network(count) = self.mode {
-                    match count {
-                        0 => self.mode = CpuMode::Network(1),
-                        1 => self.mode = CpuMode::Network(2),
-                        2 => {
-                            self.mode = CpuMode::Network(0);
-                            self.state = State::Ready;
-                        }
-                        _ => panic!("Invalid network state encountered"),
-                    }
-                }
-            }
-            Op::Jnz => {
-                if self.reg[0] != 0 {
-                    self.ip = self.reg[1] as usize;
-                    return;
-                }
-            }
-            Op::Jz => {
-                if self.reg[0] == 0 {
-                    self.ip = self.reg[1] as usize;
-                    return;
-                }
-            }
-            Op::Lt => {
-                if let RegMode::Rel = self.reg_mode[2] {
-                    self.reg[2] += self.bp;
-                }
-                if self.reg[0] < self.reg[1] {
-                    self.memory[self.reg[2] as usize] = 1;
-                } else {
-                    self.memory[self.reg[2] as usize] = 0;
-                }
-            }
-            Op::Cmp => {
-                if let RegMode::Rel = self.reg_mode[2] {
-                    self.reg[2] += self.bp;
-                }
-                if self.reg[0] == self.reg[1] {
-                    self.memory[self.reg[2] as usize] = 1;
-                } else {
-                    self.memory[self.reg[2] as usize] = 0;
-                }
-            }
-            Op::AdjBp => self.bp += self.reg[0],
-            Op::Hlt => {
-                println!("\x1b[31;1mHalting...\x1b[m");
-                self.state = State::Halted;
-                return;
-            }
-        }
-        self.ip += cmd.n_operands + 1;
+fn check_weight(ascii: &mut AsciiComputer, session: &mut Session) -> Result<Weight, AocError> {
+    let output = send(ascii, "north", session)?;
+    if output.contains("heavier") {
+        return Ok(Weight::Heavier);
+    } else if output.contains("lighter") {
+        return Ok(Weight::Lighter);
     }
+    Ok(Weight::Exact)
+}
 
-    fn run(&mut self) {
-        self.state = State::Active;
-        loop {
-            // print_prog(&self.memory, self.ip);
-            let instruction = self.memory[self.ip];
-            let cmd: Cmd = get_cmd(self.memory[self.ip]).expect("Invalid opcode encountered!");
-            self.get_mode(instruction, cmd.n_operands);
-            // self.print_cmd(&cmd);
-
-            for i in 0..cmd.n_operands {
-                self.reg[i] = self.memory[self.ip + i + 1];
-                // println!("{}", cpu.reg[i]);
+// Walks every subset of the inventory in Gray code order, so each attempt only takes
+// or drops a single item. Everything goes through `send`, so the map follows the droid
+// being thrown back to the checkpoint.
+fn hack_weight(ascii: &mut AsciiComputer, session: &mut Session) -> Result<(), AocError> {
+    let inv: Vec<String> = get_inv(ascii, session)?;
+    let mut held: u64 = (1 << inv.len()) - 1;
+
+    for mask in gray_code(inv.len() as u32) {
+        for (i, item) in inv.iter().enumerate() {
+            let bit = 1 << i;
+            if (held ^ mask) & bit == 0 {
+                continue;
             }
-
-            self.execute_cmd(cmd);
-
-            let State::Active = self.state else {
-                break;
-            };
+            let verb = if mask & bit != 0 { "take" } else { "drop" };
+            send(ascii, &format!("{verb} {item}"), session)?;
         }
-    }
-}
-
-fn get_cmd(instruction: i64) -> Option<Cmd> {
-    let opcode = instruction % 100;
-    match opcode {
-        1 => Some(Cmd {
-            op: Op::Add,
-            n_operands: 3,
-            writes: true,
-        }),
-        2 => Some(Cmd {
-            op: Op::Mul,
-            n_operands: 3,
-            writes: true,
-        }),
-        3 => Some(Cmd {
-            op: Op::In,
-            n_operands: 1,
-            writes: true,
-        }),
-        4 => Some(Cmd {
-            op: Op::Out,
-            n_operands: 1,
-            writes: false,
-        }),
-        5 => Some(Cmd {
-            op: Op::Jnz,
-            n_operands: 2,
-            writes: false,
-        }),
-        6 => Some(Cmd {
-            op: Op::Jz,
-            n_operands: 2,
-            writes: false,
-        }),
-        7 => Some(Cmd {
-            op: Op::Lt,
-            n_operands: 3,
-            writes: true,
-        }),
-        8 => Some(Cmd {
-            op: Op::Cmp,
-            n_operands: 3,
-            writes: true,
-        }),
-        9 => Some(Cmd {
-            op: Op::AdjBp,
-            n_operands: 1,
-            writes: false,
-        }),
-        99 => Some(Cmd {
-            op: Op::Hlt,
-            n_operands: 0,
-            writes: false,
-        }),
-        _ => {
-            println!("opcode: {instruction}");
-            None
+        held = mask;
+        if let Weight::Exact = check_weight(ascii, session)? {
+            return Ok(());
         }
     }
+    Ok(())
 }
 
-fn read_input() -> i64 {
-    print!("\x1b[1;32mINPUT  <\x1b[m ");
-    stdout().flush().unwrap();
-
-    let mut input = [0u8; 1];
-
-    terminal::enable_raw_mode().expect("Failed to enter raw mode");
-    stdin().read_exact(&mut input).expect("Failed to read char");
-    terminal::disable_raw_mode().expect("Failed to exit raw mode");
-    println!();
-
-    let input = input[0] as char;
-    match input {
-        'a' => -1,
-        'd' => 1,
-        ' ' => 2,
-        _ => 0,
-    }
+// Lines starting with ':' are commands for this program rather than the game
+fn print_help(files: &SaveFiles) {
+    println!("\x1b[33mcommands:\x1b[m");
+    println!("  :record NAME  start recording game commands as macro NAME");
+    println!(
+        "  :stop         finish recording and save the macro to {}",
+        files.macros.display()
+    );
+    println!("  :play NAME    send the commands of macro NAME");
+    println!("  :macros       list saved macros");
+    println!("  :history      list every game command sent this session");
+    println!("  :map          list the rooms seen so far and where their doors lead");
+    println!("  :goto [ROOM]  walk to a room already seen, by default the {CHECKPOINT}");
+    println!("  :hack         try item combinations until the checkpoint lets you through");
+    println!("  :profile      show the hottest instructions so far");
+    println!("  :coverage     show which parts of memory have run as code so far");
+    println!("  :watch ADDR   show each read and write of an address, or stop showing them");
+    println!(
+        "  :save         save everything sent and received to {}",
+        files.session.display()
+    );
 }
 
-fn get_input(filename: &str) -> String {
-    fs::read_to_string(filename).expect("Failed to open input.")
+struct Recording {
+    name: String,
+    commands: Vec<String>,
 }
 
-fn get_program(input: String) -> Vec<i64> {
-    let mut program: Vec<i64> = Vec::new();
-
-    for num in input.trim().split(",") {
-        // println!("{num}");
-        program.push(num.parse().expect("failed to parse number"));
-    }
-
-    program
+// Everything the interface remembers between commands
+struct Session {
+    history: Vec<String>,
+    recording: Option<Recording>,
+    map: RoomMap,
 }
 
-fn dump_program(program: &[i64]) {
-    for (i, num) in program.iter().enumerate() {
-        println!("{i} : {num}");
+// Sends a command to the game and shows the reply, noting the command in the history
+// and any recording, and the rooms in the reply on the map. Returns the reply, which is
+// empty once the game is over.
+fn send(ascii: &mut AsciiComputer, cmd: &str, session: &mut Session) -> Result<String, AocError> {
+    if let State::Halted = ascii.cpu.state {
+        return Ok(String::new());
     }
-}
-
-fn print_prog(program: &[i64], ip: usize) {
-    for i in 0..program.len() {
-        if i == ip {
-            print!("\x1b[31m");
-        }
-        print!("[{}]\x1b[m", program[i]);
+    session.history.push(cmd.to_string());
+    if let Some(recording) = &mut session.recording {
+        recording.commands.push(cmd.to_string());
     }
-    println!();
+    let output = ascii.converse(Some(cmd))?;
+    print_cpu_ouput(ascii, &output);
+    session.map.observe(cmd, &output);
+    Ok(output)
 }
 
-fn find_boundaries(floor: &HashMap<(usize, usize), i64>) -> (usize, usize, usize, usize) {
-    let mut min_x = usize::MAX;
-    let mut min_y = usize::MAX;
-    let mut max_x = usize::MIN;
-    let mut max_y = usize::MIN;
-
-    for (key, _) in floor {
-        let (x, y) = *key;
-        if x < min_x {
-            min_x = x;
-        } else if x > max_x {
-            max_x = x;
-        }
-        if y < min_y {
-            min_y = y;
-        } else if y > max_y {
-            max_y = y;
+fn print_map(map: &RoomMap) {
+    for room in map.rooms() {
+        let marker = if map.current() == Some(room) {
+            "*"
+        } else {
+            " "
+        };
+        println!("\x1b[33m{marker} {room}\x1b[m");
+        for (door, next) in map.exits(room) {
+            println!("    {door:5} -> {}", next.unwrap_or("?"));
         }
     }
-
-    (min_x, min_y, max_x, max_y)
 }
 
-fn draw_canvas(coords: &HashMap<(usize, usize), i64>) -> Vec<Vec<char>> {
-    let (min_x, min_y, max_x, max_y) = find_boundaries(coords);
-    let n_rows = max_y - min_y + 1;
-    let n_cols = max_x - min_x + 1;
-    let mut canvas: Vec<Vec<char>> = Vec::new();
-    println!("min: ({},{})", min_x, min_y);
-    println!("max: ({},{})", max_x, max_y);
-
-    for _ in 0..n_rows {
-        let mut row: Vec<char> = Vec::new();
-        for _ in 0..n_cols {
-            row.push(' ');
+fn run_game(
+    ascii: &mut AsciiComputer,
+    macros: &mut Macros,
+    files: &SaveFiles,
+) -> Result<(), AocError> {
+    let mut buf: String = String::new();
+    let mut session = Session {
+        history: Vec::new(),
+        recording: None,
+        map: RoomMap::new(),
+    };
+    let output = ascii.converse(None)?;
+    print_cpu_ouput(ascii, &output);
+    session.map.observe("", &output);
+    loop {
+        if let State::Halted = ascii.cpu.state {
+            println!("\x1b[31;1mGame Over!\x1b[m");
+            return Ok(());
         }
-        canvas.push(row);
-    }
-
-    for (key, val) in coords {
-        let (x, y) = ((key.0 - min_x) as usize, (key.1 - min_y) as usize);
-        match val {
-            0 => canvas[y][x] = '.',
-            1 => canvas[y][x] = '#',
-            _ => panic!("Invalid floor tile provided"),
+        buf.clear();
+        let n_read = stdin()
+            .read_line(&mut buf)
+            .map_err(|err| AocError::io("failed to read a command", err))?;
+        // Nothing more will come from a closed stdin, and the game never halts by itself
+        if n_read == 0 {
+            println!("\x1b[31;1mNo more commands, leaving the game\x1b[m");
+            return Ok(());
         }
-    }
-
-    canvas
-}
+        let cmd = buf.trim_end();
+        let Some(meta) = cmd.strip_prefix(':') else {
+            send(ascii, cmd, &mut session)?;
+            continue;
+        };
 
-fn print_canvas(canvas: &Vec<Vec<char>>) {
-    for row in canvas {
-        for c in row {
-            match c {
-                '#' => print!("\x1b[34m"),
-                '^' => print!("\x1b[31m"),
-                'v' => print!("\x1b[31m"),
-                '<' => print!("\x1b[31m"),
-                '>' => print!("\x1b[31m"),
-                _ => (),
+        let (name, arg) = match meta.split_once(' ') {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (meta, ""),
+        };
+        match name {
+            "record" if arg.is_empty() => println!("\x1b[31mUsage: :record NAME\x1b[m"),
+            "record" => {
+                if let Some(recording) = &session.recording {
+                    println!("\x1b[31mAlready recording {}\x1b[m", recording.name);
+                    continue;
+                }
+                println!("\x1b[33mRecording {arg}...\x1b[m");
+                session.recording = Some(Recording {
+                    name: arg.to_string(),
+                    commands: Vec::new(),
+                });
             }
-            print!("{c}\x1b[m");
-        }
-        println!();
-    }
-}
-
-fn send_input_cpu(cpu: &mut Cpu, input: &str) {
-    for c in input.chars() {
-        cpu.io_in.push_front(c as u8 as i64);
-    }
-    cpu.io_in.push_front(10);
-}
-
-fn print_cpu_ouput(cpu: &mut Cpu) {
-    while let Some(num) = cpu.io_out.pop_back() {
-        if (0..128).contains(&num) {
-            let c = num as u8 as char;
-            match c {
-                '#' => print!("\x1b[34m"),
-                '@' => print!("\x1b[31m"),
-                _ => (),
+            "stop" => {
+                let Some(Recording { name, commands }) = session.recording.take() else {
+                    println!("\x1b[31mNot recording\x1b[m");
+                    continue;
+                };
+                println!(
+                    "\x1b[33mRecorded {} commands as {name}\x1b[m",
+                    commands.len()
+                );
+                macros.insert(&name, commands);
+                if let Err(err) = macros.save(&files.macros) {
+                    let path = files.macros.display();
+                    println!("\x1b[31mFailed to save {path}: {err}\x1b[m");
+                }
             }
-            print!("{c}\x1b[m");
-        }
-    }
-}
-
-fn cpu_output_to_string(cpu: &mut Cpu) -> String {
-    let mut output: String = String::new();
-
-    while let Some(num) = cpu.io_out.pop_back() {
-        if (0..128).contains(&num) {
-            output.push(num as u8 as char);
-        } else {
-            println!("\x1b[31;1mOutput > \x1b[m{num}");
+            "play" => {
+                let Some(commands) = macros.get(arg) else {
+                    println!("\x1b[31mNo macro called {arg}\x1b[m");
+                    continue;
+                };
+                for cmd in commands {
+                    send(ascii, cmd, &mut session)?;
+                }
+            }
+            "macros" => {
+                for (name, commands) in macros.iter() {
+                    println!("  {name} ({} commands)", commands.len());
+                }
+            }
+            "history" => {
+                for (i, cmd) in session.history.iter().enumerate() {
+                    println!("{:4}  {cmd}", i + 1);
+                }
+            }
+            "map" => print_map(&session.map),
+            "goto" => {
+                let room = if arg.is_empty() { CHECKPOINT } else { arg };
+                let Some(route) = session.map.route(room) else {
+                    println!("\x1b[31mNo known route to {room}: see :map\x1b[m");
+                    continue;
+                };
+                for dir in route {
+                    send(ascii, &dir, &mut session)?;
+                }
+            }
+            "hack" => hack_weight(ascii, &mut session)?,
+            "profile" => match &ascii.cpu.profile {
+                Some(profile) => profile.print(ascii.cpu.memory(), PROFILE_WINDOW),
+                None => println!("\x1b[31mProfiling is off: pass `profile` after the input\x1b[m"),
+            },
+            "coverage" => {
+                if let Some(coverage) = ascii.cpu.coverage() {
+                    print_coverage(coverage, ascii.cpu.memory().allocated());
+                }
+            }
+            "watch" => match arg.parse::<usize>() {
+                Ok(addr) if ascii.cpu.watchpoints.remove(&addr).is_some() => {
+                    println!("\x1b[33mNo longer watching {addr}\x1b[m");
+                }
+                Ok(addr) => {
+                    ascii.cpu.watchpoints.insert(addr, Watch::Access);
+                    println!("\x1b[33mWatching {addr}\x1b[m");
+                }
+                Err(_) => println!("\x1b[31mUsage: :watch ADDR\x1b[m"),
+            },
+            "save" => {
+                let Some(log) = &ascii.cpu.io_log else {
+                    continue;
+                };
+                let path = files.session.display();
+                match fs::write(&files.session, log.to_string()) {
+                    Ok(()) => println!("\x1b[33mSaved the session to {path}\x1b[m"),
+                    Err(err) => println!("\x1b[31mFailed to save {path}: {err}\x1b[m"),
+                }
+            }
+            _ => print_help(files),
         }
     }
-
-    output
 }
 
-fn get_inv(cpu: &mut Cpu) -> Vec<String> {
-    let mut inv: Vec<String> = Vec::new();
-
-    send_input_cpu(cpu, "inv");
-    cpu.run();
-
-    let out = cpu_output_to_string(cpu);
-    print!("{out}");
-    let inv_string = &out[(out.find("inventory:").expect("No inventory in output")
-        + "inventory:\n".len())
-        ..out.rfind("\n\n").expect("No double newline")];
-
-    for line in inv_string.lines() {
-        if let Some(start) = line.find("- ") {
-            inv.push(line[(start + "- ".len())..].to_string());
-        }
+fn print_coverage(coverage: &Coverage, allocated: usize) {
+    let ranges = coverage.code_ranges();
+    let code: usize = ranges.iter().map(|range| range.len()).sum();
+    println!(
+        "\x1b[33m{} instructions run, {code} of {allocated} cells are code\x1b[m",
+        coverage.instructions().count()
+    );
+    for range in ranges {
+        println!("  {:6} .. {:6}", range.start, range.end);
     }
-
-    inv
-}
-
-fn drop_item(cpu: &mut Cpu, held: &mut HashMap<&str, bool>, item: &str) {
-    let cmd = String::from("drop ") + item;
-    send_input_cpu(cpu, &cmd);
-    cpu.run();
-    print_cpu_ouput(cpu);
-    // sleep(Duration::from_millis(100));
-    *held.get_mut(item).expect("Item not in held map") = false;
 }
 
-fn take_item(cpu: &mut Cpu, held: &mut HashMap<&str, bool>, item: &str) {
-    let cmd = String::from("take ") + item;
-    send_input_cpu(cpu, &cmd);
-    cpu.run();
-    print_cpu_ouput(cpu);
-    // sleep(Duration::from_millis(100));
-    *held.get_mut(item).expect("Item not in held map") = true;
+// Plays back a session saved with :save, checking the game replies as it did then
+fn replay_session(ascii: &mut AsciiComputer, path: &Path) -> Result<(), AocError> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| AocError::io(format!("failed to read {}", path.display()), err))?;
+    let log: IoLog = text
+        .parse()
+        .map_err(|err| AocError::solver(format!("{}: {err}", path.display())))?;
+    ascii.cpu.replay(&log)?;
+    println!(
+        "\x1b[33mReplayed {} characters of input from {}\x1b[m",
+        log.inputs.len(),
+        path.display()
+    );
+    Ok(())
 }
 
-fn check_weight(cpu: &mut Cpu) -> Weight {
-    send_input_cpu(cpu, "north");
-    cpu.run();
-    let output = cpu_output_to_string(cpu);
-    print!("{output}");
-    // sleep(Duration::from_millis(100));
-    if output.contains("heavier") {
-        return Weight::Heavier;
-    } else if output.contains("lighter") {
-        return Weight::Lighter;
+// Returns the number of instructions the game ran
+fn solve(program: &[i64], files: &SaveFiles, profile: bool, replay: bool) -> Result<u64, AocError> {
+    let mut ascii = Cpu::builder()
+        .program(program)
+        .verbosity(Verbosity::Silent)
+        .profile(profile)
+        .coverage(true)
+        .io_log(true)
+        .ascii_mode()
+        .build()?;
+    if replay {
+        replay_session(&mut ascii, &files.session)?;
     }
-    Weight::Exact
+    let mut macros = Macros::load(&files.macros).unwrap_or_else(|err| {
+        println!("\x1b[31mIgnoring saved macros: {err}\x1b[m");
+        Macros::new()
+    });
+    run_game(&mut ascii, &mut macros, files)?;
+    Ok(ascii.cpu.retired())
 }
 
-fn try_items(
-    cpu: &mut Cpu,
-    items: &Vec<String>,
-    held: &mut HashMap<&str, bool>,
-    item: &str,
-    found: &mut bool,
-) {
-    if *found {
-        return;
-    }
-    match check_weight(cpu) {
-        Weight::Lighter => {
-            drop_item(cpu, held, item);
-            return;
-        }
-        Weight::Exact => {
-            *found = true;
-            return;
-        }
-        _ => (),
-    }
-
-    for item in items {
-        if !*held.get(&item[..]).expect("Item not in held map") {
-            take_item(cpu, held, item);
-            try_items(cpu, items, held, item, found);
-        }
-    }
-
-    drop_item(cpu, held, item);
+fn run(args: &[String]) -> Result<u64, AocError> {
+    let path = args
+        .get(1)
+        .ok_or_else(|| AocError::Usage(String::from("no input provided!")))?;
+    let mut program = read_intcode(path)?;
+    patch_from_args(args, &mut program)?;
+    let has_flag = |flag: &str| args[2..].iter().any(|arg| arg == flag);
+    let files = SaveFiles::beside(path);
+    solve(&program, &files, has_flag("profile"), has_flag("replay"))
 }
 
-fn hack_weight(cpu: &mut Cpu) {
-    let mut inv: Vec<String> = get_inv(cpu);
-    let mut held: HashMap<&str, bool> = HashMap::new();
-
-    for item in &inv {
-        println!("{item}");
-        held.insert(item, true);
-        drop_item(cpu, &mut held, item);
-    }
-    let mut found = false;
-    for item in &inv {
-        take_item(cpu, &mut held, item);
-        try_items(cpu, &inv, &mut held, item, &mut found);
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    match run(&args) {
+        Ok(instructions) => report_instructions(instructions),
+        Err(err) => err.exit(),
     }
 }
 
-fn collect_items(cpu: &mut Cpu) {
-    send_input_cpu(cpu, "east");
-    send_input_cpu(cpu, "take food ration");
-    send_input_cpu(cpu, "south");
-    send_input_cpu(cpu, "take prime number");
-    send_input_cpu(cpu, "north");
-    send_input_cpu(cpu, "east");
-    send_input_cpu(cpu, "take manifold");
-    send_input_cpu(cpu, "east");
-    send_input_cpu(cpu, "north");
-    send_input_cpu(cpu, "north");
-    send_input_cpu(cpu, "take fuel cell");
-    send_input_cpu(cpu, "south");
-    send_input_cpu(cpu, "east");
-    send_input_cpu(cpu, "take spool of cat6");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "south");
-    send_input_cpu(cpu, "east");
-    send_input_cpu(cpu, "take jam");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "north");
-    send_input_cpu(cpu, "north");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "take mug");
-    send_input_cpu(cpu, "east");
-    send_input_cpu(cpu, "north");
-    send_input_cpu(cpu, "east");
-    send_input_cpu(cpu, "east");
-    send_input_cpu(cpu, "take loom");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "south");
-    send_input_cpu(cpu, "south");
-    send_input_cpu(cpu, "west");
-    send_input_cpu(cpu, "north");
-    send_input_cpu(cpu, "west");
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn run_game(cpu: &mut Cpu) {
-    let mut buf: String = String::new();
-    loop {
-        buf.clear();
-        cpu.run();
-        print_cpu_ouput(cpu);
-        if let State::Halted = cpu.state {
-            println!("\x1b[31;1mGame Over!\x1b[m");
-            return;
-        }
-        stdin()
-            .read_line(&mut buf)
-            .expect("Failed to read line input");
-        let cmd = buf.trim_end();
-        if cmd == "HACK" {
-            hack_weight(cpu);
-        } else if cmd == "COLLECT" {
-            collect_items(cpu);
-        } else {
-            send_input_cpu(cpu, cmd);
-        }
-    }
-}
+    #[test]
+    fn save_files_sit_beside_the_input() {
+        let files = SaveFiles::beside("puzzles/day25/input.txt");
+        assert_eq!(files.macros, Path::new("puzzles/day25").join(MACRO_FILE));
+        assert_eq!(files.session, Path::new("puzzles/day25").join(SESSION_FILE));
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        println!("no input provided!");
-        return;
+        let files = SaveFiles::beside("-");
+        assert_eq!(files.macros, Path::new(MACRO_FILE));
     }
-
-    let input = get_input(&args[1]);
-
-    let program = get_program(input);
-    let mut cpu: Cpu = Cpu::new();
-    cpu.load_program(&program);
-    cpu.verbose = false;
-    run_game(&mut cpu);
 }
@@ -0,0 +1,214 @@
+use std::collections::{BTreeMap, HashMap};
+
+use utils::pathfinding::bfs;
+
+pub const CHECKPOINT: &str = "Security Checkpoint";
+
+const DIRECTIONS: [&str; 4] = ["north", "east", "south", "west"];
+
+fn opposite(dir: &str) -> Option<&'static str> {
+    let i = DIRECTIONS.iter().position(|d| *d == dir)?;
+    Some(DIRECTIONS[(i + 2) % 4])
+}
+
+// Every room described in some output, with the doors listed under it:
+//
+//     == Hull Breach ==
+//     You got in through a hole in the floor here.
+//
+//     Doors here lead:
+//     - north
+//     - east
+fn parse_rooms(output: &str) -> Vec<(String, Vec<String>)> {
+    let mut rooms: Vec<(String, Vec<String>)> = Vec::new();
+    let mut in_doors = false;
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("== ").and_then(|l| l.strip_suffix(" ==")) {
+            rooms.push((name.to_string(), Vec::new()));
+            in_doors = false;
+        } else if line == "Doors here lead:" {
+            in_doors = true;
+        } else if in_doors
+            && let Some(door) = line.strip_prefix("- ")
+            && let Some((_, doors)) = rooms.last_mut()
+        {
+            doors.push(door.to_string());
+        } else {
+            in_doors = false;
+        }
+    }
+    rooms
+}
+
+// The rooms of the ship seen so far, pieced together from the game's output as the
+// droid moves. Doors work both ways, so each move links the two rooms both ways.
+#[derive(Default)]
+pub struct RoomMap {
+    // Doors listed in each room, in the game's order
+    doors: BTreeMap<String, Vec<String>>,
+    // Where each door that has been walked through leads
+    links: HashMap<(String, String), String>,
+    current: Option<String>,
+}
+
+impl RoomMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Notes the rooms in the output of `cmd`. Walking onto the pressure-sensitive floor
+    // with the wrong weight describes the floor and then the checkpoint the droid is
+    // thrown back to, so the door leads to the first room and the droid ends up in
+    // the last.
+    pub fn observe(&mut self, cmd: &str, output: &str) {
+        let rooms = parse_rooms(output);
+        let Some((first, _)) = rooms.first() else {
+            return;
+        };
+        if let Some(from) = self.current.take()
+            && let Some(back) = opposite(cmd)
+        {
+            self.links
+                .insert((from.clone(), cmd.to_string()), first.clone());
+            self.links.insert((first.clone(), back.to_string()), from);
+        }
+        self.current = rooms.last().map(|(name, _)| name.clone());
+        for (name, doors) in rooms {
+            self.doors.insert(name, doors);
+        }
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    // Known rooms behind each door of `room`, `None` for doors not yet walked through
+    pub fn exits(&self, room: &str) -> Vec<(&str, Option<&str>)> {
+        let Some(doors) = self.doors.get(room) else {
+            return Vec::new();
+        };
+        doors
+            .iter()
+            .map(|door| {
+                let next = self.links.get(&(room.to_string(), door.clone()));
+                (door.as_str(), next.map(|next| next.as_str()))
+            })
+            .collect()
+    }
+
+    pub fn rooms(&self) -> impl Iterator<Item = &str> {
+        self.doors.keys().map(|name| name.as_str())
+    }
+
+    // The fewest moves from the current room to the room called `to`, ignoring case.
+    // Empty if already there.
+    pub fn route(&self, to: &str) -> Option<Vec<String>> {
+        let from = self.current.clone()?;
+        let path = bfs(
+            from,
+            |room| {
+                self.exits(room)
+                    .into_iter()
+                    .filter_map(|(_, next)| next.map(String::from))
+                    .collect::<Vec<_>>()
+            },
+            |room| room.eq_ignore_ascii_case(to),
+        )?;
+        path.windows(2)
+            .map(|pair| {
+                let (door, _) = self
+                    .exits(&pair[0])
+                    .into_iter()
+                    .find(|(_, next)| *next == Some(pair[1].as_str()))?;
+                Some(door.to_string())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(name: &str, doors: &[&str]) -> String {
+        let mut text = format!("\n\n\n== {name} ==\nA room.\n\nDoors here lead:\n");
+        for door in doors {
+            text.push_str(&format!("- {door}\n"));
+        }
+        text.push_str("\nItems here:\n- mug\n\nCommand?\n");
+        text
+    }
+
+    // Hull Breach -east-> Kitchen -north-> Stables, and Hull Breach -south-> Lab
+    fn explored() -> RoomMap {
+        let mut map = RoomMap::new();
+        map.observe("", &room("Hull Breach", &["east", "south"]));
+        map.observe("east", &room("Kitchen", &["north", "west"]));
+        map.observe("north", &room("Stables", &["south"]));
+        map.observe("south", &room("Kitchen", &["north", "west"]));
+        map.observe("west", &room("Hull Breach", &["east", "south"]));
+        map.observe("south", &room("Lab", &["north"]));
+        map
+    }
+
+    #[test]
+    fn parses_doors_but_not_items() {
+        let rooms = parse_rooms(&room("Hull Breach", &["north", "east"]));
+        assert_eq!(
+            rooms,
+            [(
+                String::from("Hull Breach"),
+                vec![String::from("north"), String::from("east")]
+            )]
+        );
+    }
+
+    #[test]
+    fn moves_link_rooms_both_ways() {
+        let map = explored();
+
+        assert_eq!(map.current(), Some("Lab"));
+        assert_eq!(
+            map.exits("Hull Breach"),
+            [("east", Some("Kitchen")), ("south", Some("Lab"))]
+        );
+        assert_eq!(map.exits("Lab"), [("north", Some("Hull Breach"))]);
+        assert_eq!(
+            map.rooms().collect::<Vec<_>>(),
+            ["Hull Breach", "Kitchen", "Lab", "Stables"]
+        );
+    }
+
+    #[test]
+    fn unexplored_doors_lead_nowhere_yet() {
+        let mut map = RoomMap::new();
+        map.observe("", &room("Hull Breach", &["east", "south"]));
+
+        assert_eq!(map.exits("Hull Breach"), [("east", None), ("south", None)]);
+        assert_eq!(map.route("Kitchen"), None);
+    }
+
+    #[test]
+    fn routes_take_the_fewest_moves() {
+        let map = explored();
+        let route = map.route("stables").unwrap();
+
+        assert_eq!(route, ["north", "east", "north"]);
+        assert_eq!(map.route("Lab"), Some(Vec::new()));
+    }
+
+    #[test]
+    fn ejected_from_the_floor_ends_at_the_checkpoint() {
+        let mut map = RoomMap::new();
+        map.observe("", &room(CHECKPOINT, &["north", "south"]));
+        let mut output = room("Pressure-Sensitive Floor", &["south"]);
+        output.push_str(&room(CHECKPOINT, &["north", "south"]));
+        map.observe("south", &output);
+
+        assert_eq!(map.current(), Some(CHECKPOINT));
+        assert_eq!(
+            map.exits(CHECKPOINT),
+            [("north", None), ("south", Some("Pressure-Sensitive Floor"))]
+        );
+    }
+}
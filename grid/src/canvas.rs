@@ -0,0 +1,13 @@
+/// Prints a rendered canvas, colouring each char listed in `palette` with its
+/// escape sequence.
+pub fn print_canvas(canvas: &[Vec<char>], palette: &[(char, &str)]) {
+    for row in canvas {
+        for c in row {
+            if let Some((_, colour)) = palette.iter().find(|(p, _)| p == c) {
+                print!("{colour}");
+            }
+            print!("{c}\x1b[m");
+        }
+        println!();
+    }
+}
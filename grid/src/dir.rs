@@ -0,0 +1,51 @@
+use crate::Point2;
+
+/// A compass direction in screen orientation: y grows downwards, so North is -y.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Dir {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::North, Dir::East, Dir::South, Dir::West];
+
+    /// The unit step taken by moving one cell this way.
+    pub fn delta(self) -> Point2 {
+        match self {
+            Dir::North => Point2::new(0, -1),
+            Dir::East => Point2::new(1, 0),
+            Dir::South => Point2::new(0, 1),
+            Dir::West => Point2::new(-1, 0),
+        }
+    }
+
+    pub fn turn_left(self) -> Dir {
+        match self {
+            Dir::North => Dir::West,
+            Dir::East => Dir::North,
+            Dir::South => Dir::East,
+            Dir::West => Dir::South,
+        }
+    }
+
+    pub fn turn_right(self) -> Dir {
+        match self {
+            Dir::North => Dir::East,
+            Dir::East => Dir::South,
+            Dir::South => Dir::West,
+            Dir::West => Dir::North,
+        }
+    }
+
+    pub fn reverse(self) -> Dir {
+        match self {
+            Dir::North => Dir::South,
+            Dir::East => Dir::West,
+            Dir::South => Dir::North,
+            Dir::West => Dir::East,
+        }
+    }
+}
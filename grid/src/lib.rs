@@ -0,0 +1,15 @@
+//! 2D geometry and terminal drawing helpers for the grid-based puzzles.
+//!
+//! Coordinates are screen oriented: x grows to the right and y grows downwards.
+
+mod canvas;
+mod dir;
+mod point;
+mod sparse;
+
+pub mod prelude;
+
+pub use canvas::print_canvas;
+pub use dir::Dir;
+pub use point::{BoundingBox, Point2};
+pub use sparse::SparseGrid;
@@ -0,0 +1,122 @@
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+
+use crate::Dir;
+
+/// A position or offset on an integer grid.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Default)]
+pub struct Point2 {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point2 {
+    pub const ORIGIN: Point2 = Point2 { x: 0, y: 0 };
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan(self, other: Point2) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    pub fn step(self, dir: Dir) -> Point2 {
+        self + dir.delta()
+    }
+
+    /// The four orthogonally adjacent points, in [`Dir::ALL`] order.
+    pub fn neighbours(self) -> [Point2; 4] {
+        Dir::ALL.map(|dir| self.step(dir))
+    }
+}
+
+impl From<(i64, i64)> for Point2 {
+    fn from((x, y): (i64, i64)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Add for Point2 {
+    type Output = Point2;
+
+    fn add(self, rhs: Point2) -> Point2 {
+        Point2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point2 {
+    type Output = Point2;
+
+    fn sub(self, rhs: Point2) -> Point2 {
+        Point2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<i64> for Point2 {
+    type Output = Point2;
+
+    fn mul(self, rhs: i64) -> Point2 {
+        Point2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl AddAssign for Point2 {
+    fn add_assign(&mut self, rhs: Point2) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Point2 {
+    fn sub_assign(&mut self, rhs: Point2) {
+        *self = *self - rhs;
+    }
+}
+
+/// Smallest box containing a set of points, with both corners inclusive.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BoundingBox {
+    pub min: Point2,
+    pub max: Point2,
+}
+
+impl BoundingBox {
+    /// The box around every point, or `None` if there are none.
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point2>,
+    {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let mut bbox = BoundingBox {
+            min: first,
+            max: first,
+        };
+
+        for point in points {
+            bbox.include(point);
+        }
+
+        Some(bbox)
+    }
+
+    pub fn include(&mut self, point: Point2) {
+        self.min.x = self.min.x.min(point.x);
+        self.min.y = self.min.y.min(point.y);
+        self.max.x = self.max.x.max(point.x);
+        self.max.y = self.max.y.max(point.y);
+    }
+
+    pub fn contains(&self, point: Point2) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    /// Number of columns covered, counting both edges.
+    pub fn width(&self) -> i64 {
+        self.max.x - self.min.x + 1
+    }
+
+    /// Number of rows covered, counting both edges.
+    pub fn height(&self) -> i64 {
+        self.max.y - self.min.y + 1
+    }
+}
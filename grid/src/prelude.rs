@@ -0,0 +1,3 @@
+//! Everything needed to build and draw a grid: `use grid::prelude::*;`
+
+pub use crate::{BoundingBox, Dir, Point2, SparseGrid, print_canvas};
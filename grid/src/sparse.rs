@@ -0,0 +1,94 @@
+use std::collections::{HashMap, hash_map};
+
+use crate::{BoundingBox, Point2};
+
+/// Cells keyed by position, for maps that grow in any direction as they are explored.
+#[derive(Clone)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point2, T>,
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, pos: Point2, val: T) -> Option<T> {
+        self.cells.insert(pos, val)
+    }
+
+    pub fn get(&self, pos: Point2) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    pub fn get_mut(&mut self, pos: Point2) -> Option<&mut T> {
+        self.cells.get_mut(&pos)
+    }
+
+    pub fn contains(&self, pos: Point2) -> bool {
+        self.cells.contains_key(&pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<'_, Point2, T> {
+        self.cells.iter()
+    }
+
+    pub fn values(&self) -> hash_map::Values<'_, Point2, T> {
+        self.cells.values()
+    }
+
+    /// The smallest box holding every cell, or `None` for an empty grid.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(self.cells.keys().copied())
+    }
+
+    /// Draws every cell with `draw` onto a canvas covering the bounding box, with
+    /// `blank` for positions that have no cell. Row 0 is the lowest y.
+    pub fn render<F>(&self, blank: char, draw: F) -> Vec<Vec<char>>
+    where
+        F: Fn(&T) -> char,
+    {
+        let Some(bbox) = self.bounds() else {
+            return Vec::new();
+        };
+        let n_rows = bbox.height() as usize;
+        let n_cols = bbox.width() as usize;
+        let mut canvas: Vec<Vec<char>> = vec![vec![blank; n_cols]; n_rows];
+
+        for (&pos, val) in &self.cells {
+            let offset = pos - bbox.min;
+            canvas[offset.y as usize][offset.x as usize] = draw(val);
+        }
+
+        canvas
+    }
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SparseGrid<T> {
+    type Item = (&'a Point2, &'a T);
+    type IntoIter = hash_map::Iter<'a, Point2, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cells.iter()
+    }
+}
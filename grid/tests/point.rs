@@ -0,0 +1,77 @@
+use grid::{BoundingBox, Dir, Point2};
+
+#[test]
+fn bounding_box_spans_mixed_sign_points() {
+    let points = [
+        Point2::new(3, -2),
+        Point2::new(-5, 4),
+        Point2::new(0, 0),
+        Point2::new(1, -7),
+    ];
+    let bbox = BoundingBox::from_points(points).unwrap();
+
+    assert_eq!(bbox.min, Point2::new(-5, -7));
+    assert_eq!(bbox.max, Point2::new(3, 4));
+    assert!(points.iter().all(|&point| bbox.contains(point)));
+    assert!(!bbox.contains(Point2::new(4, 0)));
+    assert_eq!(BoundingBox::from_points(Vec::new()), None);
+}
+
+#[test]
+fn width_and_height_are_inclusive() {
+    let single = BoundingBox::from_points([Point2::new(-1, -1)]).unwrap();
+    assert_eq!((single.width(), single.height()), (1, 1));
+
+    let bbox = BoundingBox::from_points([Point2::new(-2, 5), Point2::new(2, 6)]).unwrap();
+    assert_eq!((bbox.width(), bbox.height()), (5, 2));
+}
+
+#[test]
+fn point_arithmetic() {
+    let a = Point2::new(2, -3);
+    let b = Point2::new(-4, 1);
+
+    assert_eq!(a + b, Point2::new(-2, -2));
+    assert_eq!(a - b, Point2::new(6, -4));
+    assert_eq!(b * 3, Point2::new(-12, 3));
+
+    let mut c = a;
+    c += b;
+    c -= a;
+    assert_eq!(c, b);
+}
+
+#[test]
+fn manhattan_distance() {
+    let a = Point2::new(2, -3);
+    let b = Point2::new(-4, 1);
+
+    assert_eq!(a.manhattan(b), 10);
+    assert_eq!(b.manhattan(a), 10);
+    assert_eq!(a.manhattan(a), 0);
+    assert_eq!(Point2::ORIGIN.manhattan(Point2::new(-3, -4)), 7);
+}
+
+#[test]
+fn steps_follow_screen_orientation() {
+    let p = Point2::new(5, 5);
+
+    assert_eq!(p.step(Dir::North), Point2::new(5, 4));
+    assert_eq!(p.step(Dir::East), Point2::new(6, 5));
+    assert_eq!(p.step(Dir::South), Point2::new(5, 6));
+    assert_eq!(p.step(Dir::West), Point2::new(4, 5));
+    assert_eq!(p.neighbours(), Dir::ALL.map(|dir| p.step(dir)));
+}
+
+#[test]
+fn turns() {
+    for dir in Dir::ALL {
+        assert_eq!(dir.turn_left().turn_right(), dir);
+        assert_eq!(dir.turn_right().turn_right(), dir.reverse());
+        assert_eq!(dir.reverse().reverse(), dir);
+        assert_eq!(dir.delta() + dir.reverse().delta(), Point2::ORIGIN);
+    }
+    assert_eq!(Dir::North.turn_right(), Dir::East);
+    assert_eq!(Dir::North.turn_left(), Dir::West);
+    assert_eq!(Dir::West.turn_right(), Dir::North);
+}
@@ -0,0 +1,53 @@
+use grid::{BoundingBox, Point2, SparseGrid};
+
+#[test]
+fn empty_grid_has_no_bounds() {
+    let grid: SparseGrid<char> = SparseGrid::new();
+
+    assert_eq!(grid.bounds(), None);
+    assert!(grid.render(' ', |c| *c).is_empty());
+}
+
+#[test]
+fn single_cell() {
+    let mut grid = SparseGrid::new();
+    grid.insert(Point2::new(-4, 7), '#');
+
+    let bbox = grid.bounds().unwrap();
+    assert_eq!(bbox.min, Point2::new(-4, 7));
+    assert_eq!(bbox.max, Point2::new(-4, 7));
+    assert_eq!(grid.render('.', |c| *c), vec![vec!['#']]);
+}
+
+#[test]
+fn negative_coordinates_are_offset_to_the_top_left() {
+    let mut grid = SparseGrid::new();
+    grid.insert(Point2::new(-2, -1), 1);
+    grid.insert(Point2::new(1, 0), 2);
+    grid.insert(Point2::new(0, 1), 3);
+
+    assert_eq!(
+        grid.bounds(),
+        Some(BoundingBox {
+            min: Point2::new(-2, -1),
+            max: Point2::new(1, 1),
+        })
+    );
+
+    let canvas = grid.render('.', |n| char::from(b'0' + *n as u8));
+    let rows: Vec<String> = canvas.iter().map(|row| row.iter().collect()).collect();
+    assert_eq!(rows, ["1...", "...2", "..3."]);
+}
+
+#[test]
+fn cells_can_be_replaced_and_cleared() {
+    let mut grid = SparseGrid::new();
+    assert_eq!(grid.insert(Point2::ORIGIN, 'a'), None);
+    assert_eq!(grid.insert(Point2::ORIGIN, 'b'), Some('a'));
+    assert_eq!(grid.get(Point2::ORIGIN), Some(&'b'));
+    assert_eq!(grid.len(), 1);
+
+    grid.clear();
+    assert!(grid.is_empty());
+    assert!(!grid.contains(Point2::ORIGIN));
+}
@@ -0,0 +1,112 @@
+// What logging costs a run, on workloads shaped like day 19 (many short runs of a
+// drone program, logging its IO) and day 23 (a NIC spinning in a busy loop, traced).
+// Each is run silent, through the buffered Ansi logger, and through a logger making
+// one write per line, as Ansi did before it was buffered. Lines go to /dev/null, so
+// the writes cost a syscall each without flooding the terminal.
+//
+//     cargo bench -p intcode --bench logging
+
+use std::{
+    fs::{File, OpenOptions},
+    hint::black_box,
+    io::Write,
+};
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use intcode::{Ansi, Cpu, LogEvent, Verbosity};
+
+const MEMORY: usize = 64;
+const GRID: i64 = 20;
+const SPINS: i64 = 2000;
+
+// Reads x and y and outputs whether x * y < 100, like a day 19 drone asked about a cell
+const DRONE: [i64; 16] = [3, 30, 3, 31, 2, 30, 31, 32, 1007, 32, 100, 32, 4, 32, 99, 0];
+// Counts down from SPINS, then outputs and halts, like an idle day 23 NIC
+const NIC: [i64; 12] = [1101, SPINS, 0, 30, 1001, 30, -1, 30, 1005, 30, 4, 99];
+
+#[derive(Clone, Copy)]
+enum Logging {
+    Silent,
+    Buffered,
+    PerLine,
+}
+
+impl Logging {
+    const ALL: [(Logging, &str); 3] = [
+        (Logging::Silent, "silent"),
+        (Logging::Buffered, "buffered"),
+        (Logging::PerLine, "per_line"),
+    ];
+}
+
+fn dev_null() -> File {
+    OpenOptions::new()
+        .write(true)
+        .open("/dev/null")
+        .expect("Failed to open /dev/null")
+}
+
+fn build(program: &[i64], logging: Logging, verbosity: Verbosity) -> Cpu {
+    let builder = Cpu::builder().program(program).memory_limit(MEMORY);
+    let builder = match logging {
+        Logging::Silent => builder.verbosity(Verbosity::Silent),
+        Logging::Buffered => builder
+            .verbosity(verbosity)
+            .logger(Ansi::with_writer(dev_null())),
+        Logging::PerLine => {
+            let mut out = dev_null();
+            builder.verbosity(verbosity).logger(move |event: LogEvent| {
+                let _ = out.write_all(format!("{event:?}\n").as_bytes());
+            })
+        }
+    };
+    builder.build().unwrap()
+}
+
+fn drone_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("logging/drone_scan");
+    group.throughput(Throughput::Elements((GRID * GRID) as u64));
+    for (logging, name) in Logging::ALL {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || build(&DRONE, logging, Verbosity::Io),
+                |mut cpu| {
+                    let mut pulled = 0;
+                    for y in 0..GRID {
+                        for x in 0..GRID {
+                            cpu.reset();
+                            cpu.io_in.push_front(x);
+                            cpu.io_in.push_front(y);
+                            cpu.run().unwrap();
+                            pulled += cpu.read_output().unwrap();
+                        }
+                    }
+                    black_box(pulled)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn nic_spin(c: &mut Criterion) {
+    let mut group = c.benchmark_group("logging/nic_spin");
+    group.throughput(Throughput::Elements(SPINS as u64 * 2));
+    for (logging, name) in Logging::ALL {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || build(&NIC, logging, Verbosity::Trace),
+                |mut cpu| {
+                    cpu.run().unwrap();
+                    black_box(cpu)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, drone_scan, nic_spin);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+// Tight loops dominated by a single kind of instruction, so a change to decoding or
+// operand handling shows up against the opcodes it affects.
+//
+//     cargo bench -p intcode --bench opcodes
+
+use std::hint::black_box;
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use intcode::{Cpu, Verbosity};
+
+// Memory is kept small so that loading a program costs next to nothing
+const MEMORY: usize = 256;
+const COUNTER: i64 = 200;
+const A: i64 = 201;
+const B: i64 = 202;
+const TARGET: i64 = 203;
+const ITERATIONS: i64 = 1000;
+// Copies of the instruction under test per loop iteration
+const UNROLL: usize = 16;
+
+// Wraps `UNROLL` copies of `body` in a counted loop. `body` gets the address it starts
+// at, for instructions that jump.
+fn looped(prologue: &[i64], body: impl Fn(usize) -> Vec<i64>) -> Vec<i64> {
+    let mut program = vec![1101, ITERATIONS, 0, COUNTER];
+    program.extend_from_slice(prologue);
+    let start = program.len() as i64;
+    for _ in 0..UNROLL {
+        program.extend(body(program.len()));
+    }
+    program.extend([1001, COUNTER, -1, COUNTER, 1005, COUNTER, start, 99]);
+    assert!(program.len() < COUNTER as usize, "Loop overlaps its data");
+    program
+}
+
+fn bench_program(c: &mut Criterion, name: &str, program: &[i64], inputs: usize) {
+    let mut group = c.benchmark_group("opcodes");
+    group.throughput(Throughput::Elements(ITERATIONS as u64 * UNROLL as u64));
+    group.bench_function(name, |b| {
+        b.iter_batched(
+            || {
+                let mut cpu = Cpu::builder()
+                    .program(program)
+                    .memory_limit(MEMORY)
+                    .verbosity(Verbosity::Silent)
+                    .input(&vec![7; inputs])
+                    .build()
+                    .unwrap();
+                cpu.poke(A as usize, 3).unwrap();
+                cpu.poke(B as usize, 4).unwrap();
+                cpu
+            },
+            |mut cpu| {
+                cpu.run().unwrap();
+                black_box(cpu)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn opcodes(c: &mut Criterion) {
+    // Just the loop counter, to subtract from the others
+    bench_program(c, "loop", &looped(&[], |_| Vec::new()), 0);
+    bench_program(c, "add", &looped(&[], |_| vec![1, A, B, TARGET]), 0);
+    bench_program(c, "mul", &looped(&[], |_| vec![2, A, B, TARGET]), 0);
+    bench_program(
+        c,
+        "add_immediate",
+        &looped(&[], |_| vec![1101, 3, 4, TARGET]),
+        0,
+    );
+    // Always taken, to the next instruction
+    bench_program(c, "jnz", &looped(&[], |ip| vec![1105, 1, ip as i64 + 3]), 0);
+    // Base pointer at the data, so relative operands 1, 2 and 3 are A, B and TARGET
+    bench_program(
+        c,
+        "add_relative",
+        &looped(&[109, COUNTER], |_| vec![22201, 1, 2, 3]),
+        0,
+    );
+    let io = ITERATIONS as usize * UNROLL;
+    bench_program(c, "in_out", &looped(&[], |_| vec![3, A, 4, A]), io);
+}
+
+criterion_group!(benches, opcodes);
+criterion_main!(benches);
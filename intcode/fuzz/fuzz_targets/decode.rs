@@ -0,0 +1,38 @@
+//! Garbage programs must make the interpreter return errors, never panic or index out
+//! of bounds. Run with `cargo +nightly fuzz run decode` from `intcode/`.
+#![no_main]
+
+use intcode::{Cpu, Verbosity};
+use libfuzzer_sys::fuzz_target;
+
+// Enough to get through decoding and a few loops, while keeping runs fast
+const INSTRUCTIONS: u64 = 10_000;
+// Small enough that a wild write can't take all the fuzzer's memory
+const MEMORY_LIMIT: usize = 1 << 16;
+
+fuzz_target!(|data: (Vec<i64>, Vec<i64>)| {
+    let (program, input) = data;
+    if program.is_empty() {
+        return;
+    }
+
+    // Decoding alone, over every cell
+    let mut cpu = Cpu::with_memory_limit(program.len());
+    if cpu.load_program(&program).is_ok() {
+        let _ = cpu.disassemble(0, program.len());
+    }
+
+    // Running, both with memory capped and growing as the program asks
+    for limit in [Some(MEMORY_LIMIT), None] {
+        let mut builder = Cpu::builder()
+            .program(&program)
+            .verbosity(Verbosity::Silent)
+            .input(&input);
+        if let Some(limit) = limit {
+            builder = builder.memory_limit(limit);
+        }
+        if let Ok(mut cpu) = builder.build() {
+            let _ = cpu.run_with_limit(INSTRUCTIONS);
+        }
+    }
+});
@@ -0,0 +1,98 @@
+use crate::{Cpu, IntcodeError, RunResult, State};
+
+/// A [`Cpu`] running a program that talks in ASCII text, like days 17, 21 and 25.
+///
+/// Output values outside the ASCII range (usually the puzzle answer) are kept apart
+/// from the text and can be collected with [`AsciiComputer::take_values`].
+pub struct AsciiComputer {
+    pub cpu: Cpu,
+    values: Vec<i64>,
+    // text read since the last write, used to find the prompt
+    pending: String,
+}
+
+impl AsciiComputer {
+    pub fn new(cpu: Cpu) -> Self {
+        Self {
+            cpu,
+            values: Vec::new(),
+            pending: String::new(),
+        }
+    }
+
+    pub fn load_program(&mut self, program: &[i64]) -> Result<(), IntcodeError> {
+        self.cpu.load_program(program)?;
+        self.values.clear();
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Puts back the program last loaded; see [`Cpu::reset`].
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+        self.values.clear();
+        self.pending.clear();
+    }
+
+    pub fn run(&mut self) -> Result<RunResult, IntcodeError> {
+        self.cpu.run()
+    }
+
+    pub fn run_with_limit(&mut self, max_instructions: u64) -> Result<RunResult, IntcodeError> {
+        self.cpu.run_with_limit(max_instructions)
+    }
+
+    pub fn write_str(&mut self, text: &str) {
+        for c in text.bytes() {
+            self.cpu.io_in.push_front(c as i64);
+        }
+        self.pending.clear();
+    }
+
+    /// Queues `line` followed by a newline.
+    pub fn write_line(&mut self, line: &str) {
+        self.cpu.push_ascii_line(line);
+        self.pending.clear();
+    }
+
+    /// Drains the output queue as text.
+    pub fn read_output_string(&mut self) -> String {
+        let output = self.cpu.drain_ascii_output();
+        self.values.extend(self.cpu.drain_values());
+        self.pending.push_str(&output);
+
+        output
+    }
+
+    /// Non-ASCII values output since the last call.
+    pub fn take_values(&mut self) -> Vec<i64> {
+        std::mem::take(&mut self.values)
+    }
+
+    /// Writes `line`, if any, then runs until the program halts or needs another line,
+    /// and returns everything it has printed since the last line was written. Pauses
+    /// at breakpoints or after yielding outputs are run through, so the reply is never
+    /// cut short. Check `cpu.state` to tell whether it halted.
+    pub fn converse(&mut self, line: Option<&str>) -> Result<String, IntcodeError> {
+        if let Some(line) = line {
+            self.write_line(line);
+        }
+        while !matches!(self.cpu.run()?, RunResult::Halted | RunResult::NeedsInput) {}
+        self.read_output_string();
+        Ok(self.pending.clone())
+    }
+
+    /// If the program is waiting for input, the last line it printed before asking.
+    pub fn prompt(&self) -> Option<&str> {
+        let State::Ready = self.cpu.state else {
+            return None;
+        };
+        if !self.cpu.io_in.is_empty() {
+            return None;
+        }
+        self.pending
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+    }
+}
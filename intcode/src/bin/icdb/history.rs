@@ -0,0 +1,229 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+};
+
+use intcode::{Cpu, CpuSnapshot, IntcodeError, Io, RunResult, SliceResult, StepEvent};
+
+// Instructions between snapshots. Going back replays at most this many from the nearest
+// snapshot before the target.
+const SNAPSHOT_EVERY: u64 = 10_000;
+
+// Kept up to date by the hooks History installs
+pub struct Clock {
+    // Instructions run since the program was loaded, counting one that failed
+    tick: Cell<u64>,
+    // Set while replaying, so outputs aren't shown again and inputs aren't logged again
+    replaying: Cell<bool>,
+    // Each value read, with the tick of the instruction that read it
+    inputs: RefCell<Vec<(u64, i64)>>,
+}
+
+impl Clock {
+    pub fn tick(&self) -> u64 {
+        self.tick.get()
+    }
+
+    pub fn replaying(&self) -> bool {
+        self.replaying.get()
+    }
+
+    // An input instruction that found nothing to read didn't run after all
+    fn unstart(&self) {
+        self.tick.set(self.tick.get() - 1);
+    }
+}
+
+/// Lets the debugger run a machine backwards. Snapshots are taken as it runs and every
+/// value it reads is logged, so any earlier point can be rebuilt by restoring the
+/// snapshot before it and replaying forward.
+pub struct History {
+    clock: Rc<Clock>,
+    // In tick order, starting with the freshly loaded machine
+    snapshots: Vec<(u64, CpuSnapshot)>,
+}
+
+impl History {
+    pub fn new(cpu: &mut Cpu) -> Self {
+        let clock = Rc::new(Clock {
+            tick: Cell::new(0),
+            replaying: Cell::new(false),
+            inputs: RefCell::new(Vec::new()),
+        });
+
+        let ticks = Rc::clone(&clock);
+        cpu.on_before_instruction(move |_| ticks.tick.set(ticks.tick.get() + 1));
+        let reads = Rc::clone(&clock);
+        cpu.on_io(move |event| {
+            if let Io::Input(value) = event
+                && !reads.replaying()
+            {
+                reads.inputs.borrow_mut().push((reads.tick(), value));
+            }
+        });
+
+        Self {
+            clock,
+            snapshots: vec![(0, cpu.snapshot())],
+        }
+    }
+
+    pub fn clock(&self) -> Rc<Clock> {
+        Rc::clone(&self.clock)
+    }
+
+    pub fn step(&mut self, cpu: &mut Cpu) -> Result<StepEvent, IntcodeError> {
+        let event = cpu.step()?;
+        if event == StepEvent::NeedsInput {
+            self.clock.unstart();
+        }
+        self.checkpoint(cpu);
+        Ok(event)
+    }
+
+    /// Like [`Cpu::run`], in slices so snapshots can be taken along the way.
+    pub fn run(&mut self, cpu: &mut Cpu) -> Result<RunResult, IntcodeError> {
+        loop {
+            let slice = cpu.run_for(SNAPSHOT_EVERY)?;
+            if slice == SliceResult::Stopped(RunResult::NeedsInput) {
+                self.clock.unstart();
+            }
+            self.checkpoint(cpu);
+            if let SliceResult::Stopped(result) = slice {
+                return Ok(result);
+            }
+        }
+    }
+
+    fn checkpoint(&mut self, cpu: &Cpu) {
+        let tick = self.clock.tick();
+        let (last, _) = self.snapshots[self.snapshots.len() - 1];
+        if tick >= last + SNAPSHOT_EVERY {
+            self.snapshots.push((tick, cpu.snapshot()));
+        }
+    }
+
+    /// Goes back `count` instructions, or to the start.
+    pub fn reverse_step(&mut self, cpu: &mut Cpu, count: u64) {
+        let queued = cpu.io_in.clone();
+        self.travel(cpu, self.clock.tick().saturating_sub(count), &queued);
+    }
+
+    /// Goes back to the latest point where running forward would have stopped at a
+    /// breakpoint or watchpoint, and says which. `None` if it went back to the start.
+    pub fn reverse_continue(&mut self, cpu: &mut Cpu) -> Option<RunResult> {
+        let queued = cpu.io_in.clone();
+        let now = self.clock.tick();
+        let mut end = now;
+        for i in (0..self.snapshots.len()).rev() {
+            let start = self.snapshots[i].0;
+            if start >= end {
+                continue;
+            }
+            if let Some((tick, stop)) = self.last_stop(cpu, i, end.min(now - 1), &queued) {
+                self.travel(cpu, tick, &queued);
+                // Continuing from here carries on past the breakpoint just reported
+                if let RunResult::Breakpoint(_) = stop {
+                    cpu.resume_from_breakpoint();
+                }
+                return Some(stop);
+            }
+            end = start;
+        }
+        self.travel(cpu, 0, &queued);
+        None
+    }
+
+    // The latest stop from snapshot i up to tick `last_tick`
+    fn last_stop(
+        &self,
+        cpu: &mut Cpu,
+        i: usize,
+        last_tick: u64,
+        queued: &VecDeque<i64>,
+    ) -> Option<(u64, RunResult)> {
+        let profile = cpu.profile.take();
+        self.rewind(cpu, i, queued);
+        let mut last = None;
+        loop {
+            let tick = self.clock.tick();
+            if tick > 0 && cpu.at_breakpoint() {
+                last = Some((tick, RunResult::Breakpoint(cpu.ip)));
+            }
+            if tick == last_tick {
+                break;
+            }
+            // A single instruction, so a watchpoint it sets off is reported. Breakpoints
+            // are noted above rather than stopping it.
+            cpu.resume_from_breakpoint();
+            match cpu.run_for(1) {
+                Ok(SliceResult::Stopped(RunResult::Watchpoint(addr))) => {
+                    last = Some((tick + 1, RunResult::Watchpoint(addr)));
+                }
+                Ok(SliceResult::Stopped(RunResult::NeedsInput)) => {
+                    self.clock.unstart();
+                    break;
+                }
+                Ok(_) => (),
+                Err(_) => break,
+            }
+        }
+        self.clock.replaying.set(false);
+        cpu.io_out.clear();
+        cpu.profile = profile;
+        last
+    }
+
+    // Rebuilds the machine as it was at `target`, then forgets everything after it.
+    // `queued` is what was waiting in io_in before going back.
+    fn travel(&mut self, cpu: &mut Cpu, target: u64, queued: &VecDeque<i64>) {
+        let i = self
+            .snapshots
+            .iter()
+            .rposition(|(tick, _)| *tick <= target)
+            .expect("The first snapshot is at tick 0");
+        // Replays aren't profiled, so the profile counts each instruction run once
+        let profile = cpu.profile.take();
+        self.rewind(cpu, i, queued);
+        while self.clock.tick() < target {
+            match cpu.step() {
+                Ok(StepEvent::NeedsInput) => {
+                    self.clock.unstart();
+                    break;
+                }
+                Ok(_) => (),
+                Err(_) => break,
+            }
+        }
+        self.clock.replaying.set(false);
+        cpu.io_out.clear();
+        cpu.profile = profile;
+
+        self.snapshots.truncate(i + 1);
+        self.clock
+            .inputs
+            .borrow_mut()
+            .retain(|(tick, _)| *tick <= target);
+    }
+
+    // Restores snapshot i and queues every input read since, ahead of `queued`, ready to
+    // replay
+    fn rewind(&self, cpu: &mut Cpu, i: usize, queued: &VecDeque<i64>) {
+        let (start, snapshot) = &self.snapshots[i];
+        cpu.restore(snapshot);
+        cpu.io_in.clone_from(queued);
+        for (_, value) in self
+            .clock
+            .inputs
+            .borrow()
+            .iter()
+            .filter(|(tick, _)| tick > start)
+            .rev()
+        {
+            cpu.io_in.push_back(*value);
+        }
+        self.clock.tick.set(*start);
+        self.clock.replaying.set(true);
+    }
+}
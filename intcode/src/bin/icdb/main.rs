@@ -0,0 +1,744 @@
+mod history;
+mod memview;
+mod script;
+
+use std::{
+    cell::Cell,
+    env, fs,
+    io::{self, Write},
+    process,
+    rc::Rc,
+};
+
+use intcode::{
+    CoreDump, Cpu, IntcodeError, Io, LogEvent, Op, RunResult, State, StepEvent, Symbols, Verbosity,
+    Watch, WatchedWrite,
+};
+use utils::parse::{apply_patches, parse_intcode, read_intcode, read_patches};
+
+use crate::{
+    history::History,
+    memview::{RecentWrites, remember_write},
+    script::Scripts,
+};
+
+// Instructions shown by a bare `disas`
+const DISAS_COUNT: usize = 10;
+// Blocks shown by a bare `profile`
+const PROFILE_COUNT: usize = 5;
+// Cells per row shown by `x`
+const EXAMINE_ROW: usize = 8;
+// Rows printed by `mem` without the terminal viewer
+#[cfg(not(feature = "tui"))]
+const MEM_ROWS: usize = 8;
+
+const OPS: [Op; 10] = [
+    Op::Add,
+    Op::Mul,
+    Op::In,
+    Op::Out,
+    Op::Jnz,
+    Op::Jz,
+    Op::Lt,
+    Op::Cmp,
+    Op::AdjBp,
+    Op::Hlt,
+];
+
+fn usage() {
+    println!(
+        "usage: icdb [--symbols <file>] [--patch <file>] <program> | icdb [--symbols <file>] --core <dump>"
+    );
+    println!("debugs a program interactively; type `help` at the prompt for commands");
+    println!("  --core     pick up from a core dump saved with `dump` instead of the start");
+    println!("  --symbols  name addresses from a file of addr=name lines; names can be");
+    println!("             used wherever an address is asked for");
+    println!("  --patch    change the program with a file of addr=value lines before it runs");
+}
+
+fn help() {
+    println!("  break <addr>        pause before executing the instruction at addr");
+    println!("  break-on <op>       pause before executing any instruction with an opcode,");
+    println!("                      like `break-on In` or `break-on Op::Out`");
+    println!("  delete <addr>       remove a breakpoint");
+    println!("  delete-on <op>      remove an opcode breakpoint");
+    println!("  watch <addr> [write]  pause after any instruction reads or writes addr,");
+    println!("                      or with `write` only writes");
+    println!("  unwatch <addr>      remove a watchpoint");
+    println!("  step [n]            execute n instructions (default 1)");
+    println!("  continue            run until a breakpoint, input is needed or halt");
+    println!("  reverse-step [n]    go back n instructions (default 1)");
+    println!("  reverse-continue    go back to the last breakpoint or watchpoint hit");
+    println!("  input <a,b,...>     queue values for the program to read");
+    println!("  say <text>          queue a line of ASCII text");
+    println!("  print mem[<addr>]   show a memory cell");
+    println!("  x/<n><f> [addr]     examine n cells from addr (default 1 from ip) as d for");
+    println!("                      decimal, c for characters or i for instructions");
+    println!("  set mem[<addr>]=<v> change a memory cell");
+    println!("  mem [addr]          show memory around addr (default ip), highlighting the");
+    println!("                      ip, the cell bp points at and recent writes");
+    println!("  regs                show ip, bp, state and instructions run");
+    println!("  disas [addr] [n]    disassemble n instructions from addr (default ip)");
+    println!("  profile [n]         show the n blocks that ran the most (default 5)");
+    println!("  dump <file>         save memory, registers and IO queues, with the error");
+    println!("                      that stopped the machine if there was one");
+    println!("  diff <a> [b]        show the cells that differ from a to b, each a dump or");
+    println!("                      a program (default b is the machine as it is now)");
+    println!("  on <addr> <rhai>    run a Rhai action when a breakpoint or watchpoint at");
+    println!("                      addr is hit; it sees `addr`, `value`, and `old` if");
+    println!("                      `written`, and calls `resume()` to carry on, as in");
+    println!("                      `on 337 print(value); resume()`");
+    println!("  off <addr>          remove an action");
+    println!("  eval <rhai>         run Rhai now, with peek(a), poke(a, v), ip(), bp(),");
+    println!("                      input(v), say(text) and step(), which returns false");
+    println!("                      once it halts or needs input");
+    println!("  script <file>       eval a file of Rhai");
+    println!("  name <addr> [name]  name an address, or forget its name, saving the");
+    println!("                      symbols file if there is one");
+    println!("  symbols             list the named addresses");
+    println!("  quit");
+}
+
+fn prompt() -> Option<String> {
+    print!("\x1b[35m(icdb)\x1b[m ");
+    io::stdout().flush().expect("Failed to flush stdout");
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
+
+fn parse_number(arg: Option<&str>) -> Option<usize> {
+    arg?.parse().ok()
+}
+
+// An address as a number or a name
+fn parse_addr(arg: Option<&str>, symbols: &Symbols) -> Option<usize> {
+    let arg = arg?;
+    arg.parse().ok().or_else(|| symbols.addr(arg))
+}
+
+// An opcode by the name it disassembles to, with or without `Op::`
+fn parse_op(arg: Option<&str>) -> Option<Op> {
+    let name = arg?;
+    let name = name.strip_prefix("Op::").unwrap_or(name);
+    OPS.into_iter()
+        .find(|op| format!("{op:?}").eq_ignore_ascii_case(name))
+}
+
+fn print_disas(cpu: &Cpu, symbols: &Symbols, addr: usize, count: usize) {
+    for line in cpu.disassemble(addr, count) {
+        let addr = line.addr();
+        if let Some(name) = symbols.name(addr) {
+            println!("   {name}:");
+        }
+        let marker = if addr == cpu.ip { "=>" } else { "  " };
+        let colour = if cpu.breakpoints.contains(&addr) {
+            "31"
+        } else {
+            "34"
+        };
+        println!(
+            "{marker} \x1b[{colour}m{addr:6}\x1b[m  {}",
+            line.display_with(symbols)
+        );
+    }
+}
+
+// Shows where the machine stopped, and the instruction it will execute next
+fn print_stop(cpu: &Cpu, symbols: &Symbols, reason: &str) {
+    println!("\x1b[33m{reason}\x1b[m");
+    if !matches!(cpu.state, State::Halted) {
+        print_disas(cpu, symbols, cpu.ip, 1);
+    }
+}
+
+// Shows an error that stopped the machine, and passes it on to be kept for `dump`
+fn print_error(err: IntcodeError) -> Option<IntcodeError> {
+    println!("\x1b[31;1m{err}\x1b[m");
+    Some(err)
+}
+
+// Returns the error that stopped the machine, if one did
+fn step(
+    cpu: &mut Cpu,
+    history: &mut History,
+    symbols: &Symbols,
+    count: usize,
+) -> Option<IntcodeError> {
+    for _ in 0..count {
+        match history.step(cpu) {
+            Ok(StepEvent::Continued | StepEvent::Output(_)) => (),
+            Ok(StepEvent::NeedsInput) => {
+                print_stop(cpu, symbols, "needs input");
+                return None;
+            }
+            Ok(StepEvent::Halted) => {
+                print_stop(cpu, symbols, "halted");
+                return None;
+            }
+            Err(err) => return print_error(err),
+        }
+    }
+    print_stop(cpu, symbols, "stepped");
+    None
+}
+
+// The latest write by the program since it was last cleared
+type LastWrite = Rc<Cell<Option<WatchedWrite>>>;
+
+// Returns the error that stopped the machine, if one did. Stops with an action that
+// resumes are passed over.
+fn cont(
+    cpu: &mut Cpu,
+    history: &mut History,
+    last_write: &LastWrite,
+    scripts: &mut Scripts,
+    symbols: &Symbols,
+) -> Option<IntcodeError> {
+    loop {
+        last_write.set(None);
+        let stop = history.run(cpu);
+        let resumed = match stop {
+            Ok(RunResult::Breakpoint(addr)) => scripts.run_action(cpu, addr, None),
+            Ok(RunResult::Watchpoint(addr)) => {
+                let write = last_write.get().filter(|write| write.addr == addr);
+                scripts.run_action(cpu, addr, write)
+            }
+            _ => false,
+        };
+        if !resumed {
+            match stop {
+                Ok(RunResult::Breakpoint(addr)) => {
+                    let reason = format!("breakpoint {}", symbols.describe(addr));
+                    break print_stop(cpu, symbols, &reason);
+                }
+                Ok(RunResult::Watchpoint(addr)) => {
+                    break print_watch(cpu, symbols, addr, last_write.get());
+                }
+                Ok(RunResult::NeedsInput) => break print_stop(cpu, symbols, "needs input"),
+                Ok(RunResult::Halted) => break print_stop(cpu, symbols, "halted"),
+                Ok(_) => (),
+                Err(err) => return print_error(err),
+            }
+        }
+        // Outputs were already printed by the IO hook
+        cpu.io_out.clear();
+    }
+    None
+}
+
+fn reverse_continue(
+    cpu: &mut Cpu,
+    history: &mut History,
+    last_write: &LastWrite,
+    symbols: &Symbols,
+) {
+    last_write.set(None);
+    match history.reverse_continue(cpu) {
+        Some(RunResult::Watchpoint(addr)) => print_watch(cpu, symbols, addr, last_write.get()),
+        Some(RunResult::Breakpoint(addr)) => {
+            let reason = format!("breakpoint {}", symbols.describe(addr));
+            print_stop(cpu, symbols, &reason);
+        }
+        _ => print_stop(cpu, symbols, "back at the start"),
+    }
+}
+
+// Says which instruction set off a watchpoint. The run pauses after the first one that
+// touches the address, so a write to it during the run was by that instruction, and
+// otherwise it was a read.
+fn print_watch(cpu: &Cpu, symbols: &Symbols, addr: usize, last_write: Option<WatchedWrite>) {
+    let name = symbols.describe(addr);
+    let reason = match (last_write, cpu.last_watched_read()) {
+        (Some(write), _) if write.addr == addr => {
+            format!(
+                "watchpoint {name}: {} -> {} by ip {}",
+                write.old,
+                write.new,
+                symbols.describe(write.ip)
+            )
+        }
+        (_, Some(read)) => format!(
+            "watchpoint {name}: read {} by ip {}",
+            read.value,
+            symbols.describe(read.ip)
+        ),
+        _ => format!("watchpoint {name}"),
+    };
+    print_stop(cpu, symbols, &reason);
+}
+
+// The address in `mem[<addr>]`
+fn parse_mem(arg: &str, symbols: &Symbols) -> Option<usize> {
+    let addr = arg.strip_prefix("mem[")?.strip_suffix(']')?;
+    parse_addr(Some(addr.trim()), symbols)
+}
+
+fn print_mem(cpu: &Cpu, symbols: &Symbols, arg: Option<&str>) {
+    let Some(addr) = arg.and_then(|arg| parse_mem(arg, symbols)) else {
+        return println!("usage: print mem[<addr>]");
+    };
+    match cpu.peek(addr) {
+        Ok(value) => println!("mem[{}] = {value}", symbols.describe(addr)),
+        Err(err) => println!("\x1b[31m{err}\x1b[m"),
+    }
+}
+
+// The count and format of `x/16d`, both optional, as the text after the `x`
+fn parse_examine(spec: &str) -> Option<(usize, char)> {
+    let spec = spec.strip_prefix('/').unwrap_or(spec);
+    let digits = spec.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let count = match digits {
+        "" => 1,
+        _ => digits.parse().ok()?,
+    };
+    let format = match &spec[digits.len()..] {
+        "" => 'd',
+        format @ ("d" | "c" | "i") => format.chars().next()?,
+        _ => return None,
+    };
+    Some((count, format))
+}
+
+// Shows `count` cells from `addr` as numbers or characters, a row at a time, or
+// `count` instructions
+fn examine(cpu: &Cpu, symbols: &Symbols, count: usize, format: char, addr: usize) {
+    if format == 'i' {
+        return print_disas(cpu, symbols, addr, count);
+    }
+    // Counts and addresses are typed in, so they can run past the end of usize
+    let end = addr.saturating_add(count);
+    for start in (addr..end).step_by(EXAMINE_ROW) {
+        let mut row = format!("\x1b[34m{start:6}\x1b[m ");
+        for addr in start..start.saturating_add(EXAMINE_ROW).min(end) {
+            let Ok(value) = cpu.peek(addr) else {
+                println!("{row}");
+                return println!("\x1b[31mmemory ends at {addr}\x1b[m");
+            };
+            let cell = match u8::try_from(value) {
+                Ok(byte) if format == 'c' && (byte.is_ascii_graphic() || byte == b' ') => {
+                    format!("{:?}", byte as char)
+                }
+                Ok(b'\n') if format == 'c' => "'\\n'".to_string(),
+                _ => value.to_string(),
+            };
+            row.push_str(&format!(" {cell:>8}"));
+        }
+        println!("{row}");
+    }
+}
+
+// The address and value of `set mem[<addr>]=<value>`, as the text after `set`
+fn parse_set(rest: &str, symbols: &Symbols) -> Option<(usize, i64)> {
+    let (target, value) = rest.split_once('=')?;
+    Some((
+        parse_mem(target.trim(), symbols)?,
+        value.trim().parse().ok()?,
+    ))
+}
+
+// Carries out `set mem[<addr>]=<value>`
+fn set(cpu: &mut Cpu, symbols: &Symbols, rest: &str) {
+    let Some((addr, value)) = parse_set(rest, symbols) else {
+        return println!("usage: set mem[<addr>]=<value>");
+    };
+    if let Err(err) = cpu.poke(addr, value) {
+        println!("\x1b[31m{err}\x1b[m");
+    }
+}
+
+#[cfg(feature = "tui")]
+fn show_mem(cpu: &mut Cpu, addr: usize, recent: &RecentWrites) {
+    if let Err(err) = memview::view(cpu, addr, recent) {
+        println!("\x1b[31m{err}\x1b[m");
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn show_mem(cpu: &mut Cpu, addr: usize, recent: &RecentWrites) {
+    memview::dump(cpu, addr, MEM_ROWS, recent);
+}
+
+fn print_profile(cpu: &Cpu, count: usize) {
+    let Some(profile) = &cpu.profile else {
+        return;
+    };
+    let allocated = cpu.memory().allocated();
+    let lines: Vec<_> = cpu
+        .disassemble(0, allocated)
+        .into_iter()
+        .take_while(|line| line.addr() < allocated)
+        .collect();
+    profile.print_hot_blocks(&lines, count);
+}
+
+fn read_dump(path: &str) -> CoreDump {
+    let dump = fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| text.parse());
+    dump.unwrap_or_else(|err| {
+        println!("\x1b[31;1m{path}: {err}\x1b[m");
+        process::exit(2);
+    })
+}
+
+// A core dump, or a program as it is before running
+fn read_memory(path: &str) -> Result<CoreDump, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+    if let Ok(dump) = text.parse() {
+        return Ok(dump);
+    }
+    let program =
+        parse_intcode(&text).map_err(|_| format!("{path}: neither a core dump nor a program"))?;
+    let mut cpu = Cpu::new();
+    cpu.load_program(&program).map_err(|err| err.to_string())?;
+    Ok(cpu.core_dump(None))
+}
+
+fn print_diff(cpu: &Cpu, symbols: &Symbols, before: Option<&str>, after: Option<&str>) {
+    let Some(before) = before else {
+        return println!("usage: diff <a> [b]");
+    };
+    let dumps = read_memory(before).and_then(|before| {
+        let after = match after {
+            Some(after) => read_memory(after)?,
+            None => cpu.core_dump(None),
+        };
+        Ok((before, after))
+    });
+    let (before, after) = match dumps {
+        Ok(dumps) => dumps,
+        Err(err) => return println!("\x1b[31m{err}\x1b[m"),
+    };
+    let changes = before.diff(&after);
+    for (addr, old, new) in &changes {
+        let addr = symbols.describe(*addr);
+        println!("\x1b[34m{addr:>6}\x1b[m  {old} -> \x1b[33m{new}\x1b[m");
+    }
+    println!("{} cells changed", changes.len());
+}
+
+fn write_dump(cpu: &Cpu, error: Option<&IntcodeError>, path: &str) {
+    match fs::write(path, cpu.core_dump(error).to_string()) {
+        Ok(()) => println!("dumped to {path}"),
+        Err(err) => println!("\x1b[31m{path}: {err}\x1b[m"),
+    }
+}
+
+fn read_symbols(path: &str) -> Symbols {
+    let symbols = fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| text.parse());
+    symbols.unwrap_or_else(|err| {
+        println!("\x1b[31;1m{path}: {err}\x1b[m");
+        process::exit(2);
+    })
+}
+
+// Names or unnames an address, and saves the symbols if they came from a file
+fn name(symbols: &mut Symbols, path: Option<&str>, addr: usize, name: Option<&str>) {
+    match name {
+        Some(name) => {
+            if let Err(err) = symbols.insert(addr, name) {
+                return println!("\x1b[31m{err}\x1b[m");
+            }
+        }
+        None => {
+            symbols.remove(addr);
+        }
+    }
+    if let Some(path) = path
+        && let Err(err) = fs::write(path, symbols.to_string())
+    {
+        println!("\x1b[31m{path}: {err}\x1b[m");
+    }
+}
+
+fn print_regs(cpu: &Cpu, history: &History) {
+    let state = match cpu.state {
+        State::Halted => "halted",
+        _ => "ready",
+    };
+    println!("ip = {}", cpu.ip);
+    println!("bp = {}", cpu.bp);
+    println!("state = {state}");
+    println!("queued input = {}", cpu.io_in.len());
+    println!("instructions run = {}", history.clock().tick());
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut path: Option<&String> = None;
+    let mut core = None;
+    let mut symbols_path: Option<&String> = None;
+    let mut patches = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--core" => match iter.next() {
+                Some(path) => core = Some(read_dump(path)),
+                None => return usage(),
+            },
+            "--symbols" => match iter.next() {
+                Some(path) => symbols_path = Some(path),
+                None => return usage(),
+            },
+            "--patch" => match iter.next() {
+                Some(path) => {
+                    patches = read_patches(path).unwrap_or_else(|err| {
+                        println!("\x1b[31;1m{err}\x1b[m");
+                        process::exit(2);
+                    })
+                }
+                None => return usage(),
+            },
+            _ => path = Some(arg),
+        }
+    }
+    let mut program = match (&core, path) {
+        (Some(_), _) => Vec::new(),
+        (None, Some(path)) => read_intcode(path).unwrap_or_else(|err| {
+            println!("\x1b[31;1m{err}\x1b[m");
+            process::exit(2);
+        }),
+        (None, None) => return usage(),
+    };
+    apply_patches(&mut program, &patches);
+    let mut symbols = symbols_path.map_or_else(Symbols::new, |path| read_symbols(path));
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .profile(true)
+        // Watchpoints are reported at the prompt instead
+        .logger(|_: LogEvent| ())
+        .build()
+        .unwrap_or_else(|err| {
+            println!("\x1b[31;1m{err}\x1b[m");
+            process::exit(2);
+        });
+
+    if let Some(dump) = &core {
+        cpu.load_core_dump(dump);
+        if let Some(error) = &dump.error {
+            println!("\x1b[31;1mdumped after: {error}\x1b[m");
+        }
+    }
+
+    let mut history = History::new(&mut cpu);
+    let clock = history.clock();
+
+    // Outputs are shown as the program makes them: text as text, anything else on a
+    // line of its own. Replays going backwards are kept quiet.
+    cpu.on_io(move |event| {
+        if let Io::Output(value) = event
+            && !clock.replaying()
+        {
+            match u8::try_from(value) {
+                Ok(byte) if byte.is_ascii() => print!("{}", byte as char),
+                _ => println!("\x1b[32m{value}\x1b[m"),
+            }
+        }
+    });
+
+    let last_write: LastWrite = Rc::new(Cell::new(None));
+    let hook_write = Rc::clone(&last_write);
+    let recent: RecentWrites = Rc::default();
+    let hook_recent = Rc::clone(&recent);
+    cpu.on_memory_write(move |write| {
+        hook_write.set(Some(*write));
+        remember_write(&hook_recent, write.addr);
+    });
+
+    // Kept for `dump` until the machine moves on
+    let mut last_error = None;
+    let mut scripts = Scripts::new();
+    print_disas(&cpu, &symbols, cpu.ip, 1);
+    while let Some(line) = prompt() {
+        let (command, rest) = line.split_once(' ').unwrap_or((&line, ""));
+        let mut args = rest.split_whitespace();
+        match command {
+            "" => (),
+            "break" | "b" => match parse_addr(args.next(), &symbols) {
+                Some(addr) => {
+                    cpu.breakpoints.insert(addr);
+                }
+                None => println!("usage: break <addr>"),
+            },
+            "break-on" => match parse_op(args.next()) {
+                Some(op) => {
+                    cpu.op_breakpoints.insert(op);
+                }
+                None => println!("usage: break-on <op>"),
+            },
+            "delete-on" => match parse_op(args.next()) {
+                Some(op) => {
+                    cpu.op_breakpoints.remove(&op);
+                }
+                None => println!("usage: delete-on <op>"),
+            },
+            "watch" | "w" => match (parse_addr(args.next(), &symbols), args.next()) {
+                (Some(addr), None) => {
+                    cpu.watchpoints.insert(addr, Watch::Access);
+                }
+                (Some(addr), Some("write")) => {
+                    cpu.watchpoints.insert(addr, Watch::Pause);
+                }
+                _ => println!("usage: watch <addr> [write]"),
+            },
+            "unwatch" => match parse_addr(args.next(), &symbols) {
+                Some(addr) => {
+                    cpu.watchpoints.remove(&addr);
+                }
+                None => println!("usage: unwatch <addr>"),
+            },
+            "delete" | "d" => match parse_addr(args.next(), &symbols) {
+                Some(addr) => {
+                    cpu.breakpoints.remove(&addr);
+                }
+                None => println!("usage: delete <addr>"),
+            },
+            "step" | "s" => {
+                let count = parse_number(args.next()).unwrap_or(1);
+                last_error = step(&mut cpu, &mut history, &symbols, count);
+            }
+            "continue" | "c" => {
+                last_error = cont(&mut cpu, &mut history, &last_write, &mut scripts, &symbols);
+            }
+            "reverse-step" | "rs" => {
+                let count = parse_number(args.next()).unwrap_or(1);
+                history.reverse_step(&mut cpu, count as u64);
+                last_error = None;
+                print_stop(&cpu, &symbols, "stepped back");
+            }
+            "reverse-continue" | "rc" => {
+                reverse_continue(&mut cpu, &mut history, &last_write, &symbols);
+                last_error = None;
+            }
+            "input" | "i" => match parse_intcode(rest) {
+                Ok(values) => {
+                    for value in values {
+                        cpu.io_in.push_front(value);
+                    }
+                }
+                Err(err) => println!("\x1b[31m{err}\x1b[m"),
+            },
+            "say" => cpu.push_ascii_line(rest),
+            "print" | "p" => print_mem(&cpu, &symbols, args.next()),
+            "set" => set(&mut cpu, &symbols, rest),
+            "mem" | "m" => {
+                let addr = parse_addr(args.next(), &symbols).unwrap_or(cpu.ip);
+                show_mem(&mut cpu, addr, &recent);
+            }
+            "regs" | "r" => print_regs(&cpu, &history),
+            "profile" => print_profile(&cpu, parse_number(args.next()).unwrap_or(PROFILE_COUNT)),
+            "disas" => {
+                let addr = parse_addr(args.next(), &symbols).unwrap_or(cpu.ip);
+                let count = parse_number(args.next()).unwrap_or(DISAS_COUNT);
+                print_disas(&cpu, &symbols, addr, count);
+            }
+            "dump" => match args.next() {
+                Some(path) => write_dump(&cpu, last_error.as_ref(), path),
+                None => println!("usage: dump <file>"),
+            },
+            "diff" => print_diff(&cpu, &symbols, args.next(), args.next()),
+            "on" => {
+                let (addr, source) = rest.split_once(' ').unwrap_or((rest, ""));
+                match parse_addr(Some(addr), &symbols) {
+                    Some(addr) if !source.trim().is_empty() => {
+                        if let Err(err) = scripts.set_action(addr, source) {
+                            println!("\x1b[31m{err}\x1b[m");
+                        }
+                    }
+                    _ => println!("usage: on <addr> <rhai>"),
+                }
+            }
+            "off" => match parse_addr(args.next(), &symbols) {
+                Some(addr) => {
+                    scripts.remove_action(addr);
+                }
+                None => println!("usage: off <addr>"),
+            },
+            "eval" => scripts.eval(&mut cpu, rest),
+            "script" => match fs::read_to_string(rest) {
+                Ok(source) => scripts.eval(&mut cpu, &source),
+                Err(err) => println!("\x1b[31m{rest}: {err}\x1b[m"),
+            },
+            _ if command.starts_with('x') && parse_examine(&command[1..]).is_some() => {
+                let (count, format) = parse_examine(&command[1..]).expect("Checked above");
+                let addr = parse_addr(args.next(), &symbols).unwrap_or(cpu.ip);
+                examine(&cpu, &symbols, count, format, addr);
+            }
+            "name" => match parse_addr(args.next(), &symbols) {
+                Some(addr) => {
+                    let path = symbols_path.map(String::as_str);
+                    name(&mut symbols, path, addr, args.next());
+                }
+                None => println!("usage: name <addr> [name]"),
+            },
+            "symbols" => {
+                for (addr, name) in symbols.iter() {
+                    println!("\x1b[34m{addr:6}\x1b[m  {name}");
+                }
+            }
+            "help" | "h" => help(),
+            "quit" | "q" => break,
+            _ => println!("unknown command `{command}`; try `help`"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols() -> Symbols {
+        let mut symbols = Symbols::new();
+        symbols.insert(100, "score").unwrap();
+        symbols
+    }
+
+    #[test]
+    fn examine_spec() {
+        assert_eq!(parse_examine(""), Some((1, 'd')));
+        assert_eq!(parse_examine("/16"), Some((16, 'd')));
+        assert_eq!(parse_examine("/c"), Some((1, 'c')));
+        assert_eq!(parse_examine("/4i"), Some((4, 'i')));
+        assert_eq!(parse_examine("8c"), Some((8, 'c')));
+        assert_eq!(parse_examine("/4x"), None);
+        assert_eq!(parse_examine("/-1"), None);
+        assert_eq!(parse_examine("/99999999999999999999999"), None);
+    }
+
+    #[test]
+    fn mem_operand() {
+        let symbols = symbols();
+        assert_eq!(parse_mem("mem[12]", &symbols), Some(12));
+        assert_eq!(parse_mem("mem[ score ]", &symbols), Some(100));
+        assert_eq!(parse_mem("mem[lives]", &symbols), None);
+        assert_eq!(parse_mem("mem[12", &symbols), None);
+        assert_eq!(parse_mem("12", &symbols), None);
+    }
+
+    #[test]
+    fn set_assignment() {
+        let symbols = symbols();
+        assert_eq!(parse_set(" mem[5]=-3", &symbols), Some((5, -3)));
+        assert_eq!(parse_set("mem[score] = 42", &symbols), Some((100, 42)));
+        assert_eq!(parse_set("mem[5]", &symbols), None);
+        assert_eq!(parse_set("mem[5]=x", &symbols), None);
+        assert_eq!(parse_set("5=1", &symbols), None);
+    }
+
+    #[test]
+    fn examine_near_the_top_of_memory() {
+        let cpu = Cpu::builder()
+            .program(&[99])
+            .verbosity(Verbosity::Silent)
+            .build()
+            .unwrap();
+        examine(&cpu, &Symbols::new(), 2, 'd', usize::MAX);
+        examine(&cpu, &Symbols::new(), usize::MAX, 'd', usize::MAX - 3);
+    }
+}
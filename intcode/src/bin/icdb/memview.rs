@@ -0,0 +1,186 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use intcode::Cpu;
+
+// Cells per row
+const ROW: usize = 8;
+// Width of a cell, enough for most values
+const CELL: usize = 9;
+// Written addresses remembered for highlighting
+const RECENT: usize = 16;
+
+// Addresses the program wrote to lately, newest last
+pub type RecentWrites = Rc<RefCell<VecDeque<usize>>>;
+
+pub fn remember_write(recent: &RecentWrites, addr: usize) {
+    let mut recent = recent.borrow_mut();
+    recent.retain(|a| *a != addr);
+    if recent.len() == RECENT {
+        recent.pop_front();
+    }
+    recent.push_back(addr);
+}
+
+// A row of cells from `start`: the ip is on red, the cell bp points at on blue, recent
+// writes are yellow and the cursor, if any, is reversed
+fn row(cpu: &Cpu, start: usize, cursor: Option<usize>, recent: &RecentWrites) -> String {
+    let recent = recent.borrow();
+    let bp = usize::try_from(cpu.bp).ok();
+    let mut line = format!("\x1b[34m{start:6}\x1b[m ");
+    for addr in start..start + ROW {
+        // Past the end of limited memory
+        let Ok(value) = cpu.peek(addr) else {
+            break;
+        };
+        let mut style = Vec::new();
+        if addr == cpu.ip {
+            style.push("41");
+        } else if Some(addr) == bp {
+            style.push("44");
+        } else if recent.contains(&addr) {
+            style.push("33");
+        } else if value == 0 {
+            style.push("90");
+        }
+        if Some(addr) == cursor {
+            style.push("7");
+        }
+        line.push_str(&format!("\x1b[{}m{value:>CELL$}\x1b[m", style.join(";")));
+    }
+    line
+}
+
+/// Prints `rows` rows of memory from `addr`.
+#[cfg_attr(feature = "tui", allow(dead_code))]
+pub fn dump(cpu: &Cpu, addr: usize, rows: usize, recent: &RecentWrites) {
+    let start = addr / ROW * ROW;
+    for i in 0..rows {
+        println!("{}", row(cpu, start + i * ROW, None, recent));
+    }
+}
+
+#[cfg(feature = "tui")]
+pub use viewer::view;
+
+#[cfg(feature = "tui")]
+mod viewer {
+    use std::io::{self, Write, stdout};
+
+    use crossterm::{
+        cursor::{Hide, MoveTo, Show},
+        event::{self, Event, KeyCode, KeyEventKind},
+        execute, queue,
+        style::Print,
+        terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    };
+    use intcode::Cpu;
+
+    use super::{ROW, RecentWrites, row};
+
+    // What typed digits are for
+    enum Prompt {
+        Edit,
+        Goto,
+    }
+
+    /// Opens a scrollable view of memory with the cursor on `addr`, until `q`. Cells
+    /// can be edited in place.
+    pub fn view(cpu: &mut Cpu, addr: usize, recent: &RecentWrites) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen, Hide)?;
+        let result = run(cpu, addr, recent);
+        execute!(stdout(), Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn run(cpu: &mut Cpu, mut cursor: usize, recent: &RecentWrites) -> io::Result<()> {
+        let mut out = stdout();
+        let mut top = cursor / ROW * ROW;
+        let mut prompt: Option<(Prompt, String)> = None;
+        let mut status = String::new();
+        let last = cpu.memory_limit().map_or(usize::MAX, |limit| limit - 1);
+
+        loop {
+            let (_, height) = terminal::size()?;
+            let rows = usize::from(height).saturating_sub(3).max(1);
+            cursor = cursor.min(last);
+            if cursor < top {
+                top = cursor / ROW * ROW;
+            } else if cursor >= top + rows * ROW {
+                top = (cursor / ROW + 1 - rows) * ROW;
+            }
+
+            let value = cpu.peek(cursor).unwrap_or_default();
+            queue!(
+                out,
+                Clear(ClearType::All),
+                MoveTo(0, 0),
+                Print(format!(
+                    "\x1b[1mip {}  bp {}  mem[{cursor}] = {value}\x1b[m  {status}",
+                    cpu.ip, cpu.bp
+                ))
+            )?;
+            for i in 0..rows {
+                let line = row(cpu, top + i * ROW, Some(cursor), recent);
+                queue!(out, MoveTo(0, i as u16 + 1), Print(line))?;
+            }
+            let footer = match &prompt {
+                Some((Prompt::Edit, text)) => format!("mem[{cursor}] = {text}_"),
+                Some((Prompt::Goto, text)) => format!("go to {text}_"),
+                None => "arrows/pgup/pgdn move  home ip  g go to  enter edit  q quit".to_string(),
+            };
+            queue!(out, MoveTo(0, height.saturating_sub(1)), Print(footer))?;
+            out.flush()?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some((kind, text)) = &mut prompt {
+                match key.code {
+                    KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => text.push(c),
+                    KeyCode::Backspace => {
+                        text.pop();
+                    }
+                    KeyCode::Enter => {
+                        status = match (kind, text.parse::<i64>()) {
+                            (Prompt::Edit, Ok(value)) => match cpu.poke(cursor, value) {
+                                Ok(()) => format!("set mem[{cursor}]"),
+                                Err(err) => err.to_string(),
+                            },
+                            (Prompt::Goto, Ok(addr)) => match usize::try_from(addr) {
+                                Ok(addr) => {
+                                    cursor = addr;
+                                    String::new()
+                                }
+                                Err(_) => "no such address".to_string(),
+                            },
+                            (_, Err(_)) => "not a number".to_string(),
+                        };
+                        prompt = None;
+                    }
+                    KeyCode::Esc => prompt = None,
+                    _ => (),
+                }
+                continue;
+            }
+            status.clear();
+            match key.code {
+                KeyCode::Up => cursor = cursor.saturating_sub(ROW),
+                KeyCode::Down => cursor = cursor.saturating_add(ROW),
+                KeyCode::Left => cursor = cursor.saturating_sub(1),
+                KeyCode::Right => cursor = cursor.saturating_add(1),
+                KeyCode::PageUp => cursor = cursor.saturating_sub(rows * ROW),
+                KeyCode::PageDown => cursor = cursor.saturating_add(rows * ROW),
+                KeyCode::Home => cursor = cpu.ip,
+                KeyCode::Char('g') => prompt = Some((Prompt::Goto, String::new())),
+                KeyCode::Enter => prompt = Some((Prompt::Edit, String::new())),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                _ => (),
+            }
+        }
+    }
+}
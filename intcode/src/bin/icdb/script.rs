@@ -0,0 +1,196 @@
+#[cfg(feature = "scripting")]
+pub use rhai_scripts::Scripts;
+
+#[cfg(not(feature = "scripting"))]
+pub use without_scripting::Scripts;
+
+#[cfg(feature = "scripting")]
+mod rhai_scripts {
+    use std::{
+        cell::{Cell, RefCell},
+        collections::BTreeMap,
+        mem,
+        rc::Rc,
+    };
+
+    use intcode::{Cpu, State, StepEvent, WatchedWrite};
+    use rhai::{AST, Dynamic, Engine, EvalAltResult, Position, Scope};
+
+    type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+    // Opcode of an input instruction
+    const IN: i64 = 3;
+
+    /// Rhai snippets run at the prompt with `eval`, and actions run when a breakpoint
+    /// or watchpoint is hit. Scripts see the machine through `peek`, `poke`, `ip`,
+    /// `bp`, `input`, `say` and `step`, and an action can call `resume` to carry on
+    /// instead of stopping at the prompt.
+    pub struct Scripts {
+        engine: Engine,
+        // The machine while a script runs, and a blank one in between
+        cpu: Rc<RefCell<Cpu>>,
+        // Set by `resume()` in an action
+        resume: Rc<Cell<bool>>,
+        // Actions by the address that sets them off
+        actions: BTreeMap<usize, AST>,
+    }
+
+    fn script_error(err: impl ToString) -> Box<EvalAltResult> {
+        EvalAltResult::ErrorRuntime(err.to_string().into(), Position::NONE).into()
+    }
+
+    fn to_addr(addr: i64) -> ScriptResult<usize> {
+        usize::try_from(addr).map_err(|_| format!("address {addr} is outside memory").into())
+    }
+
+    impl Scripts {
+        pub fn new() -> Self {
+            let cpu: Rc<RefCell<Cpu>> = Rc::default();
+            let resume = Rc::new(Cell::new(false));
+            let mut engine = Engine::new();
+
+            let peek = Rc::clone(&cpu);
+            engine.register_fn("peek", move |addr: i64| -> ScriptResult<i64> {
+                peek.borrow().peek(to_addr(addr)?).map_err(script_error)
+            });
+            let poke = Rc::clone(&cpu);
+            engine.register_fn("poke", move |addr: i64, value: i64| -> ScriptResult<()> {
+                poke.borrow_mut()
+                    .poke(to_addr(addr)?, value)
+                    .map_err(script_error)
+            });
+            let ip = Rc::clone(&cpu);
+            engine.register_fn("ip", move || ip.borrow().ip as i64);
+            let bp = Rc::clone(&cpu);
+            engine.register_fn("bp", move || bp.borrow().bp);
+            let input = Rc::clone(&cpu);
+            engine.register_fn("input", move |value: i64| {
+                input.borrow_mut().io_in.push_front(value)
+            });
+            let say = Rc::clone(&cpu);
+            engine.register_fn("say", move |text: &str| {
+                say.borrow_mut().push_ascii_line(text)
+            });
+            // Stepping into an input with nothing to read would put the debugger's
+            // count of instructions out, so it is refused like halting
+            let step = Rc::clone(&cpu);
+            engine.register_fn("step", move || -> ScriptResult<bool> {
+                let mut cpu = step.borrow_mut();
+                let starved =
+                    cpu.peek(cpu.ip).is_ok_and(|value| value % 100 == IN) && cpu.io_in.is_empty();
+                if starved || matches!(cpu.state, State::Halted) {
+                    return Ok(false);
+                }
+                match cpu.step() {
+                    Ok(StepEvent::Halted) => Ok(false),
+                    Ok(_) => Ok(true),
+                    Err(err) => Err(script_error(err)),
+                }
+            });
+            let resumed = Rc::clone(&resume);
+            engine.register_fn("resume", move || resumed.set(true));
+
+            Self {
+                engine,
+                cpu,
+                resume,
+                actions: BTreeMap::new(),
+            }
+        }
+
+        pub fn set_action(&mut self, addr: usize, source: &str) -> Result<(), String> {
+            let ast = self.engine.compile(source).map_err(|err| err.to_string())?;
+            self.actions.insert(addr, ast);
+            Ok(())
+        }
+
+        pub fn remove_action(&mut self, addr: usize) -> bool {
+            self.actions.remove(&addr).is_some()
+        }
+
+        /// Runs the action for a stop at `addr`, if there is one, and says whether it
+        /// asked to resume. The action sees `addr`, the `value` there, and whether the
+        /// stop was `written` with the `old` value.
+        pub fn run_action(
+            &mut self,
+            cpu: &mut Cpu,
+            addr: usize,
+            write: Option<WatchedWrite>,
+        ) -> bool {
+            let Some(ast) = self.actions.get(&addr) else {
+                return false;
+            };
+            let value = cpu.peek(addr).unwrap_or_default();
+            let mut scope = Scope::new();
+            scope.push_constant("addr", addr as i64);
+            scope.push_constant("value", value);
+            scope.push_constant("written", write.is_some());
+            scope.push_constant("old", write.map_or(value, |write| write.old));
+
+            self.resume.set(false);
+            let result = self.with_cpu(cpu, |engine| engine.run_ast_with_scope(&mut scope, ast));
+            match result {
+                Ok(()) => self.resume.get(),
+                Err(err) => {
+                    println!("\x1b[31maction at {addr}: {err}\x1b[m");
+                    false
+                }
+            }
+        }
+
+        /// Runs `source` and prints its value, if it has one.
+        pub fn eval(&mut self, cpu: &mut Cpu, source: &str) {
+            let result = self.with_cpu(cpu, |engine| engine.eval::<Dynamic>(source));
+            match result {
+                Ok(value) if value.is_unit() => (),
+                Ok(value) => println!("{value}"),
+                Err(err) => println!("\x1b[31m{err}\x1b[m"),
+            }
+        }
+
+        // Lends the machine to the engine's functions for the length of `f`
+        fn with_cpu<T>(&self, cpu: &mut Cpu, f: impl FnOnce(&Engine) -> T) -> T {
+            mem::swap(cpu, &mut *self.cpu.borrow_mut());
+            let result = f(&self.engine);
+            mem::swap(cpu, &mut *self.cpu.borrow_mut());
+            result
+        }
+    }
+}
+
+#[cfg(not(feature = "scripting"))]
+mod without_scripting {
+    use intcode::{Cpu, WatchedWrite};
+
+    const DISABLED: &str = "icdb was built without the scripting feature";
+
+    /// Stands in for Rhai scripts when they aren't built, so there are never actions.
+    pub struct Scripts;
+
+    impl Scripts {
+        pub fn new() -> Self {
+            Self
+        }
+
+        pub fn set_action(&mut self, _addr: usize, _source: &str) -> Result<(), String> {
+            Err(DISABLED.to_string())
+        }
+
+        pub fn remove_action(&mut self, _addr: usize) -> bool {
+            false
+        }
+
+        pub fn run_action(
+            &mut self,
+            _cpu: &mut Cpu,
+            _addr: usize,
+            _write: Option<WatchedWrite>,
+        ) -> bool {
+            false
+        }
+
+        pub fn eval(&mut self, _cpu: &mut Cpu, _source: &str) {
+            println!("\x1b[31m{DISABLED}\x1b[m");
+        }
+    }
+}
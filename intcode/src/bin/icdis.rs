@@ -0,0 +1,236 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs, process,
+};
+
+use intcode::{
+    Cpu, Decoded, Function, Op, Operand, Profile, Symbols, Verbosity, cfg_dot, decompile,
+    find_functions,
+};
+use utils::parse::{apply_patches, parse_intcode, read_intcode, read_patches};
+
+// Width of the column of raw cells
+const RAW_WIDTH: usize = 24;
+// Instructions run for --profile, after which the counts so far are used
+const PROFILE_LIMIT: u64 = 100_000_000;
+
+fn usage() {
+    println!(
+        "usage: icdis <program> [--symbols <file>] [--patch <file>] [--pseudo | --dot [--profile] [--input 1,2,3]]"
+    );
+    println!("prints an annotated listing of a program, with jump targets labelled and");
+    println!("functions called through the bp stack marked out");
+    println!("  --symbols  name addresses in the listing from a file of addr=name lines");
+    println!("  --patch    list the program as changed by a file of addr=value lines");
+    println!("  --pseudo   print pseudocode with ifs and loops recovered instead");
+    println!("  --dot      print the control flow graph for Graphviz instead");
+    println!("  --profile  run the program first, and shade the graph by how often each");
+    println!("             block ran; it stops when it halts or runs out of input");
+}
+
+// Runs the program silently to count how often each instruction executes
+fn profile(program: &[i64], input: &[i64]) -> Profile {
+    let mut cpu = Cpu::builder()
+        .program(program)
+        .verbosity(Verbosity::Silent)
+        .input(input)
+        .profile(true)
+        .build()
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            process::exit(2);
+        });
+    if let Err(err) = cpu.run_with_limit(PROFILE_LIMIT) {
+        eprintln!("profiling stopped: {err}");
+    }
+    cpu.profile.take().expect("Profiling is on")
+}
+
+// Labels for the fixed jump targets that start a line of the listing, numbered in
+// address order with functions apart, and for named addresses. A target inside
+// another line means the listing went out of step with the code there, so it isn't
+// labelled.
+fn make_labels(
+    lines: &[Decoded],
+    functions: &[Function],
+    symbols: &Symbols,
+) -> BTreeMap<usize, String> {
+    let starts: BTreeSet<usize> = lines.iter().map(Decoded::addr).collect();
+    let targets: BTreeSet<usize> = lines
+        .iter()
+        .filter_map(Decoded::jump_target)
+        .filter(|target| starts.contains(target) && symbols.name(*target).is_none())
+        .filter(|target| !functions.iter().any(|function| function.entry == *target))
+        .collect();
+    let mut labels: BTreeMap<usize, String> = targets
+        .into_iter()
+        .enumerate()
+        .map(|(i, target)| (target, format!("L{i}")))
+        .collect();
+    for (i, function) in functions.iter().enumerate() {
+        labels.insert(function.entry, format!("F{i}"));
+    }
+    for (addr, name) in symbols.iter() {
+        if starts.contains(&addr) {
+            labels.insert(addr, name.to_string());
+        }
+    }
+    labels
+}
+
+fn read_symbols(path: &str) -> Symbols {
+    let symbols = fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| text.parse());
+    symbols.unwrap_or_else(|err| {
+        println!("{path}: {err}");
+        process::exit(2);
+    })
+}
+
+// The character a fixed output prints, for the text adventure style programs
+fn printed_char(line: &Decoded) -> Option<char> {
+    let Decoded::Instruction {
+        op: Op::Out,
+        operands,
+        ..
+    } = line
+    else {
+        return None;
+    };
+    let Some(Operand::Immediate(value)) = operands.first() else {
+        return None;
+    };
+    let byte = u8::try_from(*value).ok()?;
+    (byte.is_ascii_graphic() || byte == b' ').then_some(byte as char)
+}
+
+fn render(
+    line: &Decoded,
+    labels: &BTreeMap<usize, String>,
+    symbols: &Symbols,
+    call: bool,
+) -> String {
+    let text = line.display_with(symbols);
+    match line.jump_target().and_then(|target| labels.get(&target)) {
+        Some(label) => {
+            let (jump, _) = text.rsplit_once(' ').expect("Jumps have a target");
+            let jump = format!("{jump} {label}");
+            if call {
+                format!("{jump:<16}; call")
+            } else {
+                jump
+            }
+        }
+        None => match printed_char(line) {
+            Some(c) => format!("{text:<16}; {c:?}"),
+            None => text,
+        },
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut path: Option<&String> = None;
+    let mut pseudo = false;
+    let mut dot = false;
+    let mut profiled = false;
+    let mut input = Vec::new();
+    let mut symbols = Symbols::new();
+    let mut patches = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--pseudo" => pseudo = true,
+            "--dot" => dot = true,
+            "--profile" => profiled = true,
+            "--symbols" => {
+                symbols = read_symbols(iter.next().expect("--symbols needs a file"));
+            }
+            "--patch" => {
+                let path = iter.next().expect("--patch needs a file");
+                patches = read_patches(path).unwrap_or_else(|err| {
+                    println!("{err}");
+                    process::exit(2);
+                });
+            }
+            "--input" => {
+                let values = iter.next().expect("--input needs values");
+                input = parse_intcode(values).expect("--input needs comma separated values");
+            }
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else {
+        usage();
+        return;
+    };
+    let mut program = read_intcode(path).unwrap_or_else(|err| {
+        println!("{err}");
+        process::exit(2);
+    });
+    apply_patches(&mut program, &patches);
+
+    // Limited to the program, so the listing ends with it
+    let mut cpu = Cpu::with_memory_limit(program.len());
+    cpu.load_program(&program)
+        .expect("Memory is as big as the program");
+    let lines = cpu.disassemble(0, program.len());
+    if pseudo {
+        print!("{}", decompile(&lines));
+        return;
+    }
+    if dot {
+        let profile = profiled.then(|| profile(&program, &input));
+        print!("{}", cfg_dot(&lines, profile.as_ref()));
+        return;
+    }
+    let functions = find_functions(&lines);
+    let labels = make_labels(&lines, &functions, &symbols);
+    let calls: BTreeSet<usize> = functions
+        .iter()
+        .flat_map(|function| function.call_sites.iter().copied())
+        .collect();
+
+    println!(
+        "; {path}: {} cells, {} labels, {} functions",
+        program.len(),
+        labels.len(),
+        functions.len()
+    );
+    for line in &lines {
+        let addr = line.addr();
+        if let Some(function) = functions.iter().find(|function| function.entry == addr) {
+            let frame = match function.frame {
+                Some(frame) => format!("frame {frame}"),
+                None => "no frame".to_string(),
+            };
+            let sites: Vec<String> = function
+                .call_sites
+                .iter()
+                .map(|site| site.to_string())
+                .collect();
+            println!();
+            println!("; function, {frame}, called from {}", sites.join(", "));
+        }
+        if let Some(label) = labels.get(&addr) {
+            println!("{label}:");
+        }
+        let raw: Vec<String> = program[addr..addr + line.size()]
+            .iter()
+            .map(|cell| cell.to_string())
+            .collect();
+        println!(
+            "{addr:6}  {:RAW_WIDTH$}  {}",
+            raw.join(","),
+            render(line, &labels, &symbols, calls.contains(&addr))
+        );
+        if let Some(function) = functions
+            .iter()
+            .find(|function| function.end == addr + line.size())
+        {
+            println!("; end of {}", labels[&function.entry]);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::{env, process};
+
+use intcode::{Cpu, IoLog, StepEvent, TraceStep, Verbosity, diverge};
+use utils::parse::{parse_intcode, read_intcode, read_to_string};
+
+// Enough for any of the puzzles, so a run that never parts or stops still ends
+const DEFAULT_LIMIT: u64 = 100_000_000;
+// Differing cells listed before the rest are only counted
+const SHOWN_CELLS: usize = 20;
+// Lines of text output shown before the point the runs part, with --ascii
+const CONTEXT_LINES: usize = 3;
+
+fn usage() {
+    println!("usage: icdiverge <program> <input a> <input b> [--ascii] [--limit N] [--uncached-b]");
+    println!("runs a program on two inputs side by side and reports the first instruction");
+    println!("where the runs part ways, with how their memory differs there");
+    println!("  inputs are sessions saved as `in ...`/`out ...` lines, like day 25's, or");
+    println!("  comma separated values");
+    println!("  --ascii  read the inputs as lines of text instead, and show the text");
+    println!("           output leading up to the difference");
+    println!("  --limit  give up after this many instructions");
+    println!("  --uncached-b  run b without the decode cache; with the same input twice,");
+    println!("           this checks cached decoding against decoding every step");
+}
+
+fn fail(err: impl ToString) -> ! {
+    println!("\x1b[31;1m{}\x1b[m", err.to_string());
+    process::exit(2);
+}
+
+fn read_input(path: &str, ascii: bool) -> Vec<i64> {
+    let text = read_to_string(path).unwrap_or_else(|err| fail(err));
+    if ascii {
+        return text.bytes().map(i64::from).collect();
+    }
+    match text.parse::<IoLog>() {
+        Ok(log) => log.inputs,
+        Err(_) => parse_intcode(&text).unwrap_or_else(|err| fail(format!("{path}: {err}"))),
+    }
+}
+
+fn describe(cpu: &Cpu, step: &TraceStep) -> String {
+    let line = match cpu.disassemble(step.ip, 1).first() {
+        Some(decoded) => decoded.to_string(),
+        None => step.instruction.to_string(),
+    };
+    let event = match step.event {
+        StepEvent::Continued => String::new(),
+        StepEvent::Output(value) => format!("  -> outputs {value}"),
+        StepEvent::NeedsInput => "  -> waits for input".to_string(),
+        StepEvent::Halted => "  -> halts".to_string(),
+    };
+    format!("ip {:<6} bp {:<6} {line}{event}", step.ip, step.bp)
+}
+
+// The last few lines both runs printed, oldest first
+fn context(cpu: &Cpu) -> Vec<String> {
+    let text: String = cpu
+        .io_out
+        .iter()
+        .rev()
+        .filter_map(|value| u8::try_from(*value).ok())
+        .map(char::from)
+        .collect();
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let start = lines.len().saturating_sub(CONTEXT_LINES);
+    lines[start..].iter().map(|line| line.to_string()).collect()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut paths: Vec<&String> = Vec::new();
+    let mut ascii = false;
+    let mut limit = DEFAULT_LIMIT;
+    let mut uncached_b = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--ascii" => ascii = true,
+            "--uncached-b" => uncached_b = true,
+            "--limit" => {
+                limit = iter
+                    .next()
+                    .and_then(|limit| limit.parse().ok())
+                    .unwrap_or_else(|| fail("--limit needs a number"));
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [path, a_path, b_path] = paths[..] else {
+        usage();
+        return;
+    };
+    let program = read_intcode(path).unwrap_or_else(|err| fail(err));
+    let build = |input: &[i64], decode_cache: bool| {
+        Cpu::builder()
+            .program(&program)
+            .verbosity(Verbosity::Silent)
+            .input(input)
+            .decode_cache(decode_cache)
+            .build()
+            .unwrap_or_else(|err| fail(err))
+    };
+    let mut a = build(&read_input(a_path, ascii), true);
+    let mut b = build(&read_input(b_path, ascii), !uncached_b);
+
+    let divergence = diverge(&mut a, &mut b, limit).unwrap_or_else(|err| fail(err));
+    let Some(divergence) = divergence else {
+        println!("\x1b[32mthe runs stay in step until they stop or reach the limit\x1b[m");
+        return;
+    };
+    println!(
+        "in step for \x1b[1m{}\x1b[m instructions, then:",
+        divergence.steps
+    );
+    if ascii {
+        for line in context(&a) {
+            println!("\x1b[90m  | {line}\x1b[m");
+        }
+    }
+    println!("\x1b[33ma\x1b[m  {}", describe(&a, &divergence.a));
+    println!("\x1b[36mb\x1b[m  {}", describe(&b, &divergence.b));
+
+    let cells = &divergence.memory;
+    println!("{} cells differ", cells.len());
+    for (addr, in_a, in_b) in cells.iter().take(SHOWN_CELLS) {
+        println!("\x1b[34m{addr:6}\x1b[m  {in_a} -> {in_b}");
+    }
+    if cells.len() > SHOWN_CELLS {
+        println!("  ...and {} more", cells.len() - SHOWN_CELLS);
+    }
+}
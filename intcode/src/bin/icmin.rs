@@ -0,0 +1,160 @@
+use std::{
+    env, fs,
+    process::{self, Command, Stdio},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use intcode::{Cpu, Verbosity, minimize};
+use utils::parse::{parse_intcode, read_intcode};
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+// Rust exits with 101 on panic, so the child uses its own code for an IntcodeError
+const PANIC_EXIT: i32 = 101;
+const ERROR_EXIT: i32 = 3;
+
+fn usage() {
+    println!("usage: icmin <program> [--input 1,2,3] [--error] [--message TEXT]");
+    println!("shrinks a program that makes the interpreter panic; with --error, one that");
+    println!("makes it stop with an IntcodeError instead. --message only counts failures");
+    println!("whose panic or error text contains TEXT. The result is written to <program>.min");
+}
+
+fn get_program(path: &str) -> Vec<i64> {
+    read_intcode(path).unwrap_or_else(|err| {
+        println!("\x1b[31;1m{err}\x1b[m");
+        process::exit(2);
+    })
+}
+
+fn to_text(program: &[i64]) -> String {
+    let nums: Vec<String> = program.iter().map(|num| num.to_string()).collect();
+    nums.join(",")
+}
+
+// Child mode: run the program once so the parent can watch for a panic. An error is
+// reported on stderr with its own exit code, so it isn't mistaken for a panic.
+fn run_once(path: &str, input: &[i64]) {
+    let program = get_program(path);
+    let result = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .input(input)
+        .build()
+        .and_then(|mut cpu| cpu.run());
+    if let Err(err) = result {
+        eprintln!("{err}");
+        process::exit(ERROR_EXIT);
+    }
+}
+
+// Runs a candidate in a child process, so panics and infinite loops can't take down the
+// minimizer. True if the child panicked, or with `error` stopped with an IntcodeError,
+// and its message contains `message`, if one was given.
+fn candidate_fails(program: &[i64], input: &str, error: bool, message: Option<&str>) -> bool {
+    if program.is_empty() {
+        return false;
+    }
+    let path = env::temp_dir().join(format!("icmin-{}.txt", process::id()));
+    fs::write(&path, to_text(program)).expect("Failed to write candidate");
+
+    let mut child = Command::new(env::current_exe().expect("No path to icmin"))
+        .args([
+            "--run",
+            path.to_str().expect("Bad temp path"),
+            "--input",
+            input,
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .env("RUST_BACKTRACE", "0")
+        .spawn()
+        .expect("Failed to launch child");
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().expect("Failed to wait on child") {
+            break status;
+        }
+        if start.elapsed() > TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return false;
+        }
+        sleep(Duration::from_millis(1));
+    };
+
+    let expected = if error { ERROR_EXIT } else { PANIC_EXIT };
+    if status.code() != Some(expected) {
+        return false;
+    }
+    let Some(message) = message else {
+        return true;
+    };
+    let output = child
+        .wait_with_output()
+        .expect("Failed to read child output");
+    String::from_utf8_lossy(&output.stderr).contains(message)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut path: Option<&String> = None;
+    let mut input = String::new();
+    let mut message: Option<&String> = None;
+    let mut error = false;
+    let mut child = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--run" => {
+                child = true;
+                path = iter.next();
+            }
+            "--input" => input = iter.next().expect("--input needs values").clone(),
+            "--error" => error = true,
+            "--message" | "--panic-message" => {
+                message = Some(iter.next().expect("--message needs text"))
+            }
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else {
+        usage();
+        return;
+    };
+    let input_values: Vec<i64> = if input.trim().is_empty() {
+        Vec::new()
+    } else {
+        parse_intcode(&input).expect("--input needs comma separated values")
+    };
+
+    if child {
+        run_once(path, &input_values);
+        return;
+    }
+
+    let program = get_program(path);
+    let message = message.map(|m| m.as_str());
+    if !candidate_fails(&program, &input, error, message) {
+        println!("\x1b[31;1mProgram does not reproduce the failure, nothing to minimize\x1b[m");
+        process::exit(1);
+    }
+
+    let mut tries = 0;
+    let minimal = minimize(&program, |candidate| {
+        tries += 1;
+        candidate_fails(candidate, &input, error, message)
+    });
+    let _ = fs::remove_file(env::temp_dir().join(format!("icmin-{}.txt", process::id())));
+
+    let out_path = format!("{path}.min");
+    fs::write(&out_path, to_text(&minimal) + "\n").expect("Failed to write minimized program");
+    println!(
+        "\x1b[32;1m{} -> {} cells\x1b[m after {tries} runs, written to {out_path}",
+        program.len(),
+        minimal.len()
+    );
+    println!("{}", to_text(&minimal));
+}
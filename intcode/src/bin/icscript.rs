@@ -0,0 +1,31 @@
+use std::{env, fs, process};
+
+use intcode::script::run_script;
+use utils::parse::read_intcode;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        println!("usage: icscript <program> <script.rhai>");
+        println!("runs a Rhai script with the program bound to `program`");
+        return;
+    }
+
+    let program = read_intcode(&args[1]).unwrap_or_else(|err| {
+        println!("\x1b[31;1m{err}\x1b[m");
+        process::exit(2);
+    });
+    let source = fs::read_to_string(&args[2]).unwrap_or_else(|err| {
+        println!("\x1b[31;1m{}: {err}\x1b[m", args[2]);
+        process::exit(2);
+    });
+
+    match run_script(&source, &program) {
+        Ok(result) if result.is_unit() => (),
+        Ok(result) => println!("{result}"),
+        Err(err) => {
+            println!("\x1b[31;1m{err}\x1b[m");
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::{collections::HashSet, ops::Range};
+
+use crate::{Decoded, Op};
+
+/// A run of instructions that is only entered at the top and only left at the bottom,
+/// from [`basic_blocks`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BasicBlock {
+    pub start: usize,
+    /// One past its last cell
+    pub end: usize,
+    /// Its lines of the disassembly it was found in
+    pub lines: Range<usize>,
+}
+
+/// Splits a disassembly into basic blocks. A block starts at the top, at each fixed
+/// jump target and after each jump, halt or run of data, so that straight-line code
+/// stays together. Computed jumps can land anywhere, but in practice they return to
+/// just after a jump, which starts a block anyway.
+pub fn basic_blocks(lines: &[Decoded]) -> Vec<BasicBlock> {
+    let targets: HashSet<usize> = lines.iter().filter_map(Decoded::jump_target).collect();
+
+    let mut blocks = Vec::new();
+    let mut first = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let next = lines.get(i + 1);
+        let ends_here = match line {
+            Decoded::Instruction { op, .. } => {
+                matches!(op, Op::Jnz | Op::Jz | Op::Hlt)
+                    || next.is_none_or(|next| {
+                        targets.contains(&next.addr()) || matches!(next, Decoded::Data { .. })
+                    })
+            }
+            Decoded::Data { .. } => {
+                next.is_none_or(|next| matches!(next, Decoded::Instruction { .. }))
+            }
+        };
+        if ends_here {
+            blocks.push(BasicBlock {
+                start: lines[first].addr(),
+                end: line.addr() + line.size(),
+                lines: first..i + 1,
+            });
+            first = i + 1;
+        }
+    }
+    blocks
+}
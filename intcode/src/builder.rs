@@ -0,0 +1,232 @@
+use std::path::PathBuf;
+
+use crate::{
+    AsciiComputer, Cpu, CpuMode, Heatmap, InputSource, IntcodeError, IoLog, JsonTrace, Logger,
+    OutputSink, Profile, Ticker, Verbosity, Word,
+};
+
+/// Settings for a new [`Cpu`], from [`Cpu::builder`]. Anything not set matches
+/// [`Cpu::new`]. For cells other than `i64`, start from `CpuBuilder::<W>::default()`.
+pub struct CpuBuilder<'a, W: Word = i64> {
+    program: Option<&'a [W]>,
+    memory_limit: Option<usize>,
+    verbosity: Verbosity,
+    logger: Option<Box<dyn Logger<W>>>,
+    strict: bool,
+    deadlock_after: Option<u32>,
+    mode: CpuMode,
+    input: Vec<W>,
+    input_source: Option<Box<dyn InputSource<W>>>,
+    output_sink: Option<Box<dyn OutputSink<W>>>,
+    profile: bool,
+    heatmap: bool,
+    stats: bool,
+    coverage: bool,
+    self_modifying: bool,
+    decode_cache: bool,
+    io_log: bool,
+    ticker: Option<Ticker>,
+    json_trace: Option<JsonTrace>,
+    core_dump_path: Option<PathBuf>,
+}
+
+impl<W: Word> Default for CpuBuilder<'_, W> {
+    fn default() -> Self {
+        Self {
+            program: None,
+            memory_limit: None,
+            verbosity: Verbosity::Io,
+            logger: None,
+            strict: false,
+            deadlock_after: None,
+            mode: CpuMode::Normal,
+            input: Vec::new(),
+            input_source: None,
+            output_sink: None,
+            profile: false,
+            heatmap: false,
+            stats: false,
+            coverage: false,
+            self_modifying: false,
+            decode_cache: true,
+            io_log: false,
+            ticker: None,
+            json_trace: None,
+            core_dump_path: None,
+        }
+    }
+}
+
+impl<'a, W: Word> CpuBuilder<'a, W> {
+    /// Program to load. Without one the machine starts halted.
+    pub fn program(mut self, program: &'a [W]) -> Self {
+        self.program = Some(program);
+        self
+    }
+
+    /// Caps memory at `limit` cells. By default it grows as the program needs.
+    pub fn memory_limit(mut self, limit: usize) -> Self {
+        self.memory_limit = Some(limit);
+        self
+    }
+
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Logs events to `logger` instead of printing them.
+    pub fn logger(mut self, logger: impl Logger<W> + 'static) -> Self {
+        self.logger = Some(Box::new(logger));
+        self
+    }
+
+    /// Make overflowing arithmetic an error; see [`Cpu::strict`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Fail after waiting for input `waits` times in a row; see [`Cpu::deadlock_after`].
+    pub fn deadlock_after(mut self, waits: u32) -> Self {
+        self.deadlock_after = Some(waits);
+        self
+    }
+
+    pub fn mode(mut self, mode: CpuMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Queues `values` as input, to be read in order.
+    pub fn input(mut self, values: &[W]) -> Self {
+        self.input.extend_from_slice(values);
+        self
+    }
+
+    /// Reads input from `source` instead of `io_in`.
+    pub fn input_source(mut self, source: impl InputSource<W> + 'static) -> Self {
+        self.input_source = Some(Box::new(source));
+        self
+    }
+
+    /// Sends outputs to `sink` instead of `io_out`.
+    pub fn output_sink(mut self, sink: impl OutputSink<W> + 'static) -> Self {
+        self.output_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Count executed instructions in [`Cpu::profile`].
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Count memory accesses in [`Cpu::heatmap`].
+    pub fn heatmap(mut self, heatmap: bool) -> Self {
+        self.heatmap = heatmap;
+        self
+    }
+
+    /// Count retired instructions in [`Cpu::stats`].
+    pub fn stats(mut self, stats: bool) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Record writes over code already run in [`Cpu::self_modifying`].
+    pub fn self_modifying(mut self, self_modifying: bool) -> Self {
+        self.self_modifying = self_modifying;
+        self
+    }
+
+    /// Keep decoded instructions by address; see [`Cpu::set_decode_cache`].
+    pub fn decode_cache(mut self, decode_cache: bool) -> Self {
+        self.decode_cache = decode_cache;
+        self
+    }
+
+    /// Record executed addresses in [`Cpu::coverage`].
+    pub fn coverage(mut self, coverage: bool) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// Record inputs and outputs in [`Cpu::io_log`].
+    pub fn io_log(mut self, io_log: bool) -> Self {
+        self.io_log = io_log;
+        self
+    }
+
+    /// Writes a [`CoreDump`](crate::CoreDump) to `path` if an instruction fails.
+    pub fn core_dump(mut self, path: impl Into<PathBuf>) -> Self {
+        self.core_dump_path = Some(path.into());
+        self
+    }
+
+    /// Writes each retired instruction to `trace`; see [`JsonTrace`].
+    pub fn json_trace(mut self, trace: JsonTrace) -> Self {
+        self.json_trace = Some(trace);
+        self
+    }
+
+    /// Calls `callback` every `every` retired instructions; see [`Ticker`].
+    pub fn ticker(mut self, every: u64, callback: impl FnMut(u64) + 'static) -> Self {
+        self.ticker = Some(Ticker::new(every, callback));
+        self
+    }
+
+    /// Fails if the program doesn't fit in memory.
+    pub fn build(self) -> Result<Cpu<W>, IntcodeError> {
+        let mut cpu = Cpu::default();
+        cpu.set_memory_limit(self.memory_limit);
+        if let Some(program) = self.program {
+            cpu.load_program(program)?;
+        }
+        cpu.verbosity = self.verbosity;
+        cpu.logger = self.logger;
+        cpu.strict = self.strict;
+        cpu.deadlock_after = self.deadlock_after;
+        cpu.mode = self.mode;
+        for value in self.input {
+            cpu.io_in.push_front(value);
+        }
+        cpu.input_source = self.input_source;
+        cpu.output_sink = self.output_sink;
+        if self.profile {
+            cpu.profile = Some(Profile::new());
+        }
+        if self.heatmap {
+            cpu.heatmap = Some(Heatmap::new());
+        }
+        cpu.set_stats(self.stats);
+        cpu.set_coverage(self.coverage);
+        cpu.set_decode_cache(self.decode_cache);
+        if self.self_modifying {
+            cpu.set_self_modifying(true);
+        }
+        cpu.ticker = self.ticker;
+        cpu.json_trace = self.json_trace;
+        cpu.core_dump_path = self.core_dump_path;
+        if self.io_log {
+            cpu.io_log = Some(IoLog::new());
+        }
+        Ok(cpu)
+    }
+}
+
+impl<'a> CpuBuilder<'a> {
+    /// Builds an [`AsciiComputer`] instead of a bare [`Cpu`].
+    pub fn ascii_mode(self) -> AsciiBuilder<'a> {
+        AsciiBuilder(self)
+    }
+}
+
+/// A [`CpuBuilder`] that builds an [`AsciiComputer`].
+pub struct AsciiBuilder<'a>(CpuBuilder<'a>);
+
+impl AsciiBuilder<'_> {
+    pub fn build(self) -> Result<AsciiComputer, IntcodeError> {
+        Ok(AsciiComputer::new(self.0.build()?))
+    }
+}
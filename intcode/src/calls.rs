@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use crate::{Decoded, Op, Operand};
+
+/// A subroutine found by [`find_functions`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Function {
+    pub entry: usize,
+    /// One past its last return, or past its last instruction if it never returns
+    pub end: usize,
+    /// Cells its opening `AdjBp` reserves on the stack, if it opens with one
+    pub frame: Option<i64>,
+    /// Addresses of the jumps that call it
+    pub call_sites: Vec<usize>,
+}
+
+/// Finds the subroutines of a disassembly compiled with the relative base stack
+/// convention of the puzzle programs. A call stores its return address at `bp+0` and
+/// jumps, and the subroutine moves `bp` up past its frame, moves it back down and
+/// jumps to `bp+0` to return:
+///
+/// ```text
+/// Add #0 #return bp+0      Jnz #1 #entry      ; call
+/// AdjBp #3  ...  AdjBp #-3  Jz #0 bp+0        ; entry ... return
+/// ```
+///
+/// A subroutine ends at its last return before the next one starts, so code reached
+/// only by jumps past that is left out.
+pub fn find_functions(lines: &[Decoded]) -> Vec<Function> {
+    let mut calls: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for pair in lines.windows(2) {
+        if let Some(entry) = call_target(&pair[0], &pair[1]) {
+            calls.entry(entry).or_default().push(pair[1].addr());
+        }
+    }
+    // Only targets that start a line; others mean the listing is out of step there
+    calls.retain(|entry, _| lines.iter().any(|line| line.addr() == *entry));
+
+    let entries: Vec<usize> = calls.keys().copied().collect();
+    let mut functions = Vec::new();
+    for (i, (entry, call_sites)) in calls.into_iter().enumerate() {
+        let next = entries.get(i + 1).copied().unwrap_or(usize::MAX);
+        let body: Vec<&Decoded> = lines
+            .iter()
+            .filter(|line| (entry..next).contains(&line.addr()))
+            .collect();
+        let last_return = body.iter().rev().find(|line| is_return(line));
+        let last_instruction = body
+            .iter()
+            .rev()
+            .find(|line| matches!(line, Decoded::Instruction { .. }));
+        let end = last_return
+            .or(last_instruction)
+            .map_or(entry, |line| line.addr() + line.size());
+        let frame = match body.first() {
+            Some(Decoded::Instruction {
+                op: Op::AdjBp,
+                operands,
+                ..
+            }) => match operands.first() {
+                Some(Operand::Immediate(size)) => Some(*size),
+                _ => None,
+            },
+            _ => None,
+        };
+        functions.push(Function {
+            entry,
+            end,
+            frame,
+            call_sites,
+        });
+    }
+    functions
+}
+
+// Where `jump` calls, if `store` saves the address after it at `bp+0` first
+fn call_target(store: &Decoded, jump: &Decoded) -> Option<usize> {
+    let Decoded::Instruction { op, operands, .. } = store else {
+        return None;
+    };
+    let saved = match operands[..] {
+        [
+            Operand::Immediate(a),
+            Operand::Immediate(b),
+            Operand::Relative(0),
+        ] => match op {
+            Op::Add => a.checked_add(b)?,
+            Op::Mul => a.checked_mul(b)?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let return_addr = jump.addr() + jump.size();
+    if jump.fixed_condition() != Some(true) || usize::try_from(saved) != Ok(return_addr) {
+        return None;
+    }
+    jump.jump_target()
+}
+
+// A jump that always goes to the address saved at `bp+0`
+fn is_return(line: &Decoded) -> bool {
+    match line {
+        Decoded::Instruction {
+            op: Op::Jnz | Op::Jz,
+            operands,
+            ..
+        } => line.fixed_condition() == Some(true) && operands.get(1) == Some(&Operand::Relative(0)),
+        _ => false,
+    }
+}
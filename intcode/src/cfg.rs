@@ -0,0 +1,86 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{BasicBlock, Decoded, Op, Profile, basic_blocks};
+
+/// Draws the control flow graph of a disassembly in Graphviz DOT, for `dot -Tsvg`.
+/// Each basic block of code is a node listing its instructions, with an edge for each
+/// jump and fall through. Jumps to computed addresses, or into the middle of an
+/// instruction, go to an `elsewhere` node.
+///
+/// With a `profile`, each node also shows how many instructions ran in it and is
+/// shaded from white for blocks that never ran to red for the hottest.
+pub fn cfg_dot(lines: &[Decoded], profile: Option<&Profile>) -> String {
+    let blocks: Vec<BasicBlock> = basic_blocks(lines)
+        .into_iter()
+        .filter(|block| matches!(lines[block.lines.start], Decoded::Instruction { .. }))
+        .collect();
+    let starts: HashMap<usize, &BasicBlock> =
+        blocks.iter().map(|block| (block.start, block)).collect();
+    let counts: HashMap<usize, u64> = profile
+        .map(|profile| profile.hot_blocks(&blocks))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(block, count)| (block.start, count))
+        .collect();
+    let max = counts.values().copied().max().unwrap_or(0).max(1);
+
+    let mut out = String::from("digraph intcode {\n");
+    out.push_str("    node [shape=box, fontname=monospace, style=filled, fillcolor=white];\n");
+    let mut elsewhere = false;
+    for block in &blocks {
+        let mut label = String::new();
+        for line in &lines[block.lines.clone()] {
+            let _ = write!(label, "{:6}  {line}\\l", line.addr());
+        }
+        let mut attrs = String::new();
+        if profile.is_some() {
+            let count = counts.get(&block.start).copied().unwrap_or(0);
+            let _ = write!(label, "{count} executed\\l");
+            let heat = count as f64 / max as f64;
+            let _ = write!(attrs, ", fillcolor=\"0.000 {heat:.3} 1.000\"");
+        }
+        let _ = writeln!(
+            out,
+            "    b{} [label=\"{}\"{attrs}];",
+            block.start,
+            label.replace('"', "\\\"")
+        );
+
+        let last = &lines[block.lines.end - 1];
+        let falls_through = match last {
+            Decoded::Instruction { op: Op::Hlt, .. } => false,
+            Decoded::Instruction {
+                op: Op::Jnz | Op::Jz,
+                ..
+            } => {
+                let fixed = last.fixed_condition();
+                if fixed != Some(false) {
+                    match last.jump_target() {
+                        Some(target) if starts.contains_key(&target) => {
+                            let _ = writeln!(
+                                out,
+                                "    b{} -> b{target} [label=\"jump\"];",
+                                block.start
+                            );
+                        }
+                        _ => {
+                            elsewhere = true;
+                            let _ =
+                                writeln!(out, "    b{} -> elsewhere [style=dashed];", block.start);
+                        }
+                    }
+                }
+                fixed != Some(true)
+            }
+            _ => true,
+        };
+        if falls_through && starts.contains_key(&block.end) {
+            let _ = writeln!(out, "    b{} -> b{};", block.start, block.end);
+        }
+    }
+    if elsewhere {
+        out.push_str("    elsewhere [shape=ellipse];\n");
+    }
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,66 @@
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+
+use crate::{InputResult, InputSource, OutputResult, OutputSink};
+
+/// An [`InputSource`] reading from a channel, so machines on separate threads can
+/// feed each other.
+///
+/// Once every sender is gone the machine waits for input that will never come, as
+/// with an empty `io_in`.
+pub struct ChannelInput {
+    rx: Receiver<i64>,
+    idle: Option<i64>,
+}
+
+impl ChannelInput {
+    /// Blocks until a value arrives.
+    pub fn new(rx: Receiver<i64>) -> Self {
+        Self { rx, idle: None }
+    }
+
+    /// Never blocks: when nothing has arrived it reads `value` and pauses, like a day
+    /// 23 NIC reading -1.
+    pub fn idle(rx: Receiver<i64>, value: i64) -> Self {
+        Self {
+            rx,
+            idle: Some(value),
+        }
+    }
+}
+
+impl InputSource for ChannelInput {
+    fn next_input(&mut self) -> InputResult {
+        let Some(idle) = self.idle else {
+            return match self.rx.recv() {
+                Ok(value) => InputResult::Value(value),
+                Err(_) => InputResult::Pending,
+            };
+        };
+        match self.rx.try_recv() {
+            Ok(value) => InputResult::Value(value),
+            Err(TryRecvError::Empty) => InputResult::Yield(idle),
+            Err(TryRecvError::Disconnected) => InputResult::Pending,
+        }
+    }
+}
+
+/// An [`OutputSink`] sending to a channel. Outputs are rejected once the receiver is
+/// gone.
+pub struct ChannelOutput {
+    tx: Sender<i64>,
+}
+
+impl ChannelOutput {
+    pub fn new(tx: Sender<i64>) -> Self {
+        Self { tx }
+    }
+}
+
+impl OutputSink for ChannelOutput {
+    fn write_output(&mut self, value: i64) -> OutputResult {
+        match self.tx.send(value) {
+            Ok(()) => OutputResult::Continue,
+            Err(_) => OutputResult::Reject,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+};
+
+// Zero cells a run of memory may span before the rest starts a new run
+const RUN_GAP: usize = 16;
+
+/// Everything needed to pick a machine back up where it stopped, taken with
+/// [`Cpu::core_dump`](crate::Cpu::core_dump) on demand or written on an error to
+/// [`Cpu::core_dump_path`](crate::Cpu::core_dump_path), and put back with
+/// [`Cpu::load_core_dump`](crate::Cpu::load_core_dump).
+///
+/// As text it is a line per register and queue followed by runs of memory, each
+/// starting at the address given, so a dump can be read and edited by hand:
+///
+/// ```text
+/// error invalid opcode 42 at 12 (bp 0, modes [], memory from 8: 1 2 3 4 42)
+/// ip 12
+/// bp 0
+/// halted true
+/// limit none
+/// in 1,2
+/// out 3
+/// mem 0 1101,1,2,3,4
+/// mem 1000 7
+/// ```
+///
+/// Queues are in the order they are consumed: `in` first to be read, `out` first
+/// output.
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct CoreDump<W = i64> {
+    /// Why the machine stopped, if it failed
+    pub error: Option<String>,
+    pub ip: usize,
+    pub bp: W,
+    pub halted: bool,
+    pub memory_limit: Option<usize>,
+    pub io_in: Vec<W>,
+    pub io_out: Vec<W>,
+    /// The cells that aren't 0
+    pub memory: BTreeMap<usize, W>,
+}
+
+impl<W: Copy + Default + Eq> CoreDump<W> {
+    /// The value at `addr`; cells left out of the dump are 0.
+    pub fn get(&self, addr: usize) -> W {
+        self.memory.get(&addr).copied().unwrap_or_default()
+    }
+
+    /// The cells that differ in `after`, in address order, with their values here and
+    /// there.
+    pub fn diff(&self, after: &CoreDump<W>) -> Vec<(usize, W, W)> {
+        let addrs: BTreeSet<usize> = self
+            .memory
+            .keys()
+            .chain(after.memory.keys())
+            .copied()
+            .collect();
+        addrs
+            .into_iter()
+            .map(|addr| (addr, self.get(addr), after.get(addr)))
+            .filter(|(_, before, after)| before != after)
+            .collect()
+    }
+}
+
+fn join<W: fmt::Display>(values: &[W]) -> String {
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    values.join(",")
+}
+
+impl<W: fmt::Display + Copy + Default + Eq> fmt::Display for CoreDump<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(error) = &self.error {
+            writeln!(f, "error {error}")?;
+        }
+        writeln!(f, "ip {}", self.ip)?;
+        writeln!(f, "bp {}", self.bp)?;
+        writeln!(f, "halted {}", self.halted)?;
+        match self.memory_limit {
+            Some(limit) => writeln!(f, "limit {limit}")?,
+            None => writeln!(f, "limit none")?,
+        }
+        writeln!(f, "in {}", join(&self.io_in))?;
+        writeln!(f, "out {}", join(&self.io_out))?;
+
+        let mut cells = self.memory.keys().copied().peekable();
+        while let Some(start) = cells.next() {
+            let mut end = start;
+            while let Some(&next) = cells.peek()
+                && next - end <= RUN_GAP
+            {
+                end = next;
+                cells.next();
+            }
+            let run: Vec<W> = (start..=end).map(|addr| self.get(addr)).collect();
+            writeln!(f, "mem {start} {}", join(&run))?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: FromStr + Copy + Default + Eq> FromStr for CoreDump<W> {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut dump = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (label, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let bad = |what: &str| format!("line {}: bad {what} {rest:?}", i + 1);
+            let values = |text: &str| -> Result<Vec<W>, String> {
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| {
+                        v.parse()
+                            .map_err(|_| format!("line {}: bad value {v:?}", i + 1))
+                    })
+                    .collect()
+            };
+            match label {
+                "error" => dump.error = Some(rest.to_string()),
+                "ip" => dump.ip = rest.parse().map_err(|_| bad("ip"))?,
+                "bp" => dump.bp = rest.parse().map_err(|_| bad("bp"))?,
+                "halted" => dump.halted = rest.parse().map_err(|_| bad("halted"))?,
+                "limit" => {
+                    dump.memory_limit = match rest {
+                        "none" => None,
+                        _ => Some(rest.parse().map_err(|_| bad("limit"))?),
+                    }
+                }
+                "in" => dump.io_in = values(rest)?,
+                "out" => dump.io_out = values(rest)?,
+                "mem" => {
+                    let (start, cells) = rest.split_once(' ').unwrap_or((rest, ""));
+                    let start: usize = start.parse().map_err(|_| bad("address in"))?;
+                    for (addr, value) in (start..).zip(values(cells)?) {
+                        if value != W::default() {
+                            dump.memory.insert(addr, value);
+                        }
+                    }
+                }
+                _ => return Err(format!("line {}: unknown field `{label}`", i + 1)),
+            }
+        }
+        Ok(dump)
+    }
+}
@@ -0,0 +1,65 @@
+use std::ops::Range;
+
+// Sets bit `addr` of a bitmap, growing it to reach
+fn set(bits: &mut Vec<u64>, addr: usize) {
+    let word = addr / 64;
+    if word >= bits.len() {
+        bits.resize(word + 1, 0);
+    }
+    bits[word] |= 1 << (addr % 64);
+}
+
+fn get(bits: &[u64], addr: usize) -> bool {
+    bits.get(addr / 64)
+        .is_some_and(|word| word & (1 << (addr % 64)) != 0)
+}
+
+/// Which addresses a [`Cpu`](crate::Cpu) with coverage turned on has executed, as
+/// bitmaps. Anything never executed is data, or code the run didn't reach.
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct Coverage {
+    // Addresses an instruction was decoded from
+    starts: Vec<u64>,
+    // Addresses of instructions and their operands
+    code: Vec<u64>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record(&mut self, ip: usize, n_operands: usize) {
+        set(&mut self.starts, ip);
+        for addr in ip..=ip + n_operands {
+            set(&mut self.code, addr);
+        }
+    }
+
+    /// Whether an instruction was executed from `addr`.
+    pub fn executed(&self, addr: usize) -> bool {
+        get(&self.starts, addr)
+    }
+
+    /// Whether `addr` holds an executed instruction or one of its operands.
+    pub fn is_code(&self, addr: usize) -> bool {
+        get(&self.code, addr)
+    }
+
+    /// Addresses instructions were executed from, lowest first.
+    pub fn instructions(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.starts.len() * 64).filter(|addr| self.executed(*addr))
+    }
+
+    /// Runs of consecutive code cells, lowest first.
+    pub fn code_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for addr in (0..self.code.len() * 64).filter(|addr| self.is_code(*addr)) {
+            match ranges.last_mut() {
+                Some(range) if range.end == addr => range.end += 1,
+                _ => ranges.push(addr..addr + 1),
+            }
+        }
+        ranges
+    }
+}
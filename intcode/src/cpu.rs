@@ -0,0 +1,1439 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fs,
+    io::{Write, stdin, stdout},
+    path::PathBuf,
+};
+
+#[cfg(feature = "tui")]
+use std::io::Read;
+
+#[cfg(feature = "tui")]
+use crossterm::terminal;
+
+use crate::{
+    Ansi, CoreDump, Coverage, CpuBuilder, CustomOp, DecodeContext, Decoded, Heatmap, InputResult,
+    InputSource, IntcodeError, IoLog, JsonTrace, LogEvent, Logger, Memory, Operand, OutputResult,
+    OutputSink, Profile, Stats, Ticker, Verbosity, Watch, WatchedRead, WatchedWrite, Word,
+    heatmap::Access,
+    hooks::{Hooks, Instruction, Io},
+    op::{Cmd, Op, RegMode, get_cmd},
+};
+
+// Cells either side of the instruction pointer shown when an instruction won't decode
+const DECODE_WINDOW: usize = 4;
+// Cells of a core dump below this address are loaded as the program
+const DENSE_DUMP_LIMIT: usize = 1 << 20;
+// Instructions below this address are kept decoded; code beyond is rare and would make
+// the cache as big as memory
+const DECODE_CACHE_LIMIT: usize = 1 << 20;
+
+// An instruction as decoded at some address, with the word it was decoded from
+#[derive(Clone, Copy)]
+struct Predecoded<W> {
+    word: W,
+    cmd: Cmd,
+    modes: [RegMode; 8],
+}
+
+// Parameter mode digits of an instruction, first operand first
+fn mode_digits(instruction: i64) -> impl Iterator<Item = u8> {
+    let mut digits = instruction / 100;
+    std::iter::from_fn(move || {
+        let digit = (digits % 10) as u8;
+        digits /= 10;
+        Some(digit)
+    })
+}
+
+/// How the [`Cpu`] behaves around IO, and so when [`Cpu::run`] returns early.
+#[derive(Default, Clone, Copy)]
+#[non_exhaustive]
+pub enum CpuMode {
+    /// Run until halted, or until input is needed and `io_in` is empty
+    #[default]
+    Normal,
+    /// Read joystick input from the terminal instead of `io_in`, as with a
+    /// [`Stdin`](crate::Stdin) input source, but without the prompt on a silent machine
+    ReadChar,
+    /// Day 23 NIC: an empty `io_in` reads -1, and return after each 3-value packet
+    Network(i64),
+}
+
+/// Where the [`Cpu`] stopped.
+#[derive(Default, Clone, Copy)]
+#[non_exhaustive]
+pub enum State {
+    /// Currently executing inside [`Cpu::run`]
+    Active,
+    /// Paused for IO or at a breakpoint; call [`Cpu::run`] again to resume
+    Ready,
+    /// Executed a halt instruction, or no program is loaded
+    #[default]
+    Halted,
+}
+
+/// Why [`Cpu::run`] returned.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum RunResult {
+    /// Executed a halt instruction
+    Halted,
+    /// Reached an input instruction with no input available; push some and run again
+    NeedsInput,
+    /// Paused after an output, because the output sink yielded or a NIC sent a whole
+    /// packet
+    OutputPending,
+    /// Paused after an input that asked to yield, like an idle NIC reading -1
+    InputYielded,
+    /// Paused before executing the instruction at this address, because the address
+    /// is in [`Cpu::breakpoints`] or its opcode in [`Cpu::op_breakpoints`]
+    Breakpoint(usize),
+    /// Paused after the program wrote to this address in [`Cpu::watchpoints`], or read
+    /// it if it is watched with [`Watch::Access`]
+    Watchpoint(usize),
+}
+
+/// What a single [`Cpu::step`] did.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum StepEvent<W = i64> {
+    /// Executed an instruction with nothing to report
+    Continued,
+    /// Executed an output instruction
+    Output(W),
+    /// Reached an input instruction with no input available; it runs on the next step
+    /// once input has been pushed
+    NeedsInput,
+    /// Executed a halt instruction
+    Halted,
+}
+
+/// Where [`Cpu::run_for`] stopped.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SliceResult {
+    /// Used up its instructions; run it again to carry on
+    Expired,
+    /// Stopped early, for the same reason [`Cpu::run`] would have
+    Stopped(RunResult),
+}
+
+/// Where [`Cpu::resume`] stopped.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Yield<W = i64> {
+    /// Executed an output instruction
+    Output(W),
+    /// Reached an input instruction with nothing in `io_in`; resume with an input
+    NeedsInput,
+    /// Halted
+    Done,
+}
+
+/// An Intcode machine.
+///
+/// Inputs are pushed with `io_in.push_front` and outputs popped with
+/// `io_out.pop_back`, so both queues are consumed in the order they were filled.
+///
+/// Cells are `i64` unless another [`Word`] is asked for, as in
+/// `CpuBuilder::<i128>::default()`.
+///
+/// Only `ip`, `bp`, `io_in`, `io_out` and `state` are stable public fields; the
+/// others are unstable, see the [crate docs](crate#stability).
+pub struct Cpu<W: Word = i64> {
+    /// Instruction pointer
+    pub ip: usize,
+    /// Relative base
+    pub bp: W,
+    reg: [W; 8],
+    reg_mode: [RegMode; 8],
+    memory: Memory<W>,
+    pub io_in: VecDeque<W>,
+    pub io_out: VecDeque<W>,
+    /// When `Some`, input instructions read from this instead of `io_in`, whatever
+    /// the mode
+    pub input_source: Option<Box<dyn InputSource<W>>>,
+    /// When `Some`, [`Cpu::run`] sends outputs here instead of `io_out`
+    pub output_sink: Option<Box<dyn OutputSink<W>>>,
+    pub mode: CpuMode,
+    pub state: State,
+    /// How much to tell the logger
+    pub verbosity: Verbosity,
+    /// When `Some`, events are logged here instead of printed by [`Ansi`]
+    pub logger: Option<Box<dyn Logger<W>>>,
+    // The default logger, made when first needed so silent machines don't buffer
+    ansi: Option<Ansi>,
+    /// Stop with [`IntcodeError::Overflow`] when an add or multiply overflows, instead
+    /// of wrapping
+    pub strict: bool,
+    /// When `Some`, asking for input this many times in a row without getting any
+    /// fails with [`IntcodeError::Deadlocked`], rather than waiting forever
+    pub deadlock_after: Option<u32>,
+    /// When `Some`, every executed opcode is appended
+    pub op_log: Option<Vec<Op>>,
+    /// When `Some`, every executed instruction is counted by opcode and address
+    pub profile: Option<Profile>,
+    /// When `Some`, every memory access is counted by address
+    pub heatmap: Option<Heatmap>,
+    /// When `Some`, called back every so many retired instructions
+    pub ticker: Option<Ticker>,
+    /// When `Some`, every retired instruction is written out as a line of JSON
+    pub json_trace: Option<JsonTrace>,
+    /// When `Some`, every value read and output is recorded, for [`Cpu::replay`]
+    pub io_log: Option<IoLog<W>>,
+    /// When `Some`, an instruction that fails writes a [`CoreDump`] of the machine to
+    /// this file, replacing it
+    pub core_dump_path: Option<PathBuf>,
+    /// [`Cpu::run`] pauses before executing an instruction at any of these addresses
+    pub breakpoints: BTreeSet<usize>,
+    /// [`Cpu::run`] pauses before executing any instruction with one of these opcodes
+    pub op_breakpoints: BTreeSet<Op>,
+    /// Writes by the program to any of these addresses are logged, and may pause
+    /// [`Cpu::run`]
+    pub watchpoints: BTreeMap<usize, Watch>,
+    last_watched: Option<WatchedWrite<W>>,
+    last_watched_read: Option<WatchedRead<W>>,
+    // Set by a write to a pausing watchpoint during the current step
+    watch_pause: Option<usize>,
+    // The breakpoint `run` last stopped at, passed over once if it carries on from there
+    resume_from_bp: Option<usize>,
+    // Input instructions that found nothing to read since the last one that did
+    starved: u32,
+    // The program last loaded, for Cpu::reset
+    program: Vec<W>,
+    // Cells of a loaded core dump too far out to go in `program`, also put back by reset
+    program_sparse: Vec<(usize, W)>,
+    custom_ops: BTreeMap<u8, CustomOp<W>>,
+    // Instructions already decoded, by address
+    decoded: Vec<Option<Predecoded<W>>>,
+    // Whether to fill `decoded` at all
+    decode_cache: bool,
+    hooks: Hooks<W>,
+    peak: Option<usize>,
+    stats: Option<Stats>,
+    coverage: Option<Coverage>,
+    self_modifying: Option<Vec<WatchedWrite<W>>>,
+    // Instructions retired over the machine's life, see `retired`
+    retired: u64,
+}
+
+impl Cpu {
+    /// A machine whose memory grows as the program uses it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts configuring a machine, as an alternative to setting fields after
+    /// [`Cpu::new`].
+    pub fn builder<'a>() -> CpuBuilder<'a> {
+        CpuBuilder::default()
+    }
+
+    /// A machine with at most `limit` cells of memory, so a runaway program fails
+    /// instead of reaching further.
+    pub fn with_memory_limit(limit: usize) -> Self {
+        Self::with_memory(Memory::with_limit(limit))
+    }
+}
+
+impl<W: Word> Cpu<W> {
+    fn with_memory(memory: Memory<W>) -> Self {
+        Self {
+            ip: 0,
+            bp: W::default(),
+            reg: [W::default(); 8],
+            reg_mode: [RegMode::Pos; 8],
+            memory,
+            io_in: VecDeque::new(),
+            io_out: VecDeque::new(),
+            input_source: None,
+            output_sink: None,
+            mode: CpuMode::Normal,
+            state: State::Halted,
+            verbosity: Verbosity::Io,
+            logger: None,
+            ansi: None,
+            strict: false,
+            deadlock_after: None,
+            op_log: None,
+            profile: None,
+            heatmap: None,
+            ticker: None,
+            json_trace: None,
+            io_log: None,
+            core_dump_path: None,
+            breakpoints: BTreeSet::new(),
+            op_breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            last_watched: None,
+            last_watched_read: None,
+            watch_pause: None,
+            resume_from_bp: None,
+            starved: 0,
+            program: Vec::new(),
+            program_sparse: Vec::new(),
+            custom_ops: BTreeMap::new(),
+            decoded: Vec::new(),
+            decode_cache: true,
+            hooks: Hooks::default(),
+            peak: None,
+            stats: None,
+            coverage: None,
+            self_modifying: None,
+            retired: 0,
+        }
+    }
+
+    /// The machine's memory, for reading. Change it with [`Cpu::poke`].
+    pub fn memory(&self) -> &Memory<W> {
+        &self.memory
+    }
+
+    /// The value at `addr`; cells never written read as 0.
+    pub fn peek(&self, addr: usize) -> Result<W, IntcodeError> {
+        self.memory.get(addr).ok_or(IntcodeError::OutOfBounds {
+            ip: self.ip,
+            addr: addr as i64,
+        })
+    }
+
+    /// Sets the cell at `addr`, growing memory to reach it.
+    pub fn poke(&mut self, addr: usize, value: W) -> Result<(), IntcodeError> {
+        let ip = self.ip;
+        let cell = self.memory.get_mut(addr).ok_or(IntcodeError::OutOfBounds {
+            ip,
+            addr: addr as i64,
+        })?;
+        *cell = value;
+        Ok(())
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory.limit()
+    }
+
+    /// Caps memory at `limit` cells, or lifts the cap with `None`. Cells already
+    /// written past a new limit become unreachable.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory.set_limit(limit);
+    }
+
+    /// The highest address the program has read, written or executed since it was
+    /// loaded, or `None` if it hasn't run.
+    pub fn peak_address(&self) -> Option<usize> {
+        self.peak
+    }
+
+    /// The latest write to an address in [`Cpu::watchpoints`], if there has been one.
+    pub fn last_watched(&self) -> Option<&WatchedWrite<W>> {
+        self.last_watched.as_ref()
+    }
+
+    /// The latest read of an address watched with [`Watch::Access`], if there has been
+    /// one.
+    pub fn last_watched_read(&self) -> Option<&WatchedRead<W>> {
+        self.last_watched_read.as_ref()
+    }
+
+    /// Counts of the instructions retired since stats were turned on, or `None` if
+    /// they are off.
+    pub fn stats(&self) -> Option<&Stats> {
+        self.stats.as_ref()
+    }
+
+    /// Instructions retired since the machine was built, counted whether or not stats
+    /// are on. Resets and restored snapshots don't take anything off.
+    pub fn retired(&self) -> u64 {
+        self.retired
+    }
+
+    /// Turns counting retired instructions on, starting from zero, or off.
+    pub fn set_stats(&mut self, enabled: bool) {
+        self.stats = enabled.then(Stats::new);
+    }
+
+    /// The addresses executed since coverage was turned on, or `None` if it is off.
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Turns recording executed addresses on, starting from nothing, or off.
+    pub fn set_coverage(&mut self, enabled: bool) {
+        self.coverage = enabled.then(Coverage::new);
+    }
+
+    /// Whether decoded instructions are kept by address, so code run again isn't
+    /// decoded again. On by default.
+    pub fn decode_cache(&self) -> bool {
+        self.decode_cache
+    }
+
+    /// Turns keeping decoded instructions on or off. The cache takes up to 40MB for
+    /// code spread over the first million cells; with it off every instruction is
+    /// decoded as it runs, which is slower but can be checked against the cache.
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decode_cache = enabled;
+        if !enabled {
+            self.decoded = Vec::new();
+        }
+    }
+
+    /// Writes by the program to cells that had already run as code, oldest first, or
+    /// `None` if detection is off.
+    pub fn self_modifying(&self) -> Option<&[WatchedWrite<W>]> {
+        self.self_modifying.as_deref()
+    }
+
+    /// Turns detecting self-modifying code on, starting from nothing, or off. It
+    /// relies on [`Cpu::coverage`] to know what has run, so turning it on turns coverage
+    /// on too if needed, and it stops noticing writes if coverage is turned off.
+    pub fn set_self_modifying(&mut self, enabled: bool) {
+        self.self_modifying = enabled.then(Vec::new);
+        if enabled && self.coverage.is_none() {
+            self.set_coverage(true);
+        }
+    }
+
+    /// Calls `hook` before each instruction executes, with the instruction as it is in
+    /// memory. Unlike tracing through the logger, this doesn't depend on the verbosity.
+    pub fn on_before_instruction(&mut self, hook: impl FnMut(&Instruction<W>) + 'static) {
+        self.hooks.before.push(Box::new(hook));
+    }
+
+    /// Calls `hook` after each write by the program, but not by [`Cpu::poke`].
+    pub fn on_memory_write(&mut self, hook: impl FnMut(&WatchedWrite<W>) + 'static) {
+        self.hooks.write.push(Box::new(hook));
+    }
+
+    /// Calls `hook` with each value the program reads or outputs.
+    pub fn on_io(&mut self, hook: impl FnMut(Io<W>) + 'static) {
+        self.hooks.io.push(Box::new(hook));
+    }
+
+    /// Removes every hook added with the `on_` methods.
+    pub fn clear_hooks(&mut self) {
+        self.hooks = Hooks::default();
+    }
+
+    fn touch(&mut self, addr: usize, access: Access) {
+        if self.peak.is_none_or(|peak| addr > peak) {
+            self.peak = Some(addr);
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record(addr, access);
+        }
+    }
+
+    /// Copies the state of the machine, so it can be put back with [`Cpu::restore`]
+    /// after trying something. The copy costs about as much as the memory in use.
+    pub fn snapshot(&self) -> CpuSnapshot<W> {
+        CpuSnapshot {
+            ip: self.ip,
+            bp: self.bp,
+            memory: self.memory.clone(),
+            io_in: self.io_in.clone(),
+            io_out: self.io_out.clone(),
+            mode: self.mode,
+            state: self.state,
+            peak: self.peak,
+        }
+    }
+
+    /// Puts the machine back as it was when `snapshot` was taken. The input source,
+    /// output sink and anything recording the run are left as they are.
+    pub fn restore(&mut self, snapshot: &CpuSnapshot<W>) {
+        self.ip = snapshot.ip;
+        self.bp = snapshot.bp;
+        self.memory.clone_from(&snapshot.memory);
+        self.io_in.clone_from(&snapshot.io_in);
+        self.io_out.clone_from(&snapshot.io_out);
+        self.mode = snapshot.mode;
+        self.state = snapshot.state;
+        self.peak = snapshot.peak;
+        self.resume_from_bp = None;
+    }
+
+    /// Copies the registers, IO queues and memory into a [`CoreDump`], to be saved for
+    /// post-mortem debugging. `error` is what stopped the machine, if anything did.
+    pub fn core_dump(&self, error: Option<&IntcodeError>) -> CoreDump<W> {
+        CoreDump {
+            error: error.map(|err| err.to_string()),
+            ip: self.ip,
+            bp: self.bp,
+            halted: matches!(self.state, State::Halted),
+            memory_limit: self.memory.limit(),
+            io_in: self.io_in.iter().rev().copied().collect(),
+            io_out: self.io_out.iter().rev().copied().collect(),
+            memory: self
+                .memory
+                .cells()
+                .filter(|(_, value)| *value != W::default())
+                .collect(),
+        }
+    }
+
+    /// Puts the machine in the state saved in `dump`, ready to carry on or inspect.
+    /// The memory of the dump counts as the loaded program, so [`Cpu::reset`] goes
+    /// back to it rather than to the start of the original run.
+    pub fn load_core_dump(&mut self, dump: &CoreDump<W>) {
+        self.memory.set_limit(dump.memory_limit);
+        // Cells far out are left to the sparse part of memory
+        let dense_end = dump
+            .memory
+            .range(..DENSE_DUMP_LIMIT)
+            .next_back()
+            .map_or(0, |(addr, _)| addr + 1);
+        let image: Vec<W> = (0..dense_end).map(|addr| dump.get(addr)).collect();
+        self.memory.load(&image);
+        self.program = image;
+        self.program_sparse = dump
+            .memory
+            .range(dense_end..)
+            .map(|(&addr, &value)| (addr, value))
+            .collect();
+        self.write_sparse_program();
+
+        self.restart();
+        self.ip = dump.ip;
+        self.bp = dump.bp;
+        self.io_in = dump.io_in.iter().rev().copied().collect();
+        self.io_out = dump.io_out.iter().rev().copied().collect();
+        self.state = if dump.halted {
+            State::Halted
+        } else {
+            State::Ready
+        };
+    }
+
+    /// Resets the machine and copies `program` to the start of memory.
+    pub fn load_program(&mut self, program: &[W]) -> Result<(), IntcodeError> {
+        if let Some(limit) = self.memory.limit()
+            && program.len() > limit
+        {
+            return Err(IntcodeError::ProgramTooLarge {
+                len: program.len(),
+                memory: limit,
+            });
+        }
+        self.program.clear();
+        self.program.extend_from_slice(program);
+        self.program_sparse.clear();
+        self.memory.load(program);
+        self.restart();
+        Ok(())
+    }
+
+    /// Resets the machine and puts back the program last given to
+    /// [`Cpu::load_program`]. Only the cells written since are copied, so this is much
+    /// cheaper than loading again for a program run many times on a large memory.
+    pub fn reset(&mut self) {
+        self.memory.reload(&self.program);
+        self.write_sparse_program();
+        self.restart();
+    }
+
+    fn write_sparse_program(&mut self) {
+        for &(addr, value) in &self.program_sparse {
+            if let Some(cell) = self.memory.get_mut(addr) {
+                *cell = value;
+            }
+        }
+    }
+
+    fn restart(&mut self) {
+        self.ip = 0;
+        self.bp = W::default();
+        self.peak = None;
+        self.starved = 0;
+        self.resume_from_bp = None;
+        self.io_in.clear();
+        self.io_out.clear();
+        self.state = State::Ready;
+    }
+
+    /// Teaches the machine an extra instruction, which programs use as `opcode` with
+    /// the usual parameter modes and show up as [`Op::Custom`]. Panics if `opcode`
+    /// isn't below 100, is one of the built in opcodes, or the instruction has more
+    /// than 8 operands.
+    pub fn register_op(&mut self, opcode: u8, op: CustomOp<W>) {
+        let built_in = (1..=9).contains(&opcode) || opcode == 99;
+        assert!(
+            opcode < 100 && !built_in,
+            "opcode {opcode} is built in or out of range"
+        );
+        assert!(op.n_operands <= self.reg.len(), "too many operands");
+        self.custom_ops.insert(opcode, op);
+        // Replacing an instruction changes how its opcode decodes
+        self.decoded.clear();
+    }
+
+    // The instruction `instruction` starts, if the machine knows it
+    fn decode(&self, instruction: i64) -> Option<Cmd> {
+        let custom = u8::try_from(instruction % 100)
+            .ok()
+            .and_then(|opcode| Some((opcode, self.custom_ops.get(&opcode)?)));
+        match custom {
+            Some((opcode, op)) => Some(Cmd {
+                op: Op::Custom(opcode),
+                n_operands: op.n_operands,
+                writes: op.writes,
+            }),
+            None => get_cmd(instruction),
+        }
+    }
+
+    /// Prints the instruction at the instruction pointer, for tracing.
+    pub fn print_cmd(&self) {
+        let Some(cmd) = self
+            .memory
+            .get(self.ip)
+            .and_then(W::to_i64)
+            .and_then(|instruction| self.decode(instruction))
+        else {
+            println!("\x1b[31mInvalid opcode at {}\x1b[m", self.ip);
+            return;
+        };
+        print!(
+            "\x1b[33m{:4}\x1b[m : \x1b[34m{:4}\x1b[m   ",
+            self.bp, self.ip
+        );
+        print!("\x1b[31m{:?}\x1b[m\t", cmd.op);
+        for i in 0..=cmd.n_operands {
+            match self.memory.get(self.ip + i) {
+                Some(value) => print!("[{value}]"),
+                None => print!("[-]"),
+            }
+        }
+        println!();
+    }
+
+    /// Disassembles up to `count` instructions starting at `addr`, stopping early at
+    /// the end of limited memory. A cell that isn't a valid instruction comes out as
+    /// data.
+    pub fn disassemble(&self, mut addr: usize, count: usize) -> Vec<Decoded<W>> {
+        let mut lines = Vec::new();
+        while lines.len() < count {
+            let Some(word) = self.memory.get(addr) else {
+                break;
+            };
+            let Some(cmd) = word
+                .to_i64()
+                .and_then(|instruction| self.decode(instruction))
+            else {
+                lines.push(Decoded::Data { addr, value: word });
+                addr += 1;
+                continue;
+            };
+            let mut modes = word.saturate() / 100;
+            let operands = (1..=cmd.n_operands)
+                .map_while(|i| self.memory.get(addr + i))
+                .map(|value| {
+                    let operand = Operand::new(modes % 10, value);
+                    modes /= 10;
+                    operand
+                })
+                .collect();
+            lines.push(Decoded::Instruction {
+                addr,
+                op: cmd.op,
+                operands,
+            });
+            addr += cmd.n_operands + 1;
+        }
+        lines
+    }
+
+    // Decodes `word`, the instruction at the instruction pointer, and sets the operand
+    // modes. Decoded instructions are kept by address and used again while the cell
+    // still holds the word they came from, so code the program overwrites is decoded
+    // afresh without having to watch for writes.
+    fn decode_at_ip(&mut self, word: W) -> Result<Cmd, IntcodeError> {
+        if let Some(Some(cached)) = self.decoded.get(self.ip)
+            && cached.word == word
+        {
+            self.reg_mode = cached.modes;
+            return Ok(cached.cmd);
+        }
+        let instruction = word.saturate();
+        let cmd = word
+            .to_i64()
+            .and_then(|instruction| self.decode(instruction))
+            .ok_or_else(|| IntcodeError::InvalidOpcode {
+                ip: self.ip,
+                value: instruction,
+                context: self.decode_context(Vec::new()),
+            })?;
+        self.get_mode(instruction, cmd.n_operands)?;
+        if self.decode_cache && self.ip < DECODE_CACHE_LIMIT {
+            if self.decoded.len() <= self.ip {
+                self.decoded.resize(self.ip + 1, None);
+            }
+            self.decoded[self.ip] = Some(Predecoded {
+                word,
+                cmd,
+                modes: self.reg_mode,
+            });
+        }
+        Ok(cmd)
+    }
+
+    fn get_mode(&mut self, instruction: i64, n_operands: usize) -> Result<(), IntcodeError> {
+        let mut digits = instruction / 100;
+
+        for i in 0..n_operands {
+            self.reg_mode[i] = match digits % 10 {
+                0 => RegMode::Pos,
+                1 => RegMode::Imm,
+                2 => RegMode::Rel,
+                _ => {
+                    let modes = mode_digits(instruction).take(i + 1).collect();
+                    return Err(IntcodeError::InvalidMode {
+                        ip: self.ip,
+                        value: instruction,
+                        context: self.decode_context(modes),
+                    });
+                }
+            };
+            digits /= 10;
+        }
+        Ok(())
+    }
+
+    // The relative base and the memory around the instruction pointer
+    fn decode_context(&self, modes: Vec<u8>) -> Box<DecodeContext> {
+        let window_start = self.ip.saturating_sub(DECODE_WINDOW);
+        let window = (window_start..=self.ip + DECODE_WINDOW)
+            .map_while(|addr| self.memory.get(addr).map(W::saturate))
+            .collect();
+        Box::new(DecodeContext {
+            bp: self.bp.saturate(),
+            window_start,
+            window,
+            modes,
+        })
+    }
+
+    fn read(&mut self, addr: W) -> Result<W, IntcodeError> {
+        let (addr, value) = to_address(addr)
+            .and_then(|addr| Some((addr, self.memory.get(addr)?)))
+            .ok_or(IntcodeError::OutOfBounds {
+                ip: self.ip,
+                addr: addr.saturate(),
+            })?;
+        self.touch(addr, Access::Read);
+        if self.watchpoints.get(&addr) == Some(&Watch::Access) {
+            let read = WatchedRead {
+                ip: self.ip,
+                addr,
+                value,
+            };
+            log_to(
+                &mut self.logger,
+                &mut self.ansi,
+                LogEvent::WatchedRead(read),
+            );
+            self.last_watched_read = Some(read);
+            self.watch_pause = Some(addr);
+        }
+        Ok(value)
+    }
+
+    fn write(&mut self, addr: W, value: W) -> Result<(), IntcodeError> {
+        let ip = self.ip;
+        let (addr, cell) = to_address(addr)
+            .and_then(|addr| Some((addr, self.memory.get_mut(addr)?)))
+            .ok_or(IntcodeError::OutOfBounds {
+                ip,
+                addr: addr.saturate(),
+            })?;
+        let old = std::mem::replace(cell, value);
+        self.touch(addr, Access::Write);
+        if let Some(trace) = &mut self.json_trace {
+            trace.write(addr, value);
+        }
+        let write = WatchedWrite {
+            ip,
+            addr,
+            old,
+            new: value,
+        };
+        for hook in &mut self.hooks.write {
+            hook(&write);
+        }
+        if let Some(writes) = &mut self.self_modifying
+            && self
+                .coverage
+                .as_ref()
+                .is_some_and(|coverage| coverage.is_code(addr))
+        {
+            writes.push(write);
+            if self.verbosity >= Verbosity::Io {
+                log_to(
+                    &mut self.logger,
+                    &mut self.ansi,
+                    LogEvent::SelfModified(write),
+                );
+            }
+        }
+        if let Some(&watch) = self.watchpoints.get(&addr) {
+            log_to(&mut self.logger, &mut self.ansi, LogEvent::Watched(write));
+            self.last_watched = Some(write);
+            if watch != Watch::Log {
+                self.watch_pause = Some(addr);
+            }
+        }
+        Ok(())
+    }
+
+    // Address of a write operand, which is never immediate
+    fn dest(&self, i: usize) -> W {
+        match self.reg_mode[i] {
+            RegMode::Rel => self.bp.wrapping_add(self.reg[i]),
+            _ => self.reg[i],
+        }
+    }
+
+    fn execute_cmd(&mut self, cmd: Cmd) -> Result<StepEvent<W>, IntcodeError> {
+        let boundary = if cmd.writes { 1 } else { 0 };
+        for i in 0..cmd.n_operands - boundary {
+            match self.reg_mode[i] {
+                RegMode::Pos => self.reg[i] = self.read(self.reg[i])?,
+                RegMode::Imm => (),
+                RegMode::Rel => self.reg[i] = self.read(self.bp.wrapping_add(self.reg[i]))?,
+            }
+        }
+
+        match cmd.op {
+            Op::Add => {
+                let sum = self.arithmetic(W::checked_add, W::wrapping_add)?;
+                self.write(self.dest(2), sum)?;
+            }
+            Op::Mul => {
+                let product = self.arithmetic(W::checked_mul, W::wrapping_mul)?;
+                self.write(self.dest(2), product)?;
+            }
+            Op::In => {
+                let input = match self.next_input() {
+                    InputResult::Value(input) => input,
+                    InputResult::Yield(input) => {
+                        self.state = State::Ready;
+                        input
+                    }
+                    InputResult::Pending => {
+                        self.state = State::Ready;
+                        self.log(LogEvent::WaitingForInput);
+                        self.starved += 1;
+                        if let Some(limit) = self.deadlock_after
+                            && self.starved >= limit
+                        {
+                            return Err(IntcodeError::Deadlocked {
+                                ip: self.ip,
+                                waits: self.starved,
+                            });
+                        }
+                        return Ok(StepEvent::NeedsInput);
+                    }
+                };
+                self.starved = 0;
+                self.log(LogEvent::Input(input));
+                self.hooks.io(Io::Input(input));
+                if let Some(io_log) = &mut self.io_log {
+                    io_log.inputs.push(input);
+                }
+                self.write(self.dest(0), input)?;
+            }
+            Op::Out => {
+                self.log(LogEvent::Output(self.reg[0]));
+                self.hooks.io(Io::Output(self.reg[0]));
+                if let Some(io_log) = &mut self.io_log {
+                    io_log.outputs.push(self.reg[0]);
+                }
+                if let CpuMode::Network(count) = self.mode {
+                    if count < 2 {
+                        self.mode = CpuMode::Network(count + 1);
+                    } else {
+                        self.mode = CpuMode::Network(0);
+                        self.state = State::Ready;
+                    }
+                }
+                self.ip += cmd.n_operands + 1;
+                return Ok(StepEvent::Output(self.reg[0]));
+            }
+            Op::Jnz => {
+                if self.reg[0] != W::default() {
+                    self.jump(self.reg[1])?;
+                    return Ok(StepEvent::Continued);
+                }
+            }
+            Op::Jz => {
+                if self.reg[0] == W::default() {
+                    self.jump(self.reg[1])?;
+                    return Ok(StepEvent::Continued);
+                }
+            }
+            Op::Lt => self.write(self.dest(2), W::from((self.reg[0] < self.reg[1]) as i64))?,
+            Op::Cmp => self.write(self.dest(2), W::from((self.reg[0] == self.reg[1]) as i64))?,
+            Op::AdjBp => self.bp = self.bp.wrapping_add(self.reg[0]),
+            Op::Hlt => {
+                self.log(LogEvent::Halted);
+                self.state = State::Halted;
+                return Ok(StepEvent::Halted);
+            }
+            Op::Custom(opcode) => {
+                let op = self
+                    .custom_ops
+                    .get_mut(&opcode)
+                    .expect("Custom ops are only decoded once registered");
+                let inputs = cmd.n_operands - boundary;
+                let result = (op.handler)(&self.reg[..inputs]);
+                if cmd.writes {
+                    self.write(self.dest(inputs), result)?;
+                }
+            }
+        }
+        self.ip += cmd.n_operands + 1;
+        Ok(StepEvent::Continued)
+    }
+
+    // Logs an input, output or halt, if the machine isn't silent
+    fn log(&mut self, event: LogEvent<W>) {
+        if self.verbosity >= Verbosity::Io {
+            log_to(&mut self.logger, &mut self.ansi, event);
+        }
+    }
+
+    // Adds or multiplies the first two operands. Overflow wraps, as it would in a
+    // release build, unless the machine is strict.
+    fn arithmetic(
+        &self,
+        checked: fn(W, W) -> Option<W>,
+        wrapping: fn(W, W) -> W,
+    ) -> Result<W, IntcodeError> {
+        let (a, b) = (self.reg[0], self.reg[1]);
+        if self.strict {
+            checked(a, b).ok_or(IntcodeError::Overflow { ip: self.ip })
+        } else {
+            Ok(wrapping(a, b))
+        }
+    }
+
+    fn next_input(&mut self) -> InputResult<W> {
+        if let Some(source) = &mut self.input_source {
+            return source.next_input();
+        }
+        match self.mode {
+            // Like a Stdin source, but the prompt is only shown if the machine isn't silent
+            CpuMode::ReadChar => {
+                self.flush_log();
+                InputResult::Value(W::from(read_key(self.verbosity >= Verbosity::Io)))
+            }
+            // An idle NIC reads -1 and lets the rest of the network run
+            CpuMode::Network(_) => match self.io_in.next_input() {
+                InputResult::Pending => InputResult::Yield(W::from(-1)),
+                result => result,
+            },
+            CpuMode::Normal => self.io_in.next_input(),
+        }
+    }
+
+    // Hands an output from the instruction at `ip` to the sink, or queues it
+    fn send_output(&mut self, ip: usize, value: W) -> Result<(), IntcodeError> {
+        let Some(sink) = &mut self.output_sink else {
+            self.io_out.push_front(value);
+            return Ok(());
+        };
+        match sink.write_output(value) {
+            OutputResult::Continue => (),
+            OutputResult::Yield => self.state = State::Ready,
+            OutputResult::Reject => {
+                self.state = State::Halted;
+                return Err(IntcodeError::RejectedOutput {
+                    ip,
+                    value: value.saturate(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn jump(&mut self, target: W) -> Result<(), IntcodeError> {
+        self.ip = to_address(target).ok_or(IntcodeError::OutOfBounds {
+            ip: self.ip,
+            addr: target.saturate(),
+        })?;
+        Ok(())
+    }
+
+    // Reads the cell `offset` past the instruction pointer
+    fn fetch(&mut self, offset: usize) -> Result<W, IntcodeError> {
+        let addr = self.ip + offset;
+        let value = self
+            .memory
+            .get(addr)
+            .ok_or(IntcodeError::TruncatedInstruction { ip: self.ip })?;
+        self.touch(addr, Access::Fetch);
+        Ok(value)
+    }
+
+    /// Pops the oldest output, or fails if the program hasn't produced one.
+    pub fn read_output(&mut self) -> Result<W, IntcodeError> {
+        self.io_out
+            .pop_back()
+            .ok_or(IntcodeError::MissingOutput { ip: self.ip })
+    }
+
+    /// Queues the bytes of `line` as input, followed by a newline.
+    pub fn push_ascii_line(&mut self, line: &str) {
+        for c in line.bytes().chain([b'\n']) {
+            self.io_in.push_front(W::from(c as i64));
+        }
+    }
+
+    /// Drains the ASCII values in `io_out` as text. Anything outside the ASCII range
+    /// (usually the puzzle answer) is left in `io_out`, in order, for
+    /// [`Cpu::drain_values`].
+    pub fn drain_ascii_output(&mut self) -> String {
+        let mut text = String::new();
+        let mut values = VecDeque::new();
+        while let Some(value) = self.io_out.pop_back() {
+            match value.to_i64() {
+                Some(c @ 0..128) => text.push(c as u8 as char),
+                _ => values.push_front(value),
+            }
+        }
+        self.io_out = values;
+        text
+    }
+
+    /// Drains `io_out`, oldest value first.
+    pub fn drain_values(&mut self) -> Vec<W> {
+        self.io_out.drain(..).rev().collect()
+    }
+
+    /// Executes a single instruction. Unlike [`Cpu::run`], an output is returned in
+    /// the event rather than sent to the output sink or `io_out`. On an error the
+    /// machine is halted with the instruction pointer left on the faulting
+    /// instruction.
+    pub fn step(&mut self) -> Result<StepEvent<W>, IntcodeError> {
+        self.state = State::Active;
+        self.watch_pause = None;
+        self.resume_from_bp = None;
+        let result = self.step_inner();
+        match &result {
+            Ok(StepEvent::NeedsInput) => (),
+            Ok(_) => {
+                self.retired += 1;
+                if let Some(ticker) = &mut self.ticker {
+                    ticker.retire();
+                }
+            }
+            Err(err) => {
+                self.state = State::Halted;
+                if let Some(path) = &self.core_dump_path {
+                    // There is nowhere to report a dump that can't be written, and the
+                    // error that matters is returned anyway
+                    let _ = fs::write(path, self.core_dump(Some(err)).to_string());
+                }
+            }
+        }
+        result
+    }
+
+    fn step_inner(&mut self) -> Result<StepEvent<W>, IntcodeError> {
+        let word = self.fetch(0)?;
+        let cmd = self.decode_at_ip(word)?;
+
+        for i in 0..cmd.n_operands {
+            self.reg[i] = self.fetch(i + 1)?;
+        }
+        if self.verbosity >= Verbosity::Trace {
+            let event = LogEvent::Instruction {
+                ip: self.ip,
+                bp: self.bp,
+                op: cmd.op,
+                instruction: word,
+                operands: &self.reg[..cmd.n_operands],
+            };
+            log_to(&mut self.logger, &mut self.ansi, event);
+        }
+        if !self.hooks.before.is_empty() {
+            let instruction = Instruction {
+                ip: self.ip,
+                bp: self.bp,
+                op: cmd.op,
+                instruction: word,
+                operands: &self.reg[..cmd.n_operands],
+            };
+            for hook in &mut self.hooks.before {
+                hook(&instruction);
+            }
+        }
+        if let Some(trace) = &mut self.json_trace {
+            let modes: Vec<u8> = self.reg_mode[..cmd.n_operands]
+                .iter()
+                .map(|mode| *mode as u8)
+                .collect();
+            trace.begin(self.ip, cmd.op, word, &self.reg[..cmd.n_operands], &modes);
+        }
+
+        let (ip, op, n_operands) = (self.ip, cmd.op, cmd.n_operands);
+        let event = self.execute_cmd(cmd)?;
+        if let Some(stats) = &mut self.stats {
+            match event {
+                StepEvent::NeedsInput => stats.waits += 1,
+                _ => stats.retire(op),
+            }
+        }
+        // An input that found nothing to read is tried again, so only counts then
+        if !matches!(event, StepEvent::NeedsInput) {
+            if let Some(log) = &mut self.op_log {
+                log.push(op);
+            }
+            if let Some(profile) = &mut self.profile {
+                profile.record(ip, op);
+            }
+            if let Some(coverage) = &mut self.coverage {
+                coverage.record(ip, n_operands);
+            }
+        }
+        if let Some(trace) = &mut self.json_trace {
+            match event {
+                StepEvent::NeedsInput => trace.discard(),
+                _ => trace.end(),
+            }
+        }
+        Ok(event)
+    }
+
+    /// Executes until the program halts, needs input or pauses, and says which. A
+    /// malformed program stops with an error and leaves the machine halted.
+    pub fn run(&mut self) -> Result<RunResult, IntcodeError> {
+        self.run_inner(None)
+    }
+
+    /// As [`Cpu::run`], but gives up with [`IntcodeError::InstructionLimit`] after
+    /// `max_instructions`, so a program stuck in a loop can't hang the caller. The
+    /// machine is left paused on the next instruction rather than halted; running it
+    /// again carries on from there.
+    pub fn run_with_limit(&mut self, max_instructions: u64) -> Result<RunResult, IntcodeError> {
+        self.run_inner(Some(max_instructions))
+    }
+
+    /// Runs at most `instructions` instructions, so a scheduler can share time between
+    /// machines fairly. Once the slice is used up the machine is paused on the next
+    /// instruction, ready to carry on.
+    pub fn run_for(&mut self, instructions: u64) -> Result<SliceResult, IntcodeError> {
+        match self.run_inner(Some(instructions)) {
+            Ok(result) => Ok(SliceResult::Stopped(result)),
+            Err(IntcodeError::InstructionLimit { .. }) => Ok(SliceResult::Expired),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Whether the instruction at the instruction pointer is at a breakpoint, by its
+    /// address or its opcode.
+    pub fn at_breakpoint(&self) -> bool {
+        if self.breakpoints.contains(&self.ip) {
+            return true;
+        }
+        if self.op_breakpoints.is_empty() {
+            return false;
+        }
+        self.memory
+            .get(self.ip)
+            .and_then(W::to_i64)
+            .and_then(|instruction| self.decode(instruction))
+            .is_some_and(|cmd| self.op_breakpoints.contains(&cmd.op))
+    }
+
+    // Sends on anything the logger has buffered, before control goes back to the caller
+    fn flush_log(&mut self) {
+        match &mut self.logger {
+            Some(logger) => logger.flush(),
+            None => {
+                if let Some(ansi) = &mut self.ansi {
+                    Logger::<W>::flush(ansi);
+                }
+            }
+        }
+    }
+
+    /// Has the next run pass over a breakpoint at the instruction pointer, as it does
+    /// when carrying on from one it stopped at. For a debugger that brings the machine
+    /// to a breakpoint some other way, such as by restoring a snapshot.
+    pub fn resume_from_breakpoint(&mut self) {
+        self.resume_from_bp = Some(self.ip);
+    }
+
+    fn run_inner(&mut self, limit: Option<u64>) -> Result<RunResult, IntcodeError> {
+        let result = self.run_loop(limit);
+        self.flush_log();
+        result
+    }
+
+    fn run_loop(&mut self, limit: Option<u64>) -> Result<RunResult, IntcodeError> {
+        let mut retired: u64 = 0;
+        let resume_from = self.resume_from_bp.take();
+        loop {
+            // The breakpoint we are carrying on from has already been reported
+            let resuming = retired == 0 && resume_from == Some(self.ip);
+            if !resuming && self.at_breakpoint() {
+                self.state = State::Ready;
+                self.resume_from_bp = Some(self.ip);
+                return Ok(RunResult::Breakpoint(self.ip));
+            }
+            if let Some(limit) = limit
+                && retired == limit
+            {
+                self.state = State::Ready;
+                return Err(IntcodeError::InstructionLimit { ip: self.ip, limit });
+            }
+            let ip = self.ip;
+            let event = self.step()?;
+            retired += 1;
+            if let StepEvent::Output(value) = event {
+                self.send_output(ip, value)?;
+            }
+            if let Some(addr) = self.watch_pause.take() {
+                self.state = State::Ready;
+                return Ok(RunResult::Watchpoint(addr));
+            }
+            if let State::Active = self.state {
+                continue;
+            }
+            return Ok(match event {
+                StepEvent::Halted => RunResult::Halted,
+                StepEvent::NeedsInput => RunResult::NeedsInput,
+                StepEvent::Output(_) => RunResult::OutputPending,
+                // Only an input can pause without an event of its own
+                StepEvent::Continued => RunResult::InputYielded,
+            });
+        }
+    }
+
+    /// Runs the machine lazily, yielding each output as it is produced. The iterator
+    /// ends when the program halts or needs input; check [`Cpu::state`] to tell
+    /// which. Outputs go to the iterator rather than the output sink or `io_out`.
+    pub fn outputs(&mut self) -> Outputs<'_, W> {
+        Outputs {
+            cpu: self,
+            done: false,
+        }
+    }
+
+    /// Runs until the next output and returns it, or `None` if the program halts or
+    /// needs input first. As with [`Cpu::outputs`], the output skips the output sink
+    /// and `io_out`.
+    pub fn run_until_output(&mut self) -> Result<Option<W>, IntcodeError> {
+        self.outputs().next().transpose()
+    }
+
+    /// Feeds the inputs of `log` to the program and checks it outputs what the log
+    /// says, in place of the input source and output sink. Stops once the program
+    /// halts or the inputs run out, so a replayed session can carry on live from
+    /// where it was recorded.
+    pub fn replay(&mut self, log: &IoLog<W>) -> Result<(), IntcodeError> {
+        let inputs: VecDeque<W> = log.inputs.iter().rev().copied().collect();
+        let source = self.input_source.replace(Box::new(inputs));
+        let result = self.replay_outputs(&log.outputs);
+        self.input_source = source;
+        result
+    }
+
+    fn replay_outputs(&mut self, expected: &[W]) -> Result<(), IntcodeError> {
+        let mut index = 0;
+        while !matches!(self.state, State::Halted) {
+            let ip = self.ip;
+            match self.step()? {
+                StepEvent::Output(value) => {
+                    if expected.get(index) != Some(&value) {
+                        return Err(IntcodeError::ReplayDiverged {
+                            ip,
+                            index,
+                            expected: expected.get(index).map(|value| value.saturate()),
+                            actual: Some(value.saturate()),
+                        });
+                    }
+                    index += 1;
+                }
+                StepEvent::Continued => (),
+                StepEvent::NeedsInput | StepEvent::Halted => break,
+            }
+        }
+        match expected.get(index) {
+            Some(value) => Err(IntcodeError::ReplayDiverged {
+                ip: self.ip,
+                index,
+                expected: Some(value.saturate()),
+                actual: None,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Drives the machine as a coroutine: queues `input`, if given, then runs until
+    /// the next output, until input is needed or until the program halts. The mode
+    /// and input source are ignored while resuming and outputs are returned rather
+    /// than sent on, so the caller alone decides what the program reads and where its
+    /// outputs go.
+    pub fn resume(&mut self, input: Option<W>) -> Result<Yield<W>, IntcodeError> {
+        if let Some(input) = input {
+            self.io_in.push_front(input);
+        }
+        if let State::Halted = self.state {
+            return Ok(Yield::Done);
+        }
+        let mode = std::mem::take(&mut self.mode);
+        let source = self.input_source.take();
+        let result = self.resume_inner();
+        self.flush_log();
+        self.mode = mode;
+        self.input_source = source;
+        result
+    }
+
+    fn resume_inner(&mut self) -> Result<Yield<W>, IntcodeError> {
+        loop {
+            match self.step()? {
+                StepEvent::Output(value) => return Ok(Yield::Output(value)),
+                StepEvent::NeedsInput => return Ok(Yield::NeedsInput),
+                StepEvent::Halted => return Ok(Yield::Done),
+                StepEvent::Continued => (),
+            }
+        }
+    }
+
+    /// Runs until the program halts or needs input, sending outputs on as
+    /// [`Cpu::run`] does. Unlike [`Cpu::run`] it doesn't return when an output sink
+    /// yields or an idle NIC reads -1.
+    pub fn run_until_input_needed(&mut self) -> Result<(), IntcodeError> {
+        if let State::Halted = self.state {
+            return Ok(());
+        }
+        let result = self.run_until_input_loop();
+        self.flush_log();
+        result
+    }
+
+    fn run_until_input_loop(&mut self) -> Result<(), IntcodeError> {
+        loop {
+            let ip = self.ip;
+            match self.step()? {
+                StepEvent::Output(value) => self.send_output(ip, value)?,
+                StepEvent::NeedsInput | StepEvent::Halted => return Ok(()),
+                StepEvent::Continued => (),
+            }
+        }
+    }
+}
+
+/// A machine's state, from [`Cpu::snapshot`].
+#[derive(Clone)]
+pub struct CpuSnapshot<W = i64> {
+    ip: usize,
+    bp: W,
+    memory: Memory<W>,
+    io_in: VecDeque<W>,
+    io_out: VecDeque<W>,
+    mode: CpuMode,
+    state: State,
+    peak: Option<usize>,
+}
+
+/// Iterator returned by [`Cpu::outputs`]. It ends after the first error.
+pub struct Outputs<'a, W: Word = i64> {
+    cpu: &'a mut Cpu<W>,
+    done: bool,
+}
+
+impl<W: Word> Iterator for Outputs<'_, W> {
+    type Item = Result<W, IntcodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || matches!(self.cpu.state, State::Halted) {
+            return None;
+        }
+        let next = self.next_output();
+        self.cpu.flush_log();
+        next
+    }
+}
+
+impl<W: Word> Outputs<'_, W> {
+    fn next_output(&mut self) -> Option<Result<W, IntcodeError>> {
+        loop {
+            match self.cpu.step() {
+                Ok(StepEvent::Output(value)) => return Some(Ok(value)),
+                Ok(StepEvent::Continued) => (),
+                Ok(StepEvent::NeedsInput | StepEvent::Halted) => {
+                    self.done = true;
+                    return None;
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl<W: Word> Default for Cpu<W> {
+    fn default() -> Self {
+        Self::with_memory(Memory::default())
+    }
+}
+
+fn log_to<W: Word>(
+    logger: &mut Option<Box<dyn Logger<W>>>,
+    ansi: &mut Option<Ansi>,
+    event: LogEvent<W>,
+) {
+    match logger {
+        Some(logger) => logger.log(event),
+        None => ansi.get_or_insert_with(Ansi::new).log(event),
+    }
+}
+
+// A cell used as an address or jump target, or `None` if it can't be one
+fn to_address<W: Word>(value: W) -> Option<usize> {
+    usize::try_from(value.to_i64()?).ok()
+}
+
+/// Reads one joystick key from the terminal: `a` is -1, `d` is 1, space is 2 and
+/// anything else is 0.
+pub fn read_input() -> i64 {
+    read_key(true)
+}
+
+// Reads a joystick key, first showing the `INPUT <` prompt if asked to
+fn read_key(prompt: bool) -> i64 {
+    if prompt {
+        print!("\x1b[1;32mINPUT  <\x1b[m ");
+        stdout().flush().unwrap();
+    }
+
+    let mut input = [0u8; 1];
+
+    #[cfg(feature = "tui")]
+    {
+        terminal::enable_raw_mode().expect("Failed to enter raw mode");
+        stdin().read_exact(&mut input).expect("Failed to read char");
+        terminal::disable_raw_mode().expect("Failed to exit raw mode");
+        if prompt {
+            println!();
+        }
+    }
+    #[cfg(not(feature = "tui"))]
+    {
+        let mut line = String::new();
+        stdin().read_line(&mut line).expect("Failed to read line");
+        input[0] = line.bytes().next().unwrap_or(b'\n');
+    }
+
+    let input = input[0] as char;
+    match input {
+        'a' => -1,
+        'd' => 1,
+        ' ' => 2,
+        _ => 0,
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+use crate::{Decoded, Op, Operand};
+
+// Spaces per level of nesting
+const INDENT: usize = 4;
+
+// A statement of the reconstructed program. Jumps that became structure keep their
+// address, in case something else jumps to them.
+enum Stmt {
+    // An instruction, or a jump that didn't fit a pattern
+    Line(usize),
+    If {
+        at: usize,
+        cond: String,
+        then: Vec<Stmt>,
+        // The jump over the else block, if there is one
+        else_at: Option<usize>,
+        otherwise: Vec<Stmt>,
+    },
+    DoWhile {
+        body: Vec<Stmt>,
+        at: usize,
+        cond: String,
+    },
+    Loop {
+        body: Vec<Stmt>,
+        at: usize,
+    },
+}
+
+struct Decompiler<'a> {
+    lines: &'a [Decoded],
+    // Line index by address
+    index: HashMap<usize, usize>,
+}
+
+/// Turns a disassembly into pseudocode, recovering `if`/`else` and loops from the
+/// jumps. A conditional jump forward over a block is an `if`, and one at the end of the
+/// block that skips the next is its `else`. A jump back to the start of a block closes
+/// a `do`/`while`, or a `loop` if it always jumps. Jumps that fit none of these, or
+/// whose targets are computed, stay as `goto`s.
+///
+/// Memory is shown as `m[addr]`, and the output is only as good as the disassembly:
+/// data mistaken for code comes out as nonsense.
+pub fn decompile(lines: &[Decoded]) -> String {
+    let decompiler = Decompiler {
+        lines,
+        index: lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| (line.addr(), i))
+            .collect(),
+    };
+    let stmts = decompiler.block(0, lines.len());
+
+    let mut labels = BTreeMap::new();
+    decompiler.find_gotos(&stmts, &mut labels);
+    for (i, label) in labels.values_mut().enumerate() {
+        *label = format!("L{i}");
+    }
+
+    let mut out = String::new();
+    decompiler.render(&stmts, 0, &labels, &mut out);
+    out
+}
+
+fn expr(operand: &Operand) -> String {
+    match operand {
+        Operand::Position(addr) => format!("m[{addr}]"),
+        Operand::Immediate(value) => value.to_string(),
+        Operand::Relative(offset) => format!("m[bp{offset:+}]"),
+        Operand::Invalid(value) => format!("?{value}"),
+    }
+}
+
+// The condition under which a jump jumps, or with `taken` false, falls through
+fn condition(line: &Decoded, taken: bool) -> String {
+    let Decoded::Instruction { op, operands, .. } = line else {
+        unreachable!("Only jumps have conditions");
+    };
+    let jumps_on_zero = *op == Op::Jz;
+    let cmp = if jumps_on_zero == taken { "==" } else { "!=" };
+    format!("{} {cmp} 0", expr(&operands[0]))
+}
+
+impl Decompiler<'_> {
+    // The line a jump goes to, if its target is fixed and starts a line
+    fn target(&self, i: usize) -> Option<usize> {
+        self.lines[i]
+            .jump_target()
+            .and_then(|target| self.index.get(&target).copied())
+    }
+
+    fn block(&self, mut i: usize, end: usize) -> Vec<Stmt> {
+        let mut stmts = Vec::new();
+        while i < end {
+            // The last jump in the region back to here closes a loop
+            if let Some(j) = (i..end).rev().find(|&j| {
+                self.target(j) == Some(i) && self.lines[j].fixed_condition() != Some(false)
+            }) {
+                let body = self.block(i, j);
+                let at = self.lines[j].addr();
+                stmts.push(match self.lines[j].fixed_condition() {
+                    Some(true) => Stmt::Loop { body, at },
+                    _ => Stmt::DoWhile {
+                        body,
+                        at,
+                        cond: condition(&self.lines[j], true),
+                    },
+                });
+                i = j + 1;
+                continue;
+            }
+
+            if let Some(k) = self.target(i)
+                && k > i
+                && k <= end
+                && self.lines[i].fixed_condition().is_none()
+            {
+                let at = self.lines[i].addr();
+                let cond = condition(&self.lines[i], false);
+                // A jump that always skips forward from the end of the block is an else
+                let skip = k - 1;
+                if skip > i
+                    && self.lines[skip].fixed_condition() == Some(true)
+                    && let Some(m) = self.target(skip)
+                    && m > k
+                    && m <= end
+                {
+                    stmts.push(Stmt::If {
+                        at,
+                        cond,
+                        then: self.block(i + 1, skip),
+                        else_at: Some(self.lines[skip].addr()),
+                        otherwise: self.block(k, m),
+                    });
+                    i = m;
+                } else {
+                    stmts.push(Stmt::If {
+                        at,
+                        cond,
+                        then: self.block(i + 1, k),
+                        else_at: None,
+                        otherwise: Vec::new(),
+                    });
+                    i = k;
+                }
+                continue;
+            }
+
+            stmts.push(Stmt::Line(i));
+            i += 1;
+        }
+        stmts
+    }
+
+    // Collects the targets of the jumps left as gotos, which need labels
+    fn find_gotos(&self, stmts: &[Stmt], labels: &mut BTreeMap<usize, String>) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Line(i) => {
+                    if let Some(target) = self.lines[*i].jump_target()
+                        && self.index.contains_key(&target)
+                        && self.lines[*i].fixed_condition() != Some(false)
+                    {
+                        labels.insert(target, String::new());
+                    }
+                }
+                Stmt::If {
+                    then, otherwise, ..
+                } => {
+                    self.find_gotos(then, labels);
+                    self.find_gotos(otherwise, labels);
+                }
+                Stmt::DoWhile { body, .. } | Stmt::Loop { body, .. } => {
+                    self.find_gotos(body, labels)
+                }
+            }
+        }
+    }
+
+    fn render(
+        &self,
+        stmts: &[Stmt],
+        depth: usize,
+        labels: &BTreeMap<usize, String>,
+        out: &mut String,
+    ) {
+        let pad = " ".repeat(depth * INDENT);
+        let label = |addr: usize, out: &mut String| {
+            if let Some(label) = labels.get(&addr) {
+                let _ = writeln!(out, "{label}:");
+            }
+        };
+        for stmt in stmts {
+            match stmt {
+                Stmt::Line(i) => {
+                    let line = &self.lines[*i];
+                    label(line.addr(), out);
+                    let _ = writeln!(out, "{pad}{}", self.statement(line, labels));
+                }
+                Stmt::If {
+                    at,
+                    cond,
+                    then,
+                    else_at,
+                    otherwise,
+                } => {
+                    label(*at, out);
+                    let _ = writeln!(out, "{pad}if {cond} {{");
+                    self.render(then, depth + 1, labels, out);
+                    if let Some(else_at) = else_at {
+                        label(*else_at, out);
+                        let _ = writeln!(out, "{pad}}} else {{");
+                        self.render(otherwise, depth + 1, labels, out);
+                    }
+                    let _ = writeln!(out, "{pad}}}");
+                }
+                Stmt::DoWhile { body, at, cond } => {
+                    let _ = writeln!(out, "{pad}do {{");
+                    self.render(body, depth + 1, labels, out);
+                    label(*at, out);
+                    let _ = writeln!(out, "{pad}}} while {cond}");
+                }
+                Stmt::Loop { body, at } => {
+                    let _ = writeln!(out, "{pad}loop {{");
+                    self.render(body, depth + 1, labels, out);
+                    label(*at, out);
+                    let _ = writeln!(out, "{pad}}}");
+                }
+            }
+        }
+    }
+
+    fn statement(&self, line: &Decoded, labels: &BTreeMap<usize, String>) -> String {
+        let Decoded::Instruction { op, operands, .. } = line else {
+            return line.to_string();
+        };
+        let args: Vec<String> = operands.iter().map(expr).collect();
+        match (op, args.as_slice()) {
+            (Op::Add, [a, b, c]) => format!("{c} = {a} + {b}"),
+            (Op::Mul, [a, b, c]) => format!("{c} = {a} * {b}"),
+            (Op::Lt, [a, b, c]) => format!("{c} = {a} < {b}"),
+            (Op::Cmp, [a, b, c]) => format!("{c} = {a} == {b}"),
+            (Op::In, [a]) => format!("{a} = input()"),
+            (Op::Out, [a]) => format!("output({a})"),
+            (Op::AdjBp, [a]) => format!("bp += {a}"),
+            (Op::Hlt, []) => "halt".to_string(),
+            (Op::Jnz | Op::Jz, [_, target]) => {
+                let goto = match line.jump_target().and_then(|addr| labels.get(&addr)) {
+                    Some(label) => format!("goto {label}"),
+                    None => match operands[1] {
+                        Operand::Immediate(addr) => format!("goto {addr}"),
+                        _ => format!("goto *{target}"),
+                    },
+                };
+                match line.fixed_condition() {
+                    Some(true) => goto,
+                    Some(false) => "nop".to_string(),
+                    None => format!("if {} {goto}", condition(line, true)),
+                }
+            }
+            (Op::Custom(opcode), args) => format!("op{opcode}({})", args.join(", ")),
+            // Cut short by the end of memory
+            _ => line.to_string(),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::mem;
+
+use crate::{Cpu, CpuMode, IntcodeError, Op};
+
+/// A test of a diagnostic program whose output wasn't zero.
+pub struct TestFailure {
+    /// Which test failed, counting from 0
+    pub index: usize,
+    /// What the test output in place of zero
+    pub value: i64,
+    /// The opcodes the test executed besides its output, in the order first seen
+    pub ops: Vec<Op>,
+}
+
+/// What a run of a diagnostic program (days 5 and 9) produced.
+pub struct DiagnosticReport {
+    /// Every output, the test results followed by the diagnostic code
+    pub outputs: Vec<i64>,
+    /// The tests that output something other than zero
+    pub failures: Vec<TestFailure>,
+}
+
+impl DiagnosticReport {
+    /// The diagnostic code, which is the last output, if there was any output.
+    pub fn code(&self) -> Option<i64> {
+        self.outputs.last().copied()
+    }
+
+    /// Whether every test output zero.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Prints a summary of the tests, each failure and the diagnostic code.
+    pub fn print(&self) {
+        let n_tests = self.outputs.len().saturating_sub(1);
+        if self.passed() {
+            println!("\x1b[32;1m{n_tests} tests passed\x1b[m");
+        } else {
+            println!(
+                "\x1b[31;1m{} of {n_tests} tests failed\x1b[m",
+                self.failures.len()
+            );
+        }
+        for failure in &self.failures {
+            println!(
+                "\x1b[31mtest {:3}\x1b[m : output {:6}   ops under test: {:?}",
+                failure.index, failure.value, failure.ops
+            );
+        }
+        match self.code() {
+            Some(code) => println!("diagnostic code: {code}"),
+            None => println!("\x1b[31;1mNo diagnostic code produced!\x1b[m"),
+        }
+    }
+}
+
+// Each test in the diagnostic programs ends with an output that should be zero, so
+// the opcodes executed since the previous output are the ones that test exercised.
+fn ops_under_test(log: &[Op]) -> Vec<Op> {
+    let mut ops: Vec<Op> = Vec::new();
+    for op in log {
+        if *op != Op::Out && !ops.contains(op) {
+            ops.push(*op);
+        }
+    }
+    ops
+}
+
+/// Runs a diagnostic `program` on `cpu` for `system_id`, noting the opcodes each test
+/// exercised. The machine's mode is put back afterwards, even on an error.
+pub fn run_diagnostic(
+    cpu: &mut Cpu,
+    program: &[i64],
+    system_id: i64,
+) -> Result<DiagnosticReport, IntcodeError> {
+    let mut outputs: Vec<i64> = Vec::new();
+    let mut logs: Vec<Vec<Op>> = Vec::new();
+
+    cpu.load_program(program)?;
+    let mode = mem::replace(&mut cpu.mode, CpuMode::Normal);
+    cpu.op_log = Some(Vec::new());
+    cpu.io_in.push_front(system_id);
+
+    // The mode is put back before any error is returned
+    let result = loop {
+        match cpu.run_until_output() {
+            Ok(Some(output)) => {
+                outputs.push(output);
+                logs.push(cpu.op_log.replace(Vec::new()).unwrap_or_default());
+            }
+            Ok(None) => break Ok(()),
+            Err(err) => break Err(err),
+        }
+    };
+    cpu.op_log = None;
+    cpu.mode = mode;
+    result?;
+
+    let mut failures: Vec<TestFailure> = Vec::new();
+    let n_tests = outputs.len().saturating_sub(1);
+    for (index, value) in outputs.iter().take(n_tests).enumerate() {
+        if *value != 0 {
+            failures.push(TestFailure {
+                index,
+                value: *value,
+                ops: ops_under_test(&logs[index]),
+            });
+        }
+    }
+
+    Ok(DiagnosticReport { outputs, failures })
+}
@@ -0,0 +1,137 @@
+use std::fmt::{self, Display};
+
+use crate::{Op, Symbols, Word};
+
+/// An operand of a disassembled instruction, as its parameter mode says to read it.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Operand<W = i64> {
+    /// Mode 0, shown as `@addr`
+    Position(W),
+    /// Mode 1, shown as `#value`
+    Immediate(W),
+    /// Mode 2, shown as `bp+offset`
+    Relative(W),
+    /// Any other mode digit, which would fail to execute
+    Invalid(W),
+}
+
+impl<W> Operand<W> {
+    pub(crate) fn new(mode: i64, value: W) -> Self {
+        match mode {
+            0 => Operand::Position(value),
+            1 => Operand::Immediate(value),
+            2 => Operand::Relative(value),
+            _ => Operand::Invalid(value),
+        }
+    }
+}
+
+impl<W: Word> Display for Operand<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Position(addr) => write!(f, "@{addr}"),
+            Operand::Immediate(value) => write!(f, "#{value}"),
+            Operand::Relative(offset) => write!(f, "bp{offset:+}"),
+            Operand::Invalid(value) => write!(f, "?{value}"),
+        }
+    }
+}
+
+/// A line of [`Cpu::disassemble`](crate::Cpu::disassemble) output.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Decoded<W = i64> {
+    /// An instruction. It has fewer operands than its opcode takes if memory ends
+    /// first.
+    Instruction {
+        addr: usize,
+        op: Op,
+        operands: Vec<Operand<W>>,
+    },
+    /// A cell that isn't a valid instruction
+    Data { addr: usize, value: W },
+}
+
+impl<W> Decoded<W> {
+    pub fn addr(&self) -> usize {
+        match self {
+            Decoded::Instruction { addr, .. } | Decoded::Data { addr, .. } => *addr,
+        }
+    }
+
+    /// The number of cells it takes up.
+    pub fn size(&self) -> usize {
+        match self {
+            Decoded::Instruction { operands, .. } => operands.len() + 1,
+            Decoded::Data { .. } => 1,
+        }
+    }
+}
+
+impl Decoded {
+    /// Where a jump goes, if it is to a fixed address.
+    pub fn jump_target(&self) -> Option<usize> {
+        match self {
+            Decoded::Instruction {
+                op: Op::Jnz | Op::Jz,
+                operands,
+                ..
+            } => match operands.get(1) {
+                Some(Operand::Immediate(target)) => usize::try_from(*target).ok(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether a jump always or never jumps, if its condition is fixed.
+    pub fn fixed_condition(&self) -> Option<bool> {
+        match self {
+            Decoded::Instruction { op, operands, .. } => match (op, operands.first()) {
+                (Op::Jnz, Some(Operand::Immediate(value))) => Some(*value != 0),
+                (Op::Jz, Some(Operand::Immediate(value))) => Some(*value == 0),
+                _ => None,
+            },
+            Decoded::Data { .. } => None,
+        }
+    }
+
+    /// Like the `Display` form, with named addresses in place of numbers: position
+    /// operands as `@name` and fixed jump targets as `#name`.
+    pub fn display_with(&self, symbols: &Symbols) -> String {
+        let Decoded::Instruction { op, operands, .. } = self else {
+            return self.to_string();
+        };
+        let target = self.jump_target().and_then(|target| symbols.name(target));
+        let mut text = format!("{op:?}");
+        for (i, operand) in operands.iter().enumerate() {
+            let name = match operand {
+                Operand::Position(addr) => usize::try_from(*addr)
+                    .ok()
+                    .and_then(|addr| symbols.name(addr))
+                    .map(|name| format!("@{name}")),
+                Operand::Immediate(_) if i == 1 => target.map(|name| format!("#{name}")),
+                _ => None,
+            };
+            match name {
+                Some(name) => text.push_str(&format!(" {name}")),
+                None => text.push_str(&format!(" {operand}")),
+            }
+        }
+        text
+    }
+}
+
+impl<W: Word> Display for Decoded<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Decoded::Instruction { op, operands, .. } => {
+                write!(f, "{op:?}")?;
+                for operand in operands {
+                    write!(f, " {operand}")?;
+                }
+                Ok(())
+            }
+            Decoded::Data { value, .. } => write!(f, "data {value}"),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{Cpu, IntcodeError, StepEvent, Word};
+
+/// An instruction as one of the runs compared by [`diverge`] executed it.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct TraceStep<W = i64> {
+    pub ip: usize,
+    pub bp: W,
+    /// The instruction word at `ip`
+    pub instruction: W,
+    /// What executing it did
+    pub event: StepEvent<W>,
+}
+
+/// Where two runs compared by [`diverge`] part ways.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Divergence<W = i64> {
+    /// Instructions both runs executed alike before parting
+    pub steps: u64,
+    pub a: TraceStep<W>,
+    pub b: TraceStep<W>,
+    /// Cells that differ once both have executed their step, with the value in `a`
+    /// and the value in `b`
+    pub memory: Vec<(usize, W, W)>,
+}
+
+/// Steps `a` and `b` in lockstep until they execute different instructions: at a
+/// different ip, with a different relative base or instruction word, or doing
+/// something different, like outputting another value or waiting for input the
+/// other has. Returns `None` if they keep in step until both halt or wait for input,
+/// or for `limit` instructions.
+///
+/// Values read are not compared, so runs fed different inputs part ways where the
+/// program first acts on the difference rather than where it reads it.
+pub fn diverge<W: Word>(
+    a: &mut Cpu<W>,
+    b: &mut Cpu<W>,
+    limit: u64,
+) -> Result<Option<Divergence<W>>, IntcodeError> {
+    for steps in 0..limit {
+        let a_step = trace_step(a)?;
+        let b_step = trace_step(b)?;
+        if a_step != b_step {
+            return Ok(Some(Divergence {
+                steps,
+                a: a_step,
+                b: b_step,
+                memory: a.core_dump(None).diff(&b.core_dump(None)),
+            }));
+        }
+        if matches!(a_step.event, StepEvent::Halted | StepEvent::NeedsInput) {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+fn trace_step<W: Word>(cpu: &mut Cpu<W>) -> Result<TraceStep<W>, IntcodeError> {
+    let ip = cpu.ip;
+    let bp = cpu.bp;
+    let instruction = cpu.peek(ip)?;
+    let event = cpu.step()?;
+    Ok(TraceStep {
+        ip,
+        bp,
+        instruction,
+        event,
+    })
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+
+/// The machine around an instruction that failed to decode.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DecodeContext {
+    /// Relative base
+    pub bp: i64,
+    /// Address of the first cell in `window`
+    pub window_start: usize,
+    /// Memory on either side of the instruction, up to the ends of memory
+    pub window: Vec<i64>,
+    /// Parameter modes read before decoding stopped, in operand order. For an invalid
+    /// mode the last one is the bad digit.
+    pub modes: Vec<u8>,
+}
+
+impl fmt::Display for DecodeContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "bp {}, modes {:?}, memory from {}:",
+            self.bp, self.modes, self.window_start
+        )?;
+        for value in &self.window {
+            write!(f, " {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a program could not carry on running.
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[non_exhaustive]
+pub enum IntcodeError {
+    /// The instruction at `ip` runs past the end of memory
+    TruncatedInstruction { ip: usize },
+    /// `value` at `ip` doesn't decode to an opcode
+    InvalidOpcode {
+        ip: usize,
+        value: i64,
+        context: Box<DecodeContext>,
+    },
+    /// The instruction `value` at `ip` has a parameter mode other than 0, 1 or 2
+    InvalidMode {
+        ip: usize,
+        value: i64,
+        context: Box<DecodeContext>,
+    },
+    /// The instruction at `ip` reads, writes or jumps to `addr`, outside memory
+    OutOfBounds { ip: usize, addr: i64 },
+    /// Output was expected, but the program stopped at `ip` without producing any
+    MissingOutput { ip: usize },
+    /// A program of `len` cells doesn't fit in `memory` cells
+    ProgramTooLarge { len: usize, memory: usize },
+    /// The output sink rejected `value`, output by the instruction at `ip`
+    RejectedOutput { ip: usize, value: i64 },
+    /// The program was still running at `ip` after `limit` instructions
+    InstructionLimit { ip: usize, limit: u64 },
+    /// The add or multiply at `ip` overflowed, on a strict machine
+    Overflow { ip: usize },
+    /// The input instruction at `ip` found nothing to read `waits` times in a row, on
+    /// a machine with [`Cpu::deadlock_after`](crate::Cpu::deadlock_after) set
+    Deadlocked { ip: usize, waits: u32 },
+    /// Output number `index` of a replay, from the instruction at `ip`, differs from
+    /// the log. `None` is an output the program or the log doesn't have.
+    ReplayDiverged {
+        ip: usize,
+        index: usize,
+        expected: Option<i64>,
+        actual: Option<i64>,
+    },
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::TruncatedInstruction { ip } => {
+                write!(f, "instruction at {ip} runs past the end of memory")
+            }
+            IntcodeError::InvalidOpcode { ip, value, context } => {
+                write!(f, "invalid opcode {value} at {ip} ({context})")
+            }
+            IntcodeError::InvalidMode { ip, value, context } => {
+                write!(f, "invalid parameter mode in {value} at {ip} ({context})")
+            }
+            IntcodeError::OutOfBounds { ip, addr } => {
+                write!(
+                    f,
+                    "instruction at {ip} accesses address {addr}, outside memory"
+                )
+            }
+            IntcodeError::MissingOutput { ip } => {
+                write!(f, "program stopped at {ip} without producing output")
+            }
+            IntcodeError::ProgramTooLarge { len, memory } => {
+                write!(
+                    f,
+                    "program of {len} cells doesn't fit in {memory} cells of memory"
+                )
+            }
+            IntcodeError::RejectedOutput { ip, value } => {
+                write!(f, "unexpected output {value} from the instruction at {ip}")
+            }
+            IntcodeError::InstructionLimit { ip, limit } => {
+                write!(f, "still running at {ip} after {limit} instructions")
+            }
+            IntcodeError::Overflow { ip } => {
+                write!(f, "arithmetic overflow in the instruction at {ip}")
+            }
+            IntcodeError::Deadlocked { ip, waits } => {
+                write!(
+                    f,
+                    "deadlocked: input at {ip} still empty after {waits} waits"
+                )
+            }
+            IntcodeError::ReplayDiverged {
+                ip,
+                index,
+                expected,
+                actual,
+            } => {
+                let show = |value: &Option<i64>| match value {
+                    Some(value) => value.to_string(),
+                    None => String::from("nothing"),
+                };
+                write!(
+                    f,
+                    "replay diverged at output {index} (ip {ip}): expected {}, got {}",
+                    show(expected),
+                    show(actual)
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntcodeError {}
+
+impl From<IntcodeError> for utils::error::AocError {
+    fn from(error: IntcodeError) -> Self {
+        utils::error::AocError::Intcode(Box::new(error))
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+// Shades from least to most accessed
+const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+
+#[derive(Clone, Copy)]
+pub(crate) enum Access {
+    Fetch,
+    Read,
+    Write,
+}
+
+/// What a cell of memory was used for during a run.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Region {
+    /// Never accessed
+    Unused,
+    /// Executed as an instruction or operand
+    Code,
+    /// Read but never written
+    Data,
+    /// Written, and maybe read back
+    Scratch,
+}
+
+impl Region {
+    #[cfg(feature = "png")]
+    fn colour(self) -> [u8; 3] {
+        match self {
+            Region::Unused => [0, 0, 0],
+            Region::Code => [60, 110, 230],
+            Region::Data => [60, 200, 90],
+            Region::Scratch => [230, 70, 50],
+        }
+    }
+
+    fn ansi(self) -> &'static str {
+        match self {
+            Region::Unused => "\x1b[90m",
+            Region::Code => "\x1b[34m",
+            Region::Data => "\x1b[32m",
+            Region::Scratch => "\x1b[31m",
+        }
+    }
+}
+
+/// Memory accesses counted per address while [`Cpu::heatmap`](crate::Cpu::heatmap) is
+/// `Some`.
+#[derive(Default)]
+pub struct Heatmap {
+    pub fetches: HashMap<usize, u64>,
+    pub reads: HashMap<usize, u64>,
+    pub writes: HashMap<usize, u64>,
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record(&mut self, addr: usize, access: Access) {
+        let counts = match access {
+            Access::Fetch => &mut self.fetches,
+            Access::Read => &mut self.reads,
+            Access::Write => &mut self.writes,
+        };
+        *counts.entry(addr).or_insert(0) += 1;
+    }
+
+    pub fn region(&self, addr: usize) -> Region {
+        if self.fetches.contains_key(&addr) {
+            Region::Code
+        } else if self.writes.contains_key(&addr) {
+            Region::Scratch
+        } else if self.reads.contains_key(&addr) {
+            Region::Data
+        } else {
+            Region::Unused
+        }
+    }
+
+    /// Accesses of every kind to `addr`.
+    pub fn heat(&self, addr: usize) -> u64 {
+        [&self.fetches, &self.reads, &self.writes]
+            .iter()
+            .map(|counts| counts.get(&addr).copied().unwrap_or(0))
+            .sum()
+    }
+
+    /// One past the highest address accessed.
+    pub fn extent(&self) -> usize {
+        [&self.fetches, &self.reads, &self.writes]
+            .iter()
+            .filter_map(|counts| counts.keys().max())
+            .max()
+            .map_or(0, |addr| addr + 1)
+    }
+
+    // 0 for an unused cell, then 1 to 4 on a log scale up to the hottest cell
+    fn level(&self, addr: usize, max: u64) -> usize {
+        let heat = self.heat(addr);
+        if heat == 0 {
+            return 0;
+        }
+        let scale = ((heat + 1) as f64).ln() / ((max + 1) as f64).ln();
+        ((scale * SHADES.len() as f64).ceil() as usize).clamp(1, SHADES.len())
+    }
+
+    fn max_heat(&self) -> u64 {
+        (0..self.extent())
+            .map(|addr| self.heat(addr))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Draws memory `width` cells to a row, coloured by region and shaded by how often
+    /// each cell was accessed.
+    pub fn print(&self, width: usize) {
+        let extent = self.extent();
+        let max = self.max_heat();
+        println!(
+            "\x1b[34;1m### MEMORY ({extent} cells) ###\x1b[m  {}code\x1b[m {}data\x1b[m {}scratch\x1b[m",
+            Region::Code.ansi(),
+            Region::Data.ansi(),
+            Region::Scratch.ansi()
+        );
+        for start in (0..extent).step_by(width.max(1)) {
+            print!("\x1b[33m{start:6}\x1b[m ");
+            for addr in start..(start + width).min(extent) {
+                let region = self.region(addr);
+                match self.level(addr, max) {
+                    0 => print!("{}·", region.ansi()),
+                    level => print!("{}{}", region.ansi(), SHADES[level - 1]),
+                }
+            }
+            println!("\x1b[m");
+        }
+    }
+
+    /// Writes the same picture as [`Heatmap::print`] as a PNG, `scale` pixels to a
+    /// cell.
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, path: &str, width: usize, scale: usize) -> std::io::Result<()> {
+        use std::{fs::File, io, io::BufWriter};
+
+        let extent = self.extent();
+        let max = self.max_heat();
+        let width = width.max(1);
+        let rows = extent.div_ceil(width).max(1);
+        let (px_width, px_height) = (width * scale, rows * scale);
+
+        let mut pixels: Vec<u8> = Vec::with_capacity(px_width * px_height * 3);
+        for row in 0..rows {
+            let colours: Vec<[u8; 3]> = (row * width..(row + 1) * width)
+                .map(|addr| {
+                    let level = self.level(addr, max) as u32;
+                    self.region(addr)
+                        .colour()
+                        .map(|c| (c as u32 * level / SHADES.len() as u32) as u8)
+                })
+                .collect();
+            for _ in 0..scale {
+                for colour in &colours {
+                    for _ in 0..scale {
+                        pixels.extend_from_slice(colour);
+                    }
+                }
+            }
+        }
+
+        let file = File::create(path)?;
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(file), px_width as u32, px_height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&pixels).map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{Op, WatchedWrite};
+
+/// The instruction a [`Cpu`](crate::Cpu) is about to execute, as given to
+/// [`Cpu::on_before_instruction`](crate::Cpu::on_before_instruction) hooks.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Instruction<'a, W = i64> {
+    pub ip: usize,
+    pub bp: W,
+    pub op: Op,
+    /// The opcode and parameter modes, as stored
+    pub instruction: W,
+    /// The cells after the instruction, before their modes are applied
+    pub operands: &'a [W],
+}
+
+/// A value read or output by the program, as given to
+/// [`Cpu::on_io`](crate::Cpu::on_io) hooks.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Io<W = i64> {
+    Input(W),
+    Output(W),
+}
+
+type BeforeHook<W> = Box<dyn FnMut(&Instruction<W>)>;
+type WriteHook<W> = Box<dyn FnMut(&WatchedWrite<W>)>;
+type IoHook<W> = Box<dyn FnMut(Io<W>)>;
+
+// Everything registered to observe a machine
+pub(crate) struct Hooks<W> {
+    pub(crate) before: Vec<BeforeHook<W>>,
+    pub(crate) write: Vec<WriteHook<W>>,
+    pub(crate) io: Vec<IoHook<W>>,
+}
+
+impl<W> Default for Hooks<W> {
+    fn default() -> Self {
+        Self {
+            before: Vec::new(),
+            write: Vec::new(),
+            io: Vec::new(),
+        }
+    }
+}
+
+impl<W: Copy> Hooks<W> {
+    pub(crate) fn io(&mut self, event: Io<W>) {
+        for hook in &mut self.io {
+            hook(event);
+        }
+    }
+}
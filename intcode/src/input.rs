@@ -0,0 +1,59 @@
+use std::collections::VecDeque;
+
+use crate::{Word, read_input};
+
+/// What an [`InputSource`] gave an input instruction.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum InputResult<W = i64> {
+    /// Read this value and carry on
+    Value(W),
+    /// Read this value, then return from [`Cpu::run`](crate::Cpu::run) after the
+    /// instruction
+    Yield(W),
+    /// Nothing to read yet: the machine pauses with
+    /// [`StepEvent::NeedsInput`](crate::StepEvent::NeedsInput) and asks again when
+    /// resumed
+    Pending,
+}
+
+/// Where a [`Cpu`](crate::Cpu) gets its input from, in place of `io_in`.
+///
+/// Set one with [`CpuBuilder::input_source`](crate::CpuBuilder::input_source) or the
+/// `input_source` field. Closures returning an [`InputResult`] are sources too.
+pub trait InputSource<W = i64> {
+    fn next_input(&mut self) -> InputResult<W>;
+}
+
+/// Pops from the back, like `io_in`.
+impl<W> InputSource<W> for VecDeque<W> {
+    fn next_input(&mut self) -> InputResult<W> {
+        match self.pop_back() {
+            Some(value) => InputResult::Value(value),
+            None => InputResult::Pending,
+        }
+    }
+}
+
+impl<W, F: FnMut() -> InputResult<W>> InputSource<W> for F {
+    fn next_input(&mut self) -> InputResult<W> {
+        self()
+    }
+}
+
+/// Reads the same value forever.
+pub struct Constant<W = i64>(pub W);
+
+impl<W: Copy> InputSource<W> for Constant<W> {
+    fn next_input(&mut self) -> InputResult<W> {
+        InputResult::Value(self.0)
+    }
+}
+
+/// Asks for a joystick key on the terminal, as [`read_input`] does.
+pub struct Stdin;
+
+impl<W: Word> InputSource<W> for Stdin {
+    fn next_input(&mut self) -> InputResult<W> {
+        InputResult::Value(W::from(read_input()))
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{Op, Word};
+
+/// Writes one JSON object per line for every instruction retired while
+/// [`Cpu::json_trace`](crate::Cpu::json_trace) is `Some`, like
+///
+/// ```text
+/// {"ip":0,"op":"Add","instruction":1001,"operands":[9,1,9],"modes":[0,1,0],"writes":[{"addr":9,"value":6}]}
+/// ```
+///
+/// `operands` are the cells after the instruction as stored, before their modes are
+/// applied. An input instruction waiting for input isn't retired, so it only appears
+/// once it reads. Write errors can't stop the machine, so the first one is kept and
+/// returned by [`JsonTrace::finish`].
+pub struct JsonTrace {
+    out: Box<dyn Write>,
+    // The record of the instruction being executed
+    line: String,
+    error: Option<io::Error>,
+}
+
+impl JsonTrace {
+    pub fn new(out: impl Write + 'static) -> Self {
+        Self {
+            out: Box::new(out),
+            line: String::new(),
+            error: None,
+        }
+    }
+
+    /// Appends to the file at `path`, creating it if needed.
+    pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    pub(crate) fn begin<W: Word>(
+        &mut self,
+        ip: usize,
+        op: Op,
+        instruction: W,
+        operands: &[W],
+        modes: &[u8],
+    ) {
+        self.line.clear();
+        let _ = write!(
+            self.line,
+            r#"{{"ip":{ip},"op":"{op:?}","instruction":{instruction},"operands":["#
+        );
+        join(&mut self.line, operands);
+        self.line.push_str(r#"],"modes":["#);
+        join(&mut self.line, modes);
+        self.line.push_str(r#"],"writes":["#);
+    }
+
+    pub(crate) fn write<W: Word>(&mut self, addr: usize, value: W) {
+        if !self.line.ends_with('[') {
+            self.line.push(',');
+        }
+        let _ = write!(self.line, r#"{{"addr":{addr},"value":{value}}}"#);
+    }
+
+    // Writes out the record of the instruction that just retired
+    pub(crate) fn end(&mut self) {
+        self.line.push_str("]}\n");
+        if self.error.is_none()
+            && let Err(err) = self.out.write_all(self.line.as_bytes())
+        {
+            self.error = Some(err);
+        }
+        self.line.clear();
+    }
+
+    // Forgets the record of an instruction that didn't retire
+    pub(crate) fn discard(&mut self) {
+        self.line.clear();
+    }
+
+    /// Flushes the output, or returns the first error writing to it.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.out.flush(),
+        }
+    }
+}
+
+fn join(line: &mut String, values: &[impl std::fmt::Display]) {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            line.push(',');
+        }
+        let _ = write!(line, "{value}");
+    }
+}
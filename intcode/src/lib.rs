@@ -0,0 +1,91 @@
+//! Intcode interpreter shared by the Advent of Code 2019 solutions.
+//!
+//! Most users only need [`prelude`]: load a program into a [`Cpu`], push inputs onto
+//! `io_in`, call [`Cpu::run`] and read results from `io_out`. Instruction decoding is
+//! internal and may change between versions.
+//!
+//! # Stability
+//!
+//! The stable surface is the prelude, [`Cpu`]'s methods and the `io_in`, `io_out`,
+//! `ip`, `bp` and `state` fields. The other public fields of [`Cpu`] (`mode`,
+//! `verbosity`, `logger`, `profile`, `heatmap`, the breakpoint sets and the rest) are
+//! exposed for the tools in this workspace and are unstable: set them through
+//! [`CpuBuilder`] where it has an option for them, as they may become private.
+
+mod ascii;
+mod blocks;
+mod builder;
+mod calls;
+mod cfg;
+mod channel;
+mod core_dump;
+mod coverage;
+mod cpu;
+mod decompile;
+mod diagnostic;
+mod disasm;
+mod diverge;
+mod error;
+mod heatmap;
+mod hooks;
+mod input;
+mod json_trace;
+mod logger;
+mod memory;
+mod minimize;
+mod op;
+mod oracle;
+mod output;
+mod parallel;
+mod profile;
+mod program;
+mod replay;
+mod self_test;
+mod stats;
+mod symbols;
+mod tick;
+mod watch;
+mod word;
+
+pub mod prelude;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "async")]
+pub mod tasks;
+
+pub use ascii::AsciiComputer;
+pub use blocks::{BasicBlock, basic_blocks};
+pub use builder::{AsciiBuilder, CpuBuilder};
+pub use calls::{Function, find_functions};
+pub use cfg::cfg_dot;
+pub use channel::{ChannelInput, ChannelOutput};
+pub use core_dump::CoreDump;
+pub use coverage::Coverage;
+pub use cpu::{
+    Cpu, CpuMode, CpuSnapshot, Outputs, RunResult, SliceResult, State, StepEvent, Yield, read_input,
+};
+pub use decompile::decompile;
+pub use diagnostic::{DiagnosticReport, TestFailure, run_diagnostic};
+pub use disasm::{Decoded, Operand};
+pub use diverge::{Divergence, TraceStep, diverge};
+pub use error::{DecodeContext, IntcodeError};
+pub use heatmap::{Heatmap, Region};
+pub use hooks::{Instruction, Io};
+pub use input::{Constant, InputResult, InputSource, Stdin};
+pub use json_trace::JsonTrace;
+pub use logger::{Ansi, LogEvent, Logger, SymbolicAnsi, Verbosity};
+pub use memory::Memory;
+pub use minimize::minimize;
+pub use op::{CustomOp, Op};
+pub use oracle::{GridOracle, IntcodeOracle, Memo, MockOracle};
+pub use output::{Discard, Lines, OutputResult, OutputSink};
+pub use parallel::{par_map, run_parallel};
+pub use profile::Profile;
+pub use program::{dump_program, print_prog};
+pub use replay::IoLog;
+pub use self_test::{SelfTestReport, SelfTestResult, self_test};
+pub use stats::Stats;
+pub use symbols::Symbols;
+pub use tick::Ticker;
+pub use watch::{Watch, WatchedRead, WatchedWrite};
+pub use word::Word;
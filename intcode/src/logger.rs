@@ -0,0 +1,202 @@
+use std::{
+    fmt,
+    io::{self, BufWriter, Stdout, Write},
+};
+
+use crate::{Op, Symbols, WatchedRead, WatchedWrite, Word};
+
+/// How much a [`Cpu`](crate::Cpu) tells its [`Logger`] about a run.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Default, Clone, Copy)]
+pub enum Verbosity {
+    /// Nothing
+    Silent,
+    /// Each input, output and halt, and each wait for input
+    #[default]
+    Io,
+    /// Every instruction as well, before it executes
+    Trace,
+}
+
+/// Something a [`Cpu`](crate::Cpu) reports while running.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum LogEvent<'a, W = i64> {
+    /// About to execute `op` at `ip`, with the raw operands as they are in memory
+    Instruction {
+        ip: usize,
+        bp: W,
+        op: Op,
+        instruction: W,
+        operands: &'a [W],
+    },
+    /// Reached an input instruction with nothing to read
+    WaitingForInput,
+    Input(W),
+    Output(W),
+    Halted,
+    /// The program wrote to a watched address. Logged whatever the verbosity.
+    Watched(WatchedWrite<W>),
+    /// The program read an address watched with [`Watch::Access`](crate::Watch::Access).
+    /// Logged whatever the verbosity.
+    WatchedRead(WatchedRead<W>),
+    /// The program wrote over code it had already run, with detection of
+    /// self-modifying code on
+    SelfModified(WatchedWrite<W>),
+}
+
+/// Where a [`Cpu`](crate::Cpu) sends its diagnostics, in place of the coloured lines
+/// [`Ansi`] prints.
+///
+/// Set one with [`CpuBuilder::logger`](crate::CpuBuilder::logger) or the `logger`
+/// field. Closures taking a [`LogEvent`] are loggers too. Apart from watched reads and
+/// writes, only events the machine's [`Verbosity`] allows are sent, so a silent machine pays
+/// nothing for logging.
+pub trait Logger<W = i64> {
+    fn log(&mut self, event: LogEvent<W>);
+
+    /// Writes out anything held back. Called whenever a run stops and control goes
+    /// back to the caller.
+    fn flush(&mut self) {}
+}
+
+impl<W, F: FnMut(LogEvent<W>)> Logger<W> for F {
+    fn log(&mut self, event: LogEvent<W>) {
+        self(event)
+    }
+}
+
+/// Prints each event to stdout as a coloured line. Used when no logger is set.
+///
+/// Lines are buffered, so a trace doesn't cost a write to stdout per instruction. They
+/// go out when the program halts or waits for input, when a run returns, and when the
+/// logger is dropped.
+pub struct Ansi<O: Write = Stdout> {
+    out: BufWriter<O>,
+}
+
+impl Ansi {
+    pub fn new() -> Self {
+        Self::with_writer(io::stdout())
+    }
+}
+
+impl<O: Write> Ansi<O> {
+    /// Prints to `out` instead of stdout, buffered the same way.
+    pub fn with_writer(out: O) -> Self {
+        Self {
+            out: BufWriter::new(out),
+        }
+    }
+}
+
+impl Default for Ansi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Word, O: Write> Logger<W> for Ansi<O> {
+    fn log(&mut self, event: LogEvent<W>) {
+        print_event(&mut self.out, event, None);
+    }
+
+    fn flush(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+/// Prints like [`Ansi`], naming the addresses that have names in `symbols`.
+pub struct SymbolicAnsi {
+    symbols: Symbols,
+    out: BufWriter<Stdout>,
+}
+
+impl SymbolicAnsi {
+    pub fn new(symbols: Symbols) -> Self {
+        Self {
+            symbols,
+            out: BufWriter::new(io::stdout()),
+        }
+    }
+}
+
+impl<W: Word> Logger<W> for SymbolicAnsi {
+    fn log(&mut self, event: LogEvent<W>) {
+        print_event(&mut self.out, event, Some(&self.symbols));
+    }
+
+    fn flush(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+// Formats the whole line first, so it goes into the buffer in one piece. Anything
+// that makes the program stop for a while sends the buffer on, so the lines before
+// it are on screen while the machine is idle.
+fn print_event<W: Word>(out: &mut impl Write, event: LogEvent<W>, symbols: Option<&Symbols>) {
+    let mut line = String::new();
+    // Writing to a String can't fail
+    let _ = write_event(&mut line, event, symbols);
+    let _ = out.write_all(line.as_bytes());
+    if matches!(event, LogEvent::Halted | LogEvent::WaitingForInput) {
+        let _ = out.flush();
+    }
+}
+
+fn write_event<W: Word>(
+    f: &mut impl fmt::Write,
+    event: LogEvent<W>,
+    symbols: Option<&Symbols>,
+) -> fmt::Result {
+    let describe = |addr: usize| match symbols {
+        Some(symbols) => symbols.describe(addr),
+        None => addr.to_string(),
+    };
+    match event {
+        LogEvent::Instruction {
+            ip,
+            bp,
+            op,
+            instruction,
+            operands,
+        } => {
+            write!(f, "\x1b[33m{bp:4}\x1b[m : \x1b[34m{ip:4}\x1b[m   ")?;
+            write!(f, "\x1b[31m{op:?}\x1b[m\t[{instruction}]")?;
+            for operand in operands {
+                write!(f, "[{operand}]")?;
+            }
+            match symbols.and_then(|symbols| symbols.name(ip)) {
+                Some(name) => writeln!(f, "  \x1b[34m<{name}>\x1b[m"),
+                None => writeln!(f),
+            }
+        }
+        LogEvent::WaitingForInput => writeln!(f, "\x1b[35;1mWaiting for IO in...\x1b[m"),
+        LogEvent::Input(value) => writeln!(f, "\x1b[1;32mINPUT  <\x1b[m {value}"),
+        LogEvent::Output(value) => writeln!(f, "\x1b[1;34mOUTPUT >\x1b[m {value}"),
+        LogEvent::Halted => writeln!(f, "\x1b[31;1mHalting...\x1b[m"),
+        LogEvent::Watched(WatchedWrite { ip, addr, old, new }) => {
+            writeln!(
+                f,
+                "\x1b[1;33mWRITE  !\x1b[m [{}] {old} -> {new} (ip {})",
+                describe(addr),
+                describe(ip)
+            )
+        }
+        LogEvent::WatchedRead(WatchedRead { ip, addr, value }) => {
+            writeln!(
+                f,
+                "\x1b[1;33mREAD   !\x1b[m [{}] {value} (ip {})",
+                describe(addr),
+                describe(ip)
+            )
+        }
+        LogEvent::SelfModified(WatchedWrite { ip, addr, old, new }) => {
+            writeln!(
+                f,
+                "\x1b[1;35mPATCH  !\x1b[m [{}] {old} -> {new} (ip {})",
+                describe(addr),
+                describe(ip)
+            )
+        }
+    }
+}
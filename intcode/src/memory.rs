@@ -0,0 +1,135 @@
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut, Range},
+};
+
+use crate::Word;
+
+// Cells below this address live in a Vec that grows on write. Higher addresses go in a
+// map, so one stray far write doesn't allocate everything in between.
+const DENSE_LIMIT: usize = 1 << 20;
+
+/// Memory of a [`Cpu`](crate::Cpu). It starts out holding just the program and grows
+/// as the program writes; cells that were never written read as 0.
+#[derive(Clone, Default)]
+pub struct Memory<W = i64> {
+    dense: Vec<W>,
+    sparse: HashMap<usize, W>,
+    limit: Option<usize>,
+    // Dense cells that may have changed since the program was loaded
+    dirty: Option<Range<usize>>,
+    // What unwritten cells read as, for indexing
+    zero: W,
+}
+
+impl Memory {
+    /// Memory with no upper bound.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Memory of `limit` cells. Addresses at or past it are out of bounds.
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Self::default()
+        }
+    }
+}
+
+impl<W: Word> Memory<W> {
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    /// Number of cells backed by storage, which is at least the program length.
+    pub fn allocated(&self) -> usize {
+        self.dense.len() + self.sparse.len()
+    }
+
+    /// The value at `addr`, or `None` if it is past the limit.
+    pub fn get(&self, addr: usize) -> Option<W> {
+        if self.limit.is_some_and(|limit| addr >= limit) {
+            return None;
+        }
+        let value = match self.dense.get(addr) {
+            Some(value) => *value,
+            None => self.sparse.get(&addr).copied().unwrap_or(self.zero),
+        };
+        Some(value)
+    }
+
+    /// The cell at `addr`, allocating it if needed, or `None` if it is past the limit.
+    pub fn get_mut(&mut self, addr: usize) -> Option<&mut W> {
+        if self.limit.is_some_and(|limit| addr >= limit) {
+            return None;
+        }
+        if addr < DENSE_LIMIT {
+            self.dirty = Some(match self.dirty.take() {
+                Some(dirty) => dirty.start.min(addr)..dirty.end.max(addr + 1),
+                None => addr..addr + 1,
+            });
+        }
+        if addr < self.dense.len() {
+            return Some(&mut self.dense[addr]);
+        }
+        if addr < DENSE_LIMIT {
+            self.dense.resize(addr + 1, self.zero);
+            return Some(&mut self.dense[addr]);
+        }
+        Some(self.sparse.entry(addr).or_insert(self.zero))
+    }
+
+    // Every cell backed by storage and its address, in no particular order
+    pub(crate) fn cells(&self) -> impl Iterator<Item = (usize, W)> + '_ {
+        self.dense
+            .iter()
+            .copied()
+            .enumerate()
+            .chain(self.sparse.iter().map(|(addr, value)| (*addr, *value)))
+    }
+
+    // Replaces everything with `program`, keeping the limit
+    pub(crate) fn load(&mut self, program: &[W]) {
+        self.dense.clear();
+        self.dense.extend_from_slice(program);
+        self.sparse.clear();
+        self.dirty = None;
+    }
+
+    // Puts back `program`, which must be the one last loaded, copying only the cells
+    // written since
+    pub(crate) fn reload(&mut self, program: &[W]) {
+        self.dense.truncate(program.len());
+        if let Some(dirty) = self.dirty.take() {
+            let dirty = dirty.start.min(program.len())..dirty.end.min(program.len());
+            self.dense[dirty.clone()].copy_from_slice(&program[dirty]);
+        }
+        self.sparse.clear();
+    }
+}
+
+impl<W: Word> Index<usize> for Memory<W> {
+    type Output = W;
+
+    fn index(&self, addr: usize) -> &W {
+        if self.limit.is_some_and(|limit| addr >= limit) {
+            panic!("Address {addr} is outside memory");
+        }
+        self.dense
+            .get(addr)
+            .or_else(|| self.sparse.get(&addr))
+            .unwrap_or(&self.zero)
+    }
+}
+
+impl<W: Word> IndexMut<usize> for Memory<W> {
+    fn index_mut(&mut self, addr: usize) -> &mut W {
+        self.get_mut(addr)
+            .unwrap_or_else(|| panic!("Address {addr} is outside memory"))
+    }
+}
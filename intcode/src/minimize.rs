@@ -0,0 +1,50 @@
+/// Shrinks `program` while `still_fails` keeps returning true, delta-debugging style:
+/// first by deleting runs of cells, halving the run length whenever nothing can be
+/// removed, then by zeroing the cells that are left.
+///
+/// `still_fails` must hold for `program` itself. It is called on many candidates that
+/// are not valid programs, so it should guard against panics and infinite loops.
+pub fn minimize<F>(program: &[i64], mut still_fails: F) -> Vec<i64>
+where
+    F: FnMut(&[i64]) -> bool,
+{
+    let mut prog = program.to_vec();
+    let mut n_chunks = 2;
+
+    while prog.len() > 1 {
+        let chunk = prog.len().div_ceil(n_chunks);
+        let mut reduced = false;
+
+        for start in (0..prog.len()).step_by(chunk) {
+            let end = (start + chunk).min(prog.len());
+            let mut candidate = prog[..start].to_vec();
+            candidate.extend_from_slice(&prog[end..]);
+            if still_fails(&candidate) {
+                prog = candidate;
+                n_chunks = (n_chunks - 1).max(2);
+                reduced = true;
+                break;
+            }
+        }
+
+        if !reduced {
+            if chunk == 1 {
+                break;
+            }
+            n_chunks = (n_chunks * 2).min(prog.len());
+        }
+    }
+
+    for i in 0..prog.len() {
+        if prog[i] == 0 {
+            continue;
+        }
+        let old = prog[i];
+        prog[i] = 0;
+        if !still_fails(&prog) {
+            prog[i] = old;
+        }
+    }
+
+    prog
+}
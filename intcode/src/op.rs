@@ -0,0 +1,129 @@
+/// Decoded opcode, as recorded in [`Cpu::op_log`](crate::Cpu::op_log).
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Op {
+    Add,
+    Mul,
+    In,
+    Out,
+    Jnz,
+    Jz,
+    Lt,
+    Cmp,
+    AdjBp,
+    Hlt,
+    /// An opcode added with [`Cpu::register_op`](crate::Cpu::register_op)
+    Custom(u8),
+}
+
+// Gets the operands of a custom instruction and returns what it writes
+type Handler<W> = Box<dyn FnMut(&[W]) -> W>;
+
+/// An instruction a program can use beyond the built in ones, for
+/// [`Cpu::register_op`](crate::Cpu::register_op). Its operands are read through their
+/// modes like any other instruction's before the handler sees them.
+pub struct CustomOp<W = i64> {
+    pub(crate) n_operands: usize,
+    pub(crate) writes: bool,
+    pub(crate) handler: Handler<W>,
+}
+
+impl<W: Default + 'static> CustomOp<W> {
+    /// An instruction that only reads its `n_operands` operands, like an output.
+    pub fn reading(n_operands: usize, mut handler: impl FnMut(&[W]) + 'static) -> Self {
+        Self {
+            n_operands,
+            writes: false,
+            handler: Box::new(move |operands| {
+                handler(operands);
+                W::default()
+            }),
+        }
+    }
+
+    /// An instruction whose last operand says where to write what the handler returns,
+    /// like an add. The handler gets the other operands.
+    pub fn writing(n_operands: usize, handler: impl FnMut(&[W]) -> W + 'static) -> Self {
+        assert!(
+            n_operands > 0,
+            "a writing instruction needs an operand to write to"
+        );
+        Self {
+            n_operands,
+            writes: true,
+            handler: Box::new(handler),
+        }
+    }
+}
+
+// The parameter mode digits
+#[derive(Copy, Clone)]
+pub(crate) enum RegMode {
+    Pos,
+    Imm,
+    Rel,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Cmd {
+    pub(crate) op: Op,
+    pub(crate) n_operands: usize,
+    pub(crate) writes: bool,
+}
+
+pub(crate) fn get_cmd(instruction: i64) -> Option<Cmd> {
+    let opcode = instruction % 100;
+    match opcode {
+        1 => Some(Cmd {
+            op: Op::Add,
+            n_operands: 3,
+            writes: true,
+        }),
+        2 => Some(Cmd {
+            op: Op::Mul,
+            n_operands: 3,
+            writes: true,
+        }),
+        3 => Some(Cmd {
+            op: Op::In,
+            n_operands: 1,
+            writes: true,
+        }),
+        4 => Some(Cmd {
+            op: Op::Out,
+            n_operands: 1,
+            writes: false,
+        }),
+        5 => Some(Cmd {
+            op: Op::Jnz,
+            n_operands: 2,
+            writes: false,
+        }),
+        6 => Some(Cmd {
+            op: Op::Jz,
+            n_operands: 2,
+            writes: false,
+        }),
+        7 => Some(Cmd {
+            op: Op::Lt,
+            n_operands: 3,
+            writes: true,
+        }),
+        8 => Some(Cmd {
+            op: Op::Cmp,
+            n_operands: 3,
+            writes: true,
+        }),
+        9 => Some(Cmd {
+            op: Op::AdjBp,
+            n_operands: 1,
+            writes: false,
+        }),
+        99 => Some(Cmd {
+            op: Op::Hlt,
+            n_operands: 0,
+            writes: false,
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,151 @@
+use std::ops::Range;
+
+use grid::{Point2, SparseGrid};
+
+use crate::{Cpu, IntcodeError, Verbosity};
+
+/// Something that can be asked what is at a cell of a grid, like the day 19 drone or
+/// the day 15 repair droid.
+pub trait GridOracle {
+    type Cell;
+
+    fn probe(&mut self, pos: Point2) -> Result<Self::Cell, IntcodeError>;
+
+    /// Probes each point in order, stopping at the first error.
+    fn probe_all(&mut self, points: &[Point2]) -> Result<Vec<Self::Cell>, IntcodeError> {
+        points.iter().map(|pos| self.probe(*pos)).collect()
+    }
+
+    /// The `x` range covered in row `y` by a beam of non-zero cells, for a beam with no
+    /// gaps along a row. Scans `xs` from the left and stops at the beam's right edge,
+    /// so only one cell past the beam is probed. `None` if the beam misses `xs`.
+    fn scan_row(&mut self, y: i64, xs: Range<i64>) -> Result<Option<Range<i64>>, IntcodeError>
+    where
+        Self: GridOracle<Cell = i64>,
+    {
+        let mut start = None;
+        for x in xs.clone() {
+            let inside = self.probe(Point2::new(x, y))? != 0;
+            match (inside, start) {
+                (true, None) => start = Some(x),
+                (false, Some(start)) => return Ok(Some(start..x)),
+                _ => (),
+            }
+        }
+        Ok(start.map(|start| start..xs.end))
+    }
+}
+
+/// Runs a fresh copy of a program for every probe, giving it `x` then `y` as input and
+/// taking its single output as the answer.
+pub struct IntcodeOracle {
+    pub cpu: Cpu,
+    /// Number of times the program has been run
+    pub runs: u64,
+}
+
+impl IntcodeOracle {
+    pub fn new(program: &[i64]) -> Self {
+        let mut cpu = Cpu::new();
+        cpu.verbosity = Verbosity::Silent;
+        cpu.load_program(program)
+            .expect("Memory without a limit fits any program");
+        Self { cpu, runs: 0 }
+    }
+}
+
+impl GridOracle for IntcodeOracle {
+    type Cell = i64;
+
+    fn probe(&mut self, pos: Point2) -> Result<i64, IntcodeError> {
+        self.runs += 1;
+        self.cpu.reset();
+        self.cpu.io_in.push_front(pos.x);
+        self.cpu.io_in.push_front(pos.y);
+        self.cpu
+            .run_until_output()?
+            .ok_or(IntcodeError::MissingOutput { ip: self.cpu.ip })
+    }
+}
+
+/// Answers probes with a closure, for testing code that would otherwise need a real
+/// program.
+pub struct MockOracle<F> {
+    answer: F,
+    /// Number of probes answered
+    pub probes: u64,
+}
+
+impl<T, F: FnMut(Point2) -> T> MockOracle<F> {
+    pub fn new(answer: F) -> Self {
+        Self { answer, probes: 0 }
+    }
+}
+
+impl<T, F: FnMut(Point2) -> T> GridOracle for MockOracle<F> {
+    type Cell = T;
+
+    fn probe(&mut self, pos: Point2) -> Result<T, IntcodeError> {
+        self.probes += 1;
+        Ok((self.answer)(pos))
+    }
+}
+
+/// Caches the answers of another oracle, so each cell is only probed once.
+pub struct Memo<O: GridOracle> {
+    pub inner: O,
+    cache: SparseGrid<O::Cell>,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl<O: GridOracle> Memo<O>
+where
+    O::Cell: Clone,
+{
+    pub fn new(inner: O) -> Self {
+        Self {
+            inner,
+            cache: SparseGrid::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn is_cached(&self, pos: Point2) -> bool {
+        self.cache.contains(pos)
+    }
+
+    /// Records a cell that is already known, so it is never probed.
+    pub fn insert(&mut self, pos: Point2, cell: O::Cell) {
+        self.cache.insert(pos, cell);
+    }
+
+    /// Every cell answered so far.
+    pub fn cache(&self) -> &SparseGrid<O::Cell> {
+        &self.cache
+    }
+
+    pub fn into_cache(self) -> SparseGrid<O::Cell> {
+        self.cache
+    }
+}
+
+impl<O: GridOracle> GridOracle for Memo<O>
+where
+    O::Cell: Clone,
+{
+    type Cell = O::Cell;
+
+    // Errors aren't cached, so a failed probe is retried next time
+    fn probe(&mut self, pos: Point2) -> Result<O::Cell, IntcodeError> {
+        if let Some(cell) = self.cache.get(pos) {
+            self.hits += 1;
+            return Ok(cell.clone());
+        }
+        self.misses += 1;
+        let cell = self.inner.probe(pos)?;
+        self.cache.insert(pos, cell.clone());
+        Ok(cell)
+    }
+}
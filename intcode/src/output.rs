@@ -0,0 +1,85 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+/// What an [`OutputSink`] did with an output.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum OutputResult {
+    /// Took the value; carry on running
+    Continue,
+    /// Took the value; return from [`Cpu::run`](crate::Cpu::run)
+    Yield,
+    /// The value makes no sense here: stop with
+    /// [`IntcodeError::RejectedOutput`](crate::IntcodeError::RejectedOutput)
+    Reject,
+}
+
+/// Where [`Cpu::run`](crate::Cpu::run) sends outputs, in place of `io_out`.
+///
+/// Set one with [`CpuBuilder::output_sink`](crate::CpuBuilder::output_sink) or the
+/// `output_sink` field. Closures taking the value and returning an [`OutputResult`]
+/// are sinks too. To look at a sink's state while the machine owns it, share it as an
+/// `Rc<RefCell<_>>`.
+pub trait OutputSink<W = i64> {
+    fn write_output(&mut self, value: W) -> OutputResult;
+}
+
+/// Pushes to the front, like `io_out`.
+impl<W> OutputSink<W> for VecDeque<W> {
+    fn write_output(&mut self, value: W) -> OutputResult {
+        self.push_front(value);
+        OutputResult::Continue
+    }
+}
+
+impl<W, F: FnMut(W) -> OutputResult> OutputSink<W> for F {
+    fn write_output(&mut self, value: W) -> OutputResult {
+        self(value)
+    }
+}
+
+impl<W, S: OutputSink<W>> OutputSink<W> for Rc<RefCell<S>> {
+    fn write_output(&mut self, value: W) -> OutputResult {
+        self.borrow_mut().write_output(value)
+    }
+}
+
+/// Throws every output away.
+pub struct Discard;
+
+impl<W> OutputSink<W> for Discard {
+    fn write_output(&mut self, _value: W) -> OutputResult {
+        OutputResult::Continue
+    }
+}
+
+/// Collects ASCII output into lines and hands each one, without its newline, to a
+/// callback. Values outside the ASCII range are rejected.
+pub struct Lines<F> {
+    on_line: F,
+    line: String,
+}
+
+impl<F: FnMut(&str) -> OutputResult> Lines<F> {
+    pub fn new(on_line: F) -> Self {
+        Self {
+            on_line,
+            line: String::new(),
+        }
+    }
+}
+
+impl<F: FnMut(&str) -> OutputResult> OutputSink for Lines<F> {
+    fn write_output(&mut self, value: i64) -> OutputResult {
+        match value {
+            10 => {
+                let result = (self.on_line)(&self.line);
+                self.line.clear();
+                result
+            }
+            0..128 => {
+                self.line.push(value as u8 as char);
+                OutputResult::Continue
+            }
+            _ => OutputResult::Reject,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::{panic, thread};
+
+use crate::{Cpu, IntcodeError, RunResult, Verbosity};
+
+/// Calls `f` on every item, spread across a thread per core, and returns the results
+/// in the order of `items`, or the first error.
+///
+/// A [`Cpu`] can't be sent between threads, since its input source, output sink and
+/// logger may share state through `Rc`. Build a machine inside `f` instead, as
+/// [`run_parallel`] does.
+pub fn par_map<T, R, E, F>(items: &[T], f: F) -> Result<Vec<R>, E>
+where
+    T: Sync,
+    R: Send,
+    E: Send,
+    F: Fn(&T) -> Result<R, E> + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = items.len().div_ceil(threads).max(1);
+    let f = &f;
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = items
+            .chunks(chunk)
+            .map(|items| scope.spawn(move || items.iter().map(f).collect::<Result<Vec<R>, E>>()))
+            .collect();
+
+        let mut results = Vec::with_capacity(items.len());
+        for worker in workers {
+            let chunk = worker
+                .join()
+                .unwrap_or_else(|err| panic::resume_unwind(err))?;
+            results.extend(chunk);
+        }
+        Ok(results)
+    })
+}
+
+/// Runs `program` on a fresh, silent machine for each list of inputs, in parallel, and
+/// returns the outputs of each run. A run that needs more input than it was given
+/// stops there with what it output so far.
+pub fn run_parallel(program: &[i64], inputs: &[Vec<i64>]) -> Result<Vec<Vec<i64>>, IntcodeError> {
+    par_map(inputs, |inputs| {
+        let mut cpu = Cpu::builder()
+            .program(program)
+            .verbosity(Verbosity::Silent)
+            .input(inputs)
+            .build()?;
+        while let RunResult::OutputPending
+        | RunResult::InputYielded
+        | RunResult::Breakpoint(_)
+        | RunResult::Watchpoint(_) = cpu.run()?
+        {}
+        Ok(cpu.drain_values())
+    })
+}
@@ -0,0 +1,6 @@
+//! The types needed to load and drive a program: `use intcode::prelude::*;`
+
+pub use crate::{
+    AsciiComputer, Cpu, CpuBuilder, CpuMode, IntcodeError, RunResult, State, StepEvent, Verbosity,
+    Yield,
+};
@@ -0,0 +1,117 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{BasicBlock, Decoded, Memory, Op, Word, basic_blocks};
+
+const BAR_WIDTH: usize = 40;
+
+/// Execution counts gathered while [`Cpu::profile`](crate::Cpu::profile) is `Some`.
+#[derive(Default)]
+pub struct Profile {
+    pub total: u64,
+    pub op_counts: HashMap<Op, u64>,
+    pub addr_counts: HashMap<usize, u64>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record(&mut self, ip: usize, op: Op) {
+        self.total += 1;
+        *self.op_counts.entry(op).or_insert(0) += 1;
+        *self.addr_counts.entry(ip).or_insert(0) += 1;
+    }
+
+    /// The `width` consecutive addresses that executed the most instructions, as
+    /// (start address, instructions executed in the window).
+    pub fn hottest_window(&self, width: usize) -> Option<(usize, u64)> {
+        let mut addrs: Vec<(usize, u64)> = self.addr_counts.iter().map(|(a, c)| (*a, *c)).collect();
+        addrs.sort();
+
+        let mut best: Option<(usize, u64)> = None;
+        let mut sum = 0;
+        let mut tail = 0;
+        for &(addr, count) in &addrs {
+            sum += count;
+            while addrs[tail].0 + width <= addr {
+                sum -= addrs[tail].1;
+                tail += 1;
+            }
+            if best.is_none_or(|(_, b)| sum > b) {
+                best = Some((addrs[tail].0, sum));
+            }
+        }
+        best
+    }
+
+    /// The blocks that ran with the instructions executed in each, hottest first.
+    pub fn hot_blocks<'a>(&self, blocks: &'a [BasicBlock]) -> Vec<(&'a BasicBlock, u64)> {
+        let mut hot: Vec<(&BasicBlock, u64)> = blocks
+            .iter()
+            .map(|block| {
+                let count = (block.start..block.end)
+                    .filter_map(|addr| self.addr_counts.get(&addr))
+                    .sum();
+                (block, count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        hot.sort_by_key(|(block, count)| (Reverse(*count), block.start));
+        hot
+    }
+
+    /// Prints the `count` basic blocks of a disassembly that executed the most
+    /// instructions, each with its share of the total and its code.
+    pub fn print_hot_blocks(&self, lines: &[Decoded], count: usize) {
+        let blocks = basic_blocks(lines);
+        println!(
+            "\x1b[34;1m### HOTTEST BLOCKS ({} executed) ###\x1b[m",
+            self.total
+        );
+        for (block, executed) in self.hot_blocks(&blocks).into_iter().take(count) {
+            let share = executed as f64 * 100.0 / self.total.max(1) as f64;
+            println!(
+                "\x1b[32;1m{share:5.1}%\x1b[m {executed:>10}  \x1b[33m{}..{}\x1b[m",
+                block.start, block.end
+            );
+            for line in &lines[block.lines.clone()] {
+                let runs = self.addr_counts.get(&line.addr()).copied().unwrap_or(0);
+                println!("\x1b[34m{:6}\x1b[m {runs:>10}x  {line}", line.addr());
+            }
+        }
+    }
+
+    /// Draws the opcode histogram and the hottest address window. Call it between runs
+    /// after clearing the screen for a live view.
+    pub fn print<W: Word>(&self, memory: &Memory<W>, width: usize) {
+        println!("\x1b[34;1m### OPCODES ({} executed) ###\x1b[m", self.total);
+
+        let mut ops: Vec<(&Op, &u64)> = self.op_counts.iter().collect();
+        ops.sort_by_key(|(op, count)| (Reverse(**count), **op));
+        let max = ops.first().map_or(1, |(_, c)| **c).max(1);
+        for (op, count) in ops {
+            let bar = (*count as usize * BAR_WIDTH).div_ceil(max as usize);
+            println!(
+                "\x1b[31m{:>6}\x1b[m {:>10} \x1b[32m{}\x1b[m",
+                format!("{op:?}"),
+                count,
+                "█".repeat(bar)
+            );
+        }
+
+        let Some((start, total)) = self.hottest_window(width) else {
+            return;
+        };
+        println!("\x1b[34;1m### HOTTEST {width} ADDRESSES ({total} executed) ###\x1b[m");
+        for addr in start..start + width {
+            let count = self.addr_counts.get(&addr).copied().unwrap_or(0);
+            let value = memory.get(addr).unwrap_or_default();
+            if count > 0 {
+                println!("\x1b[33m{addr:6}\x1b[m [{value:6}] {count:>10}");
+            } else {
+                println!("\x1b[90m{addr:6} [{value:6}]\x1b[m");
+            }
+        }
+    }
+}
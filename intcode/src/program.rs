@@ -0,0 +1,15 @@
+pub fn dump_program(program: &[i64]) {
+    for (i, num) in program.iter().enumerate() {
+        println!("{i} : {num}");
+    }
+}
+
+pub fn print_prog(program: &[i64], ip: usize) {
+    for (i, num) in program.iter().enumerate() {
+        if i == ip {
+            print!("\x1b[31m");
+        }
+        print!("[{num}]\x1b[m");
+    }
+    println!();
+}
@@ -0,0 +1,63 @@
+use std::{fmt, str::FromStr};
+
+/// Every value a program read and output, in order, recorded while
+/// [`Cpu::io_log`](crate::Cpu::io_log) is `Some`. Feeding it back with
+/// [`Cpu::replay`](crate::Cpu::replay) reproduces the run exactly.
+///
+/// As text it is two lines, so a log can be saved and edited by hand:
+///
+/// ```text
+/// in 1,2,3
+/// out 4,5
+/// ```
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct IoLog<W = i64> {
+    pub inputs: Vec<W>,
+    pub outputs: Vec<W>,
+}
+
+impl<W> IoLog<W> {
+    pub fn new() -> Self {
+        Self {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+}
+
+impl<W: fmt::Display> fmt::Display for IoLog<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (label, values) in [("in", &self.inputs), ("out", &self.outputs)] {
+            let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            writeln!(f, "{label} {}", values.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: FromStr> FromStr for IoLog<W> {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut log = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (label, values) = line.split_once(' ').unwrap_or((line, ""));
+            let list = match label {
+                "in" => &mut log.inputs,
+                "out" => &mut log.outputs,
+                _ => return Err(format!("line {}: expected `in` or `out`", i + 1)),
+            };
+            for value in values.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("line {}: bad value {value:?}", i + 1))?;
+                list.push(value);
+            }
+        }
+        Ok(log)
+    }
+}
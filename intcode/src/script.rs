@@ -0,0 +1,169 @@
+//! Driving machines from [Rhai](https://rhai.rs) scripts, for quick automation without
+//! recompiling.
+//!
+//! A script gets the loaded program as `program` and can start any number of machines
+//! from it:
+//!
+//! ```text
+//! let m = machine(program);
+//! m.run();
+//! print(m.read_text());
+//! m.write_line("north");
+//! m.run();
+//! while m.has_output { print(m.output()); }
+//! ```
+//!
+//! `m.run(n)` gives up with an error after `n` instructions instead of running
+//! forever.
+//!
+//! Machines are [`AsciiComputer`]s and scripts only see their public API. Interpreter
+//! errors stop the script with the [`IntcodeError`](crate::IntcodeError) message.
+
+use std::{cell::RefCell, rc::Rc};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Position, Scope};
+
+use crate::{AsciiComputer, Cpu, State, Verbosity};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// A machine as seen by scripts. Clones share the same machine.
+#[derive(Clone)]
+pub struct Machine(Rc<RefCell<AsciiComputer>>);
+
+impl Machine {
+    fn new(program: Array) -> ScriptResult<Self> {
+        let program = to_program(program)?;
+        let ascii = Cpu::builder()
+            .program(&program)
+            .verbosity(Verbosity::Silent)
+            .ascii_mode()
+            .build()
+            .map_err(script_error)?;
+        Ok(Self(Rc::new(RefCell::new(ascii))))
+    }
+
+    fn run(&mut self) -> ScriptResult<()> {
+        self.0.borrow_mut().run().map(|_| ()).map_err(script_error)
+    }
+
+    fn run_with_limit(&mut self, max_instructions: i64) -> ScriptResult<()> {
+        let max_instructions = u64::try_from(max_instructions)
+            .map_err(|_| script_error(format!("{max_instructions} is not an instruction count")))?;
+        self.0
+            .borrow_mut()
+            .run_with_limit(max_instructions)
+            .map(|_| ())
+            .map_err(script_error)
+    }
+
+    fn input(&mut self, value: i64) {
+        self.0.borrow_mut().cpu.io_in.push_front(value);
+    }
+
+    fn write(&mut self, text: &str) {
+        self.0.borrow_mut().write_str(text);
+    }
+
+    fn write_line(&mut self, line: &str) {
+        self.0.borrow_mut().write_line(line);
+    }
+
+    fn output(&mut self) -> ScriptResult<i64> {
+        self.0.borrow_mut().cpu.read_output().map_err(script_error)
+    }
+
+    fn read_text(&mut self) -> String {
+        self.0.borrow_mut().read_output_string()
+    }
+
+    fn values(&mut self) -> Array {
+        let values = self.0.borrow_mut().take_values();
+        values.into_iter().map(Dynamic::from).collect()
+    }
+
+    fn peek(&mut self, addr: i64) -> ScriptResult<i64> {
+        let ascii = self.0.borrow();
+        let addr = to_addr(addr)?;
+        ascii.cpu.peek(addr).map_err(script_error)
+    }
+
+    fn poke(&mut self, addr: i64, value: i64) -> ScriptResult<()> {
+        let addr = to_addr(addr)?;
+        self.0
+            .borrow_mut()
+            .cpu
+            .poke(addr, value)
+            .map_err(script_error)
+    }
+
+    fn has_output(&mut self) -> bool {
+        !self.0.borrow().cpu.io_out.is_empty()
+    }
+
+    fn halted(&mut self) -> bool {
+        matches!(self.0.borrow().cpu.state, State::Halted)
+    }
+
+    fn prompt(&mut self) -> Dynamic {
+        match self.0.borrow().prompt() {
+            Some(prompt) => prompt.into(),
+            None => Dynamic::UNIT,
+        }
+    }
+
+    fn ip(&mut self) -> i64 {
+        self.0.borrow().cpu.ip as i64
+    }
+}
+
+fn script_error(err: impl ToString) -> Box<EvalAltResult> {
+    EvalAltResult::ErrorRuntime(err.to_string().into(), Position::NONE).into()
+}
+
+fn to_addr(addr: i64) -> ScriptResult<usize> {
+    usize::try_from(addr).map_err(|_| format!("address {addr} is outside memory").into())
+}
+
+fn to_program(program: Array) -> ScriptResult<Vec<i64>> {
+    program
+        .into_iter()
+        .map(|value| {
+            value
+                .as_int()
+                .map_err(|kind| format!("program contains a {kind}, not an integer").into())
+        })
+        .collect()
+}
+
+/// An engine with `machine(program)` and the [`Machine`] methods registered.
+pub fn script_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .register_type_with_name::<Machine>("Machine")
+        .register_fn("machine", Machine::new)
+        .register_fn("run", Machine::run)
+        .register_fn("run", Machine::run_with_limit)
+        .register_fn("input", Machine::input)
+        .register_fn("write", Machine::write)
+        .register_fn("write_line", Machine::write_line)
+        .register_fn("output", Machine::output)
+        .register_fn("read_text", Machine::read_text)
+        .register_fn("values", Machine::values)
+        .register_fn("peek", Machine::peek)
+        .register_fn("poke", Machine::poke)
+        .register_get("has_output", Machine::has_output)
+        .register_get("halted", Machine::halted)
+        .register_get("prompt", Machine::prompt)
+        .register_get("ip", Machine::ip);
+    engine
+}
+
+/// Runs `source` with `program` in scope, returning the value of its last statement.
+pub fn run_script(source: &str, program: &[i64]) -> ScriptResult<Dynamic> {
+    let engine = script_engine();
+    let mut scope = Scope::new();
+    let program: Array = program.iter().map(|num| Dynamic::from(*num)).collect();
+    scope.push("program", program);
+    engine.eval_with_scope(&mut scope, source)
+}
@@ -0,0 +1,217 @@
+use crate::{Cpu, RunResult, Verbosity};
+
+// Enough for every example; anything still running by then is stuck
+const MAX_INSTRUCTIONS: u64 = 100_000;
+
+const QUINE: [i64; 16] = [
+    109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+];
+
+// Outputs 999 below 8, 1000 for 8 and 1001 above
+const COMPARE_WITH_8: [i64; 47] = [
+    3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+    1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105,
+    1, 46, 98, 99,
+];
+
+// What a halted example should have left behind
+enum Expect {
+    Outputs(&'static [i64]),
+    // The start of memory
+    Memory(&'static [i64]),
+}
+
+struct Example {
+    name: &'static str,
+    program: &'static [i64],
+    input: &'static [i64],
+    expect: Expect,
+}
+
+// The example programs from the puzzle texts of days 2, 5 and 9
+const EXAMPLES: &[Example] = &[
+    Example {
+        name: "day 2: add and multiply",
+        program: &[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50],
+        input: &[],
+        expect: Expect::Memory(&[3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50]),
+    },
+    Example {
+        name: "day 2: overwrite a halt",
+        program: &[1, 1, 1, 4, 99, 5, 6, 0, 99],
+        input: &[],
+        expect: Expect::Memory(&[30, 1, 1, 4, 2, 5, 6, 0, 99]),
+    },
+    Example {
+        name: "day 5: echo",
+        program: &[3, 0, 4, 0, 99],
+        input: &[42],
+        expect: Expect::Outputs(&[42]),
+    },
+    Example {
+        name: "day 5: parameter modes",
+        program: &[1002, 4, 3, 4, 33],
+        input: &[],
+        expect: Expect::Memory(&[1002, 4, 3, 4, 99]),
+    },
+    Example {
+        name: "day 5: negative immediates",
+        program: &[1101, 100, -1, 4, 0],
+        input: &[],
+        expect: Expect::Memory(&[1101, 100, -1, 4, 99]),
+    },
+    Example {
+        name: "day 5: equals, position mode",
+        program: &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+        input: &[8],
+        expect: Expect::Outputs(&[1]),
+    },
+    Example {
+        name: "day 5: less than, position mode",
+        program: &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+        input: &[8],
+        expect: Expect::Outputs(&[0]),
+    },
+    Example {
+        name: "day 5: equals, immediate mode",
+        program: &[3, 3, 1108, -1, 8, 3, 4, 3, 99],
+        input: &[7],
+        expect: Expect::Outputs(&[0]),
+    },
+    Example {
+        name: "day 5: less than, immediate mode",
+        program: &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
+        input: &[7],
+        expect: Expect::Outputs(&[1]),
+    },
+    Example {
+        name: "day 5: jump, position mode",
+        program: &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9],
+        input: &[0],
+        expect: Expect::Outputs(&[0]),
+    },
+    Example {
+        name: "day 5: jump, immediate mode",
+        program: &[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1],
+        input: &[5],
+        expect: Expect::Outputs(&[1]),
+    },
+    Example {
+        name: "day 5: compare with 8, below",
+        program: &COMPARE_WITH_8,
+        input: &[7],
+        expect: Expect::Outputs(&[999]),
+    },
+    Example {
+        name: "day 5: compare with 8, equal",
+        program: &COMPARE_WITH_8,
+        input: &[8],
+        expect: Expect::Outputs(&[1000]),
+    },
+    Example {
+        name: "day 5: compare with 8, above",
+        program: &COMPARE_WITH_8,
+        input: &[9],
+        expect: Expect::Outputs(&[1001]),
+    },
+    Example {
+        name: "day 9: quine",
+        program: &QUINE,
+        input: &[],
+        expect: Expect::Outputs(&QUINE),
+    },
+    Example {
+        name: "day 9: 16 digit product",
+        program: &[1102, 34915192, 34915192, 7, 4, 7, 99, 0],
+        input: &[],
+        expect: Expect::Outputs(&[1219070632396864]),
+    },
+    Example {
+        name: "day 9: large number",
+        program: &[104, 1125899906842624, 99],
+        input: &[],
+        expect: Expect::Outputs(&[1125899906842624]),
+    },
+];
+
+/// How one example program did in [`self_test`].
+pub struct SelfTestResult {
+    pub name: &'static str,
+    /// What went wrong, if anything
+    pub failure: Option<String>,
+}
+
+pub struct SelfTestReport {
+    pub results: Vec<SelfTestResult>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| result.failure.is_none())
+    }
+
+    pub fn print(&self) {
+        for result in &self.results {
+            match &result.failure {
+                None => println!("\x1b[32m  ok\x1b[m  {}", result.name),
+                Some(failure) => println!("\x1b[31mFAIL\x1b[m  {}: {failure}", result.name),
+            }
+        }
+        let failed = self
+            .results
+            .iter()
+            .filter(|result| result.failure.is_some())
+            .count();
+        if failed == 0 {
+            println!("\x1b[32;1m{} examples passed\x1b[m", self.results.len());
+        } else {
+            println!(
+                "\x1b[31;1m{failed} of {} examples failed\x1b[m",
+                self.results.len()
+            );
+        }
+    }
+}
+
+/// Runs the example programs from the puzzles on fresh machines and checks what they
+/// output or leave in memory, to check the interpreter after changing it.
+pub fn self_test() -> SelfTestReport {
+    let results = EXAMPLES
+        .iter()
+        .map(|example| SelfTestResult {
+            name: example.name,
+            failure: check(example).err(),
+        })
+        .collect();
+    SelfTestReport { results }
+}
+
+fn check(example: &Example) -> Result<(), String> {
+    let mut cpu = Cpu::builder()
+        .program(example.program)
+        .verbosity(Verbosity::Silent)
+        .input(example.input)
+        .build()
+        .map_err(|err| err.to_string())?;
+    match cpu.run_with_limit(MAX_INSTRUCTIONS) {
+        Ok(RunResult::Halted) => (),
+        Ok(result) => return Err(format!("stopped with {result:?}")),
+        Err(err) => return Err(err.to_string()),
+    }
+
+    match example.expect {
+        Expect::Outputs(expected) => {
+            let outputs = cpu.drain_values();
+            if outputs != expected {
+                return Err(format!("output {outputs:?}, expected {expected:?}"));
+            }
+        }
+        Expect::Memory(expected) => {
+            let memory: Vec<i64> = (0..expected.len()).map(|addr| cpu.memory()[addr]).collect();
+            if memory != expected {
+                return Err(format!("memory {memory:?}, expected {expected:?}"));
+            }
+        }
+    }
+    Ok(())
+}
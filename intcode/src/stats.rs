@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use crate::Op;
+
+const OPS: [Op; 10] = [
+    Op::Add,
+    Op::Mul,
+    Op::In,
+    Op::Out,
+    Op::Jnz,
+    Op::Jz,
+    Op::Lt,
+    Op::Cmp,
+    Op::AdjBp,
+    Op::Hlt,
+];
+
+/// Counts of retired instructions, kept by a [`Cpu`](crate::Cpu) with stats turned on.
+///
+/// Unlike a [`Profile`](crate::Profile) nothing is kept per address, so counting
+/// costs next to nothing and can be left on for a whole run.
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct Stats {
+    /// Instructions retired
+    pub total: u64,
+    /// Times an input instruction found nothing to read
+    pub waits: u64,
+    op_counts: [u64; OPS.len()],
+    custom_counts: BTreeMap<u8, u64>,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn retire(&mut self, op: Op) {
+        self.total += 1;
+        match op {
+            Op::Custom(opcode) => *self.custom_counts.entry(opcode).or_insert(0) += 1,
+            _ => self.op_counts[index(op)] += 1,
+        }
+    }
+
+    /// Times `op` was retired.
+    pub fn count(&self, op: Op) -> u64 {
+        match op {
+            Op::Custom(opcode) => self.custom_counts.get(&opcode).copied().unwrap_or(0),
+            _ => self.op_counts[index(op)],
+        }
+    }
+
+    /// Each opcode that was retired, with its count, in opcode order. Custom opcodes
+    /// come last.
+    pub fn op_counts(&self) -> impl Iterator<Item = (Op, u64)> + '_ {
+        let custom = self
+            .custom_counts
+            .iter()
+            .map(|(opcode, count)| (Op::Custom(*opcode), *count));
+        OPS.iter()
+            .map(|op| (*op, self.count(*op)))
+            .filter(|(_, count)| *count > 0)
+            .chain(custom)
+    }
+
+    /// Values read by input instructions.
+    pub fn inputs(&self) -> u64 {
+        self.count(Op::In)
+    }
+
+    /// Values produced by output instructions.
+    pub fn outputs(&self) -> u64 {
+        self.count(Op::Out)
+    }
+
+    pub fn print(&self) {
+        println!(
+            "\x1b[34;1m### STATS ({} executed, {} in, {} out, {} waits) ###\x1b[m",
+            self.total,
+            self.inputs(),
+            self.outputs(),
+            self.waits
+        );
+        for (op, count) in self.op_counts() {
+            let share = count as f64 * 100.0 / self.total as f64;
+            println!(
+                "\x1b[31m{:>6}\x1b[m {count:>10} {share:>5.1}%",
+                format!("{op:?}")
+            );
+        }
+    }
+}
+
+// Where a built in op is counted
+fn index(op: Op) -> usize {
+    OPS.iter()
+        .position(|known| *known == op)
+        .expect("Every built in op is in OPS")
+}
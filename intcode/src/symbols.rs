@@ -0,0 +1,107 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// Names for addresses, so listings and traces read `@weight_threshold` rather than
+/// `@586`. Kept by hand in a file of `addr=name` lines, where `#` starts a comment:
+///
+/// ```text
+/// # day 25
+/// 100=main_loop
+/// 586=weight_threshold
+/// ```
+///
+/// Names are looked up both ways, and writing the symbols back out gives the same
+/// file without its comments.
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct Symbols {
+    names: BTreeMap<usize, String>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names `addr`, replacing any name it had. Names can't contain spaces or start
+    /// with a digit, so either a name or an address can be typed where an address is
+    /// wanted.
+    pub fn insert(&mut self, addr: usize, name: &str) -> Result<(), String> {
+        if name.is_empty()
+            || name.contains(char::is_whitespace)
+            || name.starts_with(|c: char| c.is_ascii_digit())
+        {
+            return Err(format!("bad name {name:?}"));
+        }
+        self.names.insert(addr, name.to_string());
+        Ok(())
+    }
+
+    pub fn remove(&mut self, addr: usize) -> Option<String> {
+        self.names.remove(&addr)
+    }
+
+    /// The name of `addr`, if it has one.
+    pub fn name(&self, addr: usize) -> Option<&str> {
+        self.names.get(&addr).map(String::as_str)
+    }
+
+    /// The address called `name`, if there is one.
+    pub fn addr(&self, name: &str) -> Option<usize> {
+        self.names
+            .iter()
+            .find_map(|(addr, other)| (other == name).then_some(*addr))
+    }
+
+    /// The name of `addr`, or the address itself.
+    pub fn describe(&self, addr: usize) -> String {
+        match self.name(addr) {
+            Some(name) => name.to_string(),
+            None => addr.to_string(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.names.iter().map(|(addr, name)| (*addr, name.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl fmt::Display for Symbols {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (addr, name) in &self.names {
+            writeln!(f, "{addr}={name}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Symbols {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut symbols = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = match line.find('#') {
+                Some(start) => &line[..start],
+                None => line,
+            }
+            .trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((addr, name)) = line.split_once('=') else {
+                return Err(format!("line {}: expected `addr=name`", i + 1));
+            };
+            let addr = addr
+                .trim()
+                .parse()
+                .map_err(|_| format!("line {}: bad address {:?}", i + 1, addr.trim()))?;
+            symbols
+                .insert(addr, name.trim())
+                .map_err(|err| format!("line {}: {err}", i + 1))?;
+        }
+        Ok(symbols)
+    }
+}
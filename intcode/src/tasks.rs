@@ -0,0 +1,63 @@
+//! Running machines as [tokio](https://tokio.rs) tasks, for networks of machines that
+//! spend most of their time waiting on each other.
+//!
+//! Input instructions await a channel instead of pausing, and outputs are sent on
+//! another, waiting while its buffer is full. Wiring machines together with bounded
+//! channels gives backpressure for free. A [`Cpu`] isn't `Send`, so machines are
+//! spawned on a [`LocalSet`](tokio::task::LocalSet):
+//!
+//! ```text
+//! let (tx, rx) = mpsc::channel(16);
+//! local.spawn_local(async move { run_async(&mut cpu, &mut inputs, &tx).await });
+//! ```
+
+use tokio::{sync::mpsc, task};
+
+use crate::{Cpu, IntcodeError, StepEvent, Word};
+
+// Instructions executed between yields to the scheduler, so a machine that computes
+// for a long time without IO doesn't starve the other tasks on its thread
+const BATCH: u32 = 1024;
+
+/// Runs `cpu` until it halts, or until it needs input and every sender of `inputs` is
+/// gone. Anything already in `io_in` is read first. Outputs are sent on `outputs`;
+/// once its receiver is gone they are dropped and the machine keeps running.
+///
+/// The machine's input source and output sink aren't used, and it should be in
+/// [`CpuMode::Normal`](crate::CpuMode::Normal).
+pub async fn run_async<W: Word>(
+    cpu: &mut Cpu<W>,
+    inputs: &mut mpsc::Receiver<W>,
+    outputs: &mpsc::Sender<W>,
+) -> Result<(), IntcodeError> {
+    let source = cpu.input_source.take();
+    let result = drive(cpu, inputs, outputs).await;
+    cpu.input_source = source;
+    result
+}
+
+async fn drive<W: Word>(
+    cpu: &mut Cpu<W>,
+    inputs: &mut mpsc::Receiver<W>,
+    outputs: &mpsc::Sender<W>,
+) -> Result<(), IntcodeError> {
+    let mut batch = 0;
+    loop {
+        match cpu.step()? {
+            StepEvent::Continued => (),
+            StepEvent::Output(value) => {
+                let _ = outputs.send(value).await;
+            }
+            StepEvent::NeedsInput => match inputs.recv().await {
+                Some(value) => cpu.io_in.push_front(value),
+                None => return Ok(()),
+            },
+            StepEvent::Halted => return Ok(()),
+        }
+        batch += 1;
+        if batch == BATCH {
+            batch = 0;
+            task::yield_now().await;
+        }
+    }
+}
@@ -0,0 +1,34 @@
+/// Calls back every so many retired instructions, so an animation can pace itself
+/// against how far the program has got rather than waiting for its next output.
+///
+/// The callback is given the number of instructions retired so far. Reaching an input
+/// instruction with no input doesn't count; the instruction is retired once it runs.
+pub struct Ticker {
+    every: u64,
+    retired: u64,
+    callback: Box<dyn FnMut(u64)>,
+}
+
+impl Ticker {
+    /// Calls `callback` after every `every` instructions, or after each one if `every`
+    /// is 0.
+    pub fn new(every: u64, callback: impl FnMut(u64) + 'static) -> Self {
+        Self {
+            every: every.max(1),
+            retired: 0,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Instructions retired since the ticker was created.
+    pub fn retired(&self) -> u64 {
+        self.retired
+    }
+
+    pub(crate) fn retire(&mut self) {
+        self.retired += 1;
+        if self.retired.is_multiple_of(self.every) {
+            (self.callback)(self.retired);
+        }
+    }
+}
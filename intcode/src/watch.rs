@@ -0,0 +1,35 @@
+/// What a [`Cpu`](crate::Cpu) does when the program writes to an address in
+/// [`Cpu::watchpoints`](crate::Cpu::watchpoints). Whatever the watch, the write is
+/// logged, whatever the verbosity, and kept as
+/// [`Cpu::last_watched`](crate::Cpu::last_watched).
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Watch {
+    /// Carry on running
+    Log,
+    /// Return [`RunResult::Watchpoint`](crate::RunResult::Watchpoint) from
+    /// [`Cpu::run`](crate::Cpu::run) once the writing instruction has finished
+    Pause,
+    /// Pause like [`Watch::Pause`], and on reads too. Reads are logged and kept as
+    /// [`Cpu::last_watched_read`](crate::Cpu::last_watched_read).
+    Access,
+}
+
+/// A write by the program, to a watched address or as seen by
+/// [`Cpu::on_memory_write`](crate::Cpu::on_memory_write) hooks.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct WatchedWrite<W = i64> {
+    /// The instruction that wrote
+    pub ip: usize,
+    pub addr: usize,
+    pub old: W,
+    pub new: W,
+}
+
+/// A read by the program of an address watched with [`Watch::Access`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct WatchedRead<W = i64> {
+    /// The instruction that read
+    pub ip: usize,
+    pub addr: usize,
+    pub value: W,
+}
@@ -0,0 +1,54 @@
+use std::fmt::{Debug, Display};
+
+/// The value held in one cell of a [`Cpu`](crate::Cpu)'s memory. `i64` is enough for
+/// every puzzle; `Cpu<i128>` gives programs that multiply very large numbers room to
+/// do so without wrapping.
+///
+/// Opcodes, addresses and jump targets still have to fit in an `i64`. Arbitrary
+/// precision isn't offered, since a cell has to be `Copy`.
+pub trait Word: Copy + Eq + Ord + Default + Debug + Display + From<i64> + 'static {
+    /// The value as an `i64`, or `None` if it doesn't fit.
+    fn to_i64(self) -> Option<i64>;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_mul(self, other: Self) -> Self;
+
+    /// The nearest `i64`, for reporting a value in an [`IntcodeError`](crate::IntcodeError).
+    fn saturate(self) -> i64 {
+        self.to_i64().unwrap_or(if self < Self::from(0) {
+            i64::MIN
+        } else {
+            i64::MAX
+        })
+    }
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {$(
+        impl Word for $t {
+            fn to_i64(self) -> Option<i64> {
+                i64::try_from(self).ok()
+            }
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+
+            fn wrapping_add(self, other: Self) -> Self {
+                <$t>::wrapping_add(self, other)
+            }
+
+            fn wrapping_mul(self, other: Self) -> Self {
+                <$t>::wrapping_mul(self, other)
+            }
+        }
+    )*};
+}
+
+impl_word!(i64, i128);
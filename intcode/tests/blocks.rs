@@ -0,0 +1,61 @@
+use intcode::{BasicBlock, Cpu, Decoded, Verbosity, basic_blocks, cfg_dot};
+
+// Counts m[12] down from 44, then outputs it and halts
+const COUNTDOWN: [i64; 13] = [101, -1, 12, 12, 1005, 12, 0, 4, 12, 99, 0, 0, 44];
+
+fn disassemble(program: &[i64]) -> Vec<Decoded> {
+    let mut cpu = Cpu::with_memory_limit(program.len());
+    cpu.load_program(program).unwrap();
+    cpu.disassemble(0, program.len())
+}
+
+#[test]
+fn blocks_split_at_jumps_targets_and_data() {
+    let lines = disassemble(&COUNTDOWN);
+    let blocks = basic_blocks(&lines);
+    let ranges: Vec<(usize, usize)> = blocks.iter().map(|b| (b.start, b.end)).collect();
+    assert_eq!(ranges, [(0, 7), (7, 10), (10, 13)]);
+    assert_eq!(
+        blocks[1],
+        BasicBlock {
+            start: 7,
+            end: 10,
+            lines: 2..4,
+        }
+    );
+}
+
+#[test]
+fn hot_blocks_rank_by_instructions_executed() {
+    let mut cpu = Cpu::builder()
+        .program(&COUNTDOWN)
+        .verbosity(Verbosity::Silent)
+        .profile(true)
+        .build()
+        .unwrap();
+    cpu.run().unwrap();
+
+    let lines = disassemble(&COUNTDOWN);
+    let blocks = basic_blocks(&lines);
+    let profile = cpu.profile.as_ref().unwrap();
+    let hot: Vec<(usize, u64)> = profile
+        .hot_blocks(&blocks)
+        .into_iter()
+        .map(|(block, count)| (block.start, count))
+        .collect();
+    // The data block never ran, so isn't listed
+    assert_eq!(hot, [(0, 88), (7, 2)]);
+}
+
+#[test]
+fn cfg_has_an_edge_per_jump_and_fall_through() {
+    let dot = cfg_dot(&disassemble(&COUNTDOWN), None);
+    assert!(dot.contains("b0 -> b0 [label=\"jump\"];"));
+    assert!(dot.contains("b0 -> b7;"));
+    // Halts, so nothing leaves the second block, and data isn't drawn
+    assert!(!dot.contains("b7 ->"));
+    assert!(!dot.contains("b10"));
+
+    let computed = cfg_dot(&disassemble(&[2106, 0, -1]), None);
+    assert!(computed.contains("b0 -> elsewhere [style=dashed];"));
+}
@@ -0,0 +1,50 @@
+use intcode::{Cpu, Decoded, Function, Verbosity, find_functions};
+
+// Sets up a stack, calls a function that outputs its first local, and halts
+const CALL: [i64; 19] = [
+    109, 100, // AdjBp #100
+    21101, 0, 9, 0, // Add #0 #9 bp+0
+    1105, 1, 10, // Jnz #1 #10
+    99, // Hlt
+    109, 2, // AdjBp #2
+    204, 1, // Out bp+1
+    109, -2, // AdjBp #-2
+    2105, 1, 0, // Jnz #1 bp+0
+];
+
+fn disassemble(program: &[i64]) -> Vec<Decoded> {
+    let mut cpu = Cpu::with_memory_limit(program.len());
+    cpu.load_program(program).unwrap();
+    cpu.disassemble(0, program.len())
+}
+
+#[test]
+fn calls_and_returns_mark_out_functions() {
+    let functions = find_functions(&disassemble(&CALL));
+    assert_eq!(
+        functions,
+        [Function {
+            entry: 10,
+            end: 19,
+            frame: Some(2),
+            call_sites: vec![6],
+        }]
+    );
+
+    // The analysis agrees with running it
+    let mut cpu = Cpu::builder()
+        .program(&CALL)
+        .verbosity(Verbosity::Silent)
+        .build()
+        .unwrap();
+    cpu.run().unwrap();
+    assert_eq!(cpu.drain_values(), [0]);
+}
+
+#[test]
+fn jumps_without_a_saved_return_address_are_not_calls() {
+    let mut program = CALL;
+    // Saves the wrong return address
+    program[4] = 8;
+    assert_eq!(find_functions(&disassemble(&program)), []);
+}
@@ -0,0 +1,83 @@
+use std::{sync::mpsc, thread};
+
+use intcode::{ChannelInput, ChannelOutput, Cpu, IntcodeError, RunResult, State, Verbosity};
+
+// Reads a value into 17 and outputs it doubled, twice
+const DOUBLE_TWICE: [i64; 17] = [
+    3, 17, 1002, 17, 2, 17, 4, 17, 3, 17, 1002, 17, 2, 17, 4, 17, 99,
+];
+
+#[test]
+fn machines_on_threads() {
+    let (to_first, first_in) = mpsc::channel();
+    let (to_second, second_in) = mpsc::channel();
+    let (to_main, outputs) = mpsc::channel();
+
+    let spawn = |rx, tx| {
+        thread::spawn(move || {
+            let mut cpu = Cpu::builder()
+                .program(&DOUBLE_TWICE)
+                .verbosity(Verbosity::Silent)
+                .input_source(ChannelInput::new(rx))
+                .output_sink(ChannelOutput::new(tx))
+                .build()?;
+            cpu.run()
+        })
+    };
+    let first = spawn(first_in, to_second);
+    let second = spawn(second_in, to_main);
+
+    to_first.send(3).unwrap();
+    to_first.send(5).unwrap();
+    assert_eq!(outputs.iter().collect::<Vec<_>>(), [12, 20]);
+    assert_eq!(first.join().unwrap(), Ok(RunResult::Halted));
+    assert_eq!(second.join().unwrap(), Ok(RunResult::Halted));
+}
+
+#[test]
+fn idle_input_never_blocks() {
+    let (tx, rx) = mpsc::channel();
+    let (out_tx, out_rx) = mpsc::channel();
+    let mut cpu = Cpu::builder()
+        .program(&DOUBLE_TWICE)
+        .verbosity(Verbosity::Silent)
+        .input_source(ChannelInput::idle(rx, -1))
+        .output_sink(ChannelOutput::new(out_tx))
+        .build()
+        .unwrap();
+
+    // Nothing sent yet: reads -1 and pauses straight after
+    cpu.run().unwrap();
+    assert!(matches!(cpu.state, State::Ready));
+    tx.send(4).unwrap();
+    cpu.run().unwrap();
+    assert_eq!(out_rx.try_iter().collect::<Vec<_>>(), [-2, 8]);
+    assert!(matches!(cpu.state, State::Halted));
+}
+
+#[test]
+fn hung_up_channels() {
+    let (tx, rx) = mpsc::channel();
+    let (out_tx, out_rx) = mpsc::channel();
+    let mut cpu = Cpu::builder()
+        .program(&DOUBLE_TWICE)
+        .verbosity(Verbosity::Silent)
+        .input_source(ChannelInput::new(rx))
+        .output_sink(ChannelOutput::new(out_tx))
+        .build()
+        .unwrap();
+
+    // No senders left: waits for input rather than blocking forever
+    drop(tx);
+    cpu.run().unwrap();
+    assert!(matches!(cpu.state, State::Ready));
+
+    let (tx, rx) = mpsc::channel();
+    cpu.input_source = Some(Box::new(ChannelInput::new(rx)));
+    tx.send(1).unwrap();
+    drop(out_rx);
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::RejectedOutput { ip: 6, value: 2 })
+    );
+}
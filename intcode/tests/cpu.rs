@@ -0,0 +1,1439 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use intcode::{
+    Ansi, Constant, CoreDump, Cpu, CpuBuilder, CpuMode, CustomOp, DecodeContext, Decoded, Discard,
+    InputResult, Instruction, IntcodeError, Io, IoLog, JsonTrace, Lines, LogEvent, Op, Operand,
+    OutputResult, Region, RunResult, SliceResult, State, StepEvent, Verbosity, Watch, WatchedRead,
+    WatchedWrite, Yield, diverge, self_test,
+};
+
+fn quiet_cpu(program: &[i64], memory: usize) -> Cpu {
+    Cpu::builder()
+        .program(program)
+        .memory_limit(memory)
+        .verbosity(Verbosity::Silent)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn truncated_instruction_is_an_error() {
+    // An add with only two of its three operands
+    let program = [1, 0, 0];
+    let mut cpu = quiet_cpu(&program, program.len());
+
+    assert_eq!(cpu.run(), Err(IntcodeError::TruncatedInstruction { ip: 0 }));
+    assert!(matches!(cpu.state, State::Halted));
+}
+
+#[test]
+fn running_off_the_end_is_an_error() {
+    // Output 7, then fall off the end of memory
+    let program = [104, 7];
+    let mut cpu = quiet_cpu(&program, program.len());
+
+    assert_eq!(cpu.run(), Err(IntcodeError::TruncatedInstruction { ip: 2 }));
+    assert_eq!(cpu.read_output(), Ok(7));
+}
+
+#[test]
+fn invalid_opcode_and_mode() {
+    let mut cpu = quiet_cpu(&[104, 1, 42], 16);
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::InvalidOpcode {
+            ip: 2,
+            value: 42,
+            context: Box::new(DecodeContext {
+                bp: 0,
+                window_start: 0,
+                window: vec![104, 1, 42, 0, 0, 0, 0],
+                modes: vec![],
+            }),
+        })
+    );
+
+    // The first mode is fine, the second isn't
+    let mut cpu = quiet_cpu(&[109, 3, 99, 3101, 0, 0, 0], 7);
+    cpu.ip = 3;
+    cpu.bp = 5;
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::InvalidMode {
+            ip: 3,
+            value: 3101,
+            context: Box::new(DecodeContext {
+                bp: 5,
+                window_start: 0,
+                window: vec![109, 3, 99, 3101, 0, 0, 0],
+                modes: vec![1, 3],
+            }),
+        })
+    );
+}
+
+#[test]
+fn decode_errors_show_their_context() {
+    let mut cpu = quiet_cpu(&[104, 1, 3201, 0, 0, 0, 99], 16);
+    let err = cpu.run().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid parameter mode in 3201 at 2 (bp 0, modes [2, 3], memory from 0: 104 1 3201 0 0 0 99)"
+    );
+}
+
+#[test]
+fn out_of_bounds_accesses() {
+    // Read past the end of memory
+    let mut cpu = quiet_cpu(&[4, 100, 99], 16);
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::OutOfBounds { ip: 0, addr: 100 })
+    );
+
+    // Write to a negative address
+    let mut cpu = quiet_cpu(&[1101, 1, 1, -1, 99], 16);
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::OutOfBounds { ip: 0, addr: -1 })
+    );
+
+    // Jump to a negative address
+    let mut cpu = quiet_cpu(&[1105, 1, -5], 16);
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::OutOfBounds { ip: 0, addr: -5 })
+    );
+}
+
+#[test]
+fn program_too_large() {
+    let mut cpu = Cpu::with_memory_limit(2);
+    assert_eq!(
+        cpu.load_program(&[1, 2, 3]),
+        Err(IntcodeError::ProgramTooLarge { len: 3, memory: 2 })
+    );
+}
+
+#[test]
+fn missing_output() {
+    let mut cpu = quiet_cpu(&[99], 16);
+    cpu.run().unwrap();
+    assert_eq!(
+        cpu.read_output(),
+        Err(IntcodeError::MissingOutput { ip: 0 })
+    );
+}
+
+#[test]
+fn step_runs_one_instruction() {
+    let mut cpu = quiet_cpu(&[1101, 2, 3, 5, 99, 0], 16);
+
+    assert_eq!(cpu.step(), Ok(StepEvent::Continued));
+    assert_eq!(cpu.ip, 4);
+    assert_eq!(cpu.peek(5), Ok(5));
+    assert_eq!(cpu.step(), Ok(StepEvent::Halted));
+    assert!(matches!(cpu.state, State::Halted));
+}
+
+#[test]
+fn step_reports_io_events() {
+    // Read a value, output it, then halt
+    let mut cpu = quiet_cpu(&[3, 7, 4, 7, 99], 16);
+
+    assert_eq!(cpu.step(), Ok(StepEvent::NeedsInput));
+    assert_eq!(cpu.ip, 0);
+    cpu.io_in.push_front(9);
+    assert_eq!(cpu.step(), Ok(StepEvent::Continued));
+    assert_eq!(cpu.step(), Ok(StepEvent::Output(9)));
+    // Stepping hands outputs back instead of queueing them
+    assert!(cpu.io_out.is_empty());
+    assert_eq!(cpu.step(), Ok(StepEvent::Halted));
+}
+
+#[test]
+fn well_formed_program_runs() {
+    let mut cpu = Cpu::new();
+    cpu.verbosity = Verbosity::Silent;
+    cpu.load_program(&[1002, 4, 3, 4, 33]).unwrap();
+
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    assert_eq!(cpu.peek(4), Ok(99));
+}
+
+#[test]
+fn reset_puts_back_the_loaded_program() {
+    let mut cpu = Cpu::new();
+    cpu.verbosity = Verbosity::Silent;
+    cpu.load_program(&[1002, 4, 3, 4, 33]).unwrap();
+    cpu.run().unwrap();
+    cpu.poke(100, 5).unwrap();
+
+    cpu.reset();
+    assert!(matches!(cpu.state, State::Ready));
+    assert_eq!(cpu.peek(4), Ok(33));
+    assert_eq!(cpu.peek(100), Ok(0));
+    assert_eq!(cpu.memory().allocated(), 5);
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    assert_eq!(cpu.peek(4), Ok(99));
+}
+
+#[test]
+fn memory_grows_to_far_addresses() {
+    // Store 5 at a trillion, then output it back
+    let far = 1_000_000_000_000;
+    let program = [1101, 5, 0, far, 4, far, 99];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .build()
+        .unwrap();
+
+    assert_eq!(cpu.memory().allocated(), program.len());
+    cpu.run().unwrap();
+    assert_eq!(cpu.read_output(), Ok(5));
+    assert_eq!(cpu.peek(far as usize), Ok(5));
+}
+
+#[test]
+fn peak_address_tracks_reads_writes_and_code() {
+    let mut cpu = quiet_cpu(&[1101, 1, 2, 9, 99], 100);
+    assert_eq!(cpu.peak_address(), None);
+
+    // Fetching the add reaches 3, then it writes to 9
+    cpu.step().unwrap();
+    assert_eq!(cpu.peak_address(), Some(9));
+
+    // Reads count too, and loading a program resets it
+    cpu.load_program(&[4, 50, 99]).unwrap();
+    cpu.run().unwrap();
+    assert_eq!(cpu.peak_address(), Some(50));
+}
+
+#[test]
+fn memory_limit_can_be_changed() {
+    let mut cpu = quiet_cpu(&[4, 50, 99], 100);
+    assert_eq!(cpu.memory_limit(), Some(100));
+
+    cpu.set_memory_limit(Some(20));
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::OutOfBounds { ip: 0, addr: 50 })
+    );
+
+    cpu.load_program(&[4, 50, 99]).unwrap();
+    cpu.set_memory_limit(None);
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+}
+
+#[test]
+fn builder_configures_the_machine() {
+    let mut cpu = Cpu::builder()
+        .program(&[3, 12, 3, 13, 1, 12, 13, 14, 4, 14, 99])
+        .memory_limit(16)
+        .verbosity(Verbosity::Silent)
+        .mode(CpuMode::Network(0))
+        .input(&[2])
+        .profile(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(cpu.memory_limit(), Some(16));
+    cpu.run().unwrap();
+    // Read -1 for the missing input and paused rather than waiting
+    assert!(matches!(cpu.state, State::Ready));
+    assert_eq!(cpu.profile.as_ref().map(|profile| profile.total), Some(2));
+    cpu.run().unwrap();
+    assert_eq!(cpu.read_output(), Ok(1));
+}
+
+#[test]
+fn builder_without_a_program_starts_halted() {
+    let cpu = Cpu::builder().build().unwrap();
+    assert!(matches!(cpu.state, State::Halted));
+
+    let too_large = Cpu::builder().program(&[1, 2, 3]).memory_limit(2).build();
+    assert!(matches!(
+        too_large,
+        Err(IntcodeError::ProgramTooLarge { len: 3, memory: 2 })
+    ));
+}
+
+#[test]
+fn builder_ascii_mode() {
+    // Prints "ok\n"
+    let program = [104, 111, 104, 107, 104, 10, 99];
+    let mut ascii = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .ascii_mode()
+        .build()
+        .unwrap();
+
+    ascii.run().unwrap();
+    assert_eq!(ascii.read_output_string(), "ok\n");
+}
+
+#[test]
+fn converse_replies_a_line_at_a_time() {
+    // Prints "?", reads a line of one character and prints it back with a "!"
+    let mut program = vec![104, 63, 104, 10, 3, 20, 4, 20, 3, 21, 104, 33, 4, 21, 99];
+    program.resize(22, 0);
+    let mut ascii = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .ascii_mode()
+        .build()
+        .unwrap();
+
+    assert_eq!(ascii.converse(None).unwrap(), "?\n");
+    assert!(matches!(ascii.cpu.state, State::Ready));
+    assert_eq!(ascii.converse(Some("h")).unwrap(), "h!\n");
+    assert!(matches!(ascii.cpu.state, State::Halted));
+}
+
+#[test]
+fn ascii_io_on_the_cpu() {
+    // Echoes one byte of input, then prints 1000
+    let program = [3, 9, 4, 9, 104, 1000, 104, 10, 99, 0];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .build()
+        .unwrap();
+
+    cpu.push_ascii_line("hi");
+    assert_eq!(cpu.io_in.len(), 3);
+    cpu.run().unwrap();
+    assert_eq!(cpu.drain_ascii_output(), "h\n");
+    assert_eq!(cpu.drain_values(), [1000]);
+    assert!(cpu.drain_values().is_empty());
+}
+
+// Reads two inputs into 11 and 12 and outputs their sum
+const ADD_INPUTS: [i64; 11] = [3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99];
+
+#[test]
+fn constant_input_source() {
+    let mut cpu = Cpu::builder()
+        .program(&ADD_INPUTS)
+        .verbosity(Verbosity::Silent)
+        .input(&[100, 100])
+        .input_source(Constant(4))
+        .build()
+        .unwrap();
+
+    cpu.run().unwrap();
+    // The source replaces io_in, which is left alone
+    assert_eq!(cpu.read_output(), Ok(8));
+    assert_eq!(cpu.io_in.len(), 2);
+}
+
+#[test]
+fn closure_input_source_can_pause() {
+    let mut values = vec![5];
+    let mut cpu = Cpu::builder()
+        .program(&ADD_INPUTS)
+        .verbosity(Verbosity::Silent)
+        .input_source(move || match values.pop() {
+            Some(value) => InputResult::Value(value),
+            None => InputResult::Pending,
+        })
+        .build()
+        .unwrap();
+
+    cpu.run().unwrap();
+    assert!(matches!(cpu.state, State::Ready));
+    assert_eq!(cpu.step(), Ok(StepEvent::NeedsInput));
+
+    cpu.input_source = Some(Box::new(VecDeque::from([7])));
+    cpu.run().unwrap();
+    assert_eq!(cpu.read_output(), Ok(12));
+}
+
+#[test]
+fn yielding_input_returns_from_run() {
+    let mut cpu = Cpu::builder()
+        .program(&ADD_INPUTS)
+        .verbosity(Verbosity::Silent)
+        .input_source(|| InputResult::Yield(1))
+        .build()
+        .unwrap();
+
+    // Each input is read, then run returns straight after it
+    cpu.run().unwrap();
+    assert!(matches!(cpu.state, State::Ready));
+    assert_eq!(cpu.ip, 2);
+    cpu.run().unwrap();
+    assert_eq!(cpu.ip, 4);
+    cpu.run().unwrap();
+    assert_eq!(cpu.read_output(), Ok(2));
+    assert!(matches!(cpu.state, State::Halted));
+}
+
+// Outputs 1, 2, 3
+const COUNT: [i64; 7] = [104, 1, 104, 2, 104, 3, 99];
+
+#[test]
+fn shared_output_sink() {
+    let outputs = Rc::new(RefCell::new(VecDeque::new()));
+    let mut cpu = Cpu::builder()
+        .program(&COUNT)
+        .verbosity(Verbosity::Silent)
+        .output_sink(Rc::clone(&outputs))
+        .build()
+        .unwrap();
+
+    cpu.run().unwrap();
+    assert!(cpu.io_out.is_empty());
+    assert_eq!(*outputs.borrow(), [3, 2, 1]);
+
+    cpu.load_program(&COUNT).unwrap();
+    cpu.output_sink = Some(Box::new(Discard));
+    cpu.run().unwrap();
+    assert_eq!(outputs.borrow().len(), 3);
+}
+
+#[test]
+fn output_sink_can_yield_and_reject() {
+    let mut cpu = Cpu::builder()
+        .program(&COUNT)
+        .verbosity(Verbosity::Silent)
+        .output_sink(|value| match value {
+            1 => OutputResult::Yield,
+            2 => OutputResult::Continue,
+            _ => OutputResult::Reject,
+        })
+        .build()
+        .unwrap();
+
+    cpu.run().unwrap();
+    assert!(matches!(cpu.state, State::Ready));
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::RejectedOutput { ip: 4, value: 3 })
+    );
+    assert!(matches!(cpu.state, State::Halted));
+}
+
+#[test]
+fn lines_sink() {
+    // Prints "hi\nyo\n" then 200
+    let program = [
+        104, 104, 104, 105, 104, 10, 104, 121, 104, 111, 104, 10, 104, 200, 99,
+    ];
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let seen = Rc::clone(&lines);
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .output_sink(Lines::new(move |line: &str| {
+            seen.borrow_mut().push(line.to_string());
+            OutputResult::Continue
+        }))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::RejectedOutput { ip: 12, value: 200 })
+    );
+    assert_eq!(*lines.borrow(), ["hi", "yo"]);
+}
+
+#[test]
+fn heatmap_finds_regions() {
+    // Adds the constant at 9 into 10 twice, then halts; 11 is never touched
+    let program = [1, 9, 10, 10, 1, 9, 10, 10, 99, 5, 0, 0];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .heatmap(true)
+        .build()
+        .unwrap();
+    cpu.run().unwrap();
+
+    let heatmap = cpu.heatmap.as_ref().unwrap();
+    assert_eq!(heatmap.region(0), Region::Code);
+    assert_eq!(heatmap.region(9), Region::Data);
+    assert_eq!(heatmap.region(10), Region::Scratch);
+    assert_eq!(heatmap.region(11), Region::Unused);
+    // Read and written by both adds
+    assert_eq!(heatmap.heat(10), 4);
+    assert_eq!(heatmap.extent(), 11);
+}
+
+// Doubles an input, then reads another input and counts to 3
+fn add_then_count() -> Vec<i64> {
+    let mut program = vec![3, 100, 1, 100, 100, 100, 4, 100, 3, 100];
+    program.extend(COUNT);
+    program
+}
+
+#[test]
+fn outputs_run_lazily() {
+    let program = add_then_count();
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .input(&[4])
+        .build()
+        .unwrap();
+
+    // Stops right after the first output, leaving the rest of the program
+    assert_eq!(cpu.outputs().next(), Some(Ok(8)));
+    assert_eq!(cpu.ip, 8);
+    // Then ends when the program asks for more input
+    assert_eq!(cpu.outputs().next(), None);
+    assert!(matches!(cpu.state, State::Ready));
+
+    cpu.io_in.push_front(0);
+    let rest: Vec<_> = cpu.outputs().collect();
+    assert_eq!(rest, [Ok(1), Ok(2), Ok(3)]);
+    assert!(matches!(cpu.state, State::Halted));
+    assert_eq!(cpu.outputs().next(), None);
+}
+
+#[test]
+fn outputs_end_after_an_error() {
+    let mut cpu = quiet_cpu(&[104, 1, 42], 16);
+    let outputs: Vec<_> = cpu.outputs().collect();
+    assert_eq!(
+        outputs,
+        [
+            Ok(1),
+            Err(IntcodeError::InvalidOpcode {
+                ip: 2,
+                value: 42,
+                context: Box::new(DecodeContext {
+                    bp: 0,
+                    window_start: 0,
+                    window: vec![104, 1, 42, 0, 0, 0, 0],
+                    modes: vec![],
+                }),
+            })
+        ]
+    );
+}
+
+#[test]
+fn run_until_output_then_input_needed() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let sink_seen = Rc::clone(&seen);
+    let mut cpu = Cpu::builder()
+        .program(&add_then_count())
+        .verbosity(Verbosity::Silent)
+        .input(&[4])
+        .output_sink(move |value| {
+            sink_seen.borrow_mut().push(value);
+            OutputResult::Yield
+        })
+        .build()
+        .unwrap();
+
+    // The output comes back here instead of going to the sink
+    assert_eq!(cpu.run_until_output(), Ok(Some(8)));
+    assert!(seen.borrow().is_empty());
+    assert_eq!(cpu.run_until_output(), Ok(None));
+    assert!(matches!(cpu.state, State::Ready));
+
+    // The sink yields after every output, but only a lack of input stops this
+    cpu.io_in.push_front(0);
+    cpu.run_until_input_needed().unwrap();
+    assert_eq!(*seen.borrow(), [1, 2, 3]);
+    assert!(matches!(cpu.state, State::Halted));
+    assert_eq!(cpu.run_until_output(), Ok(None));
+}
+
+#[test]
+fn run_with_limit_stops_a_runaway_program() {
+    // Outputs 7, then jumps back to the start forever
+    let mut cpu = quiet_cpu(&[104, 7, 1105, 1, 0], 16);
+    assert_eq!(
+        cpu.run_with_limit(5),
+        Err(IntcodeError::InstructionLimit { ip: 2, limit: 5 })
+    );
+    assert_eq!(cpu.io_out, [7, 7, 7]);
+    // Paused rather than halted, so it can be given another budget
+    assert!(matches!(cpu.state, State::Ready));
+    assert!(cpu.run_with_limit(1).is_err());
+    assert_eq!(cpu.ip, 0);
+
+    let mut cpu = quiet_cpu(&COUNT, 16);
+    assert_eq!(cpu.run_with_limit(4), Ok(RunResult::Halted));
+    assert!(matches!(cpu.state, State::Halted));
+}
+
+#[test]
+fn run_for_shares_out_time_slices() {
+    let mut cpu = quiet_cpu(&[104, 7, 1105, 1, 0], 16);
+    assert_eq!(cpu.run_for(3), Ok(SliceResult::Expired));
+    assert_eq!(cpu.io_out, [7, 7]);
+    assert_eq!(cpu.run_for(1), Ok(SliceResult::Expired));
+    assert_eq!(cpu.ip, 0);
+
+    let mut cpu = quiet_cpu(&[3, 0, 99], 16);
+    assert_eq!(
+        cpu.run_for(10),
+        Ok(SliceResult::Stopped(RunResult::NeedsInput))
+    );
+    cpu.io_in.push_front(1);
+    assert_eq!(cpu.run_for(10), Ok(SliceResult::Stopped(RunResult::Halted)));
+}
+
+#[test]
+fn ticker_counts_retired_instructions() {
+    let ticks = Rc::new(RefCell::new(Vec::new()));
+    let seen = Rc::clone(&ticks);
+    let mut cpu = Cpu::builder()
+        .program(&add_then_count())
+        .verbosity(Verbosity::Silent)
+        .ticker(2, move |retired| seen.borrow_mut().push(retired))
+        .build()
+        .unwrap();
+
+    // Waiting at the first input retires nothing
+    cpu.run().unwrap();
+    assert!(ticks.borrow().is_empty());
+    cpu.io_in.push_front(1);
+    cpu.io_in.push_front(2);
+    cpu.run().unwrap();
+    // Input, add, output, input, three outputs and the halt
+    assert_eq!(cpu.ticker.as_ref().map(|ticker| ticker.retired()), Some(8));
+    assert_eq!(*ticks.borrow(), [2, 4, 6, 8]);
+}
+
+#[test]
+fn run_says_why_it_stopped() {
+    let mut cpu = Cpu::builder()
+        .program(&add_then_count())
+        .verbosity(Verbosity::Silent)
+        .mode(CpuMode::Network(0))
+        .build()
+        .unwrap();
+    assert_eq!(cpu.run(), Ok(RunResult::InputYielded));
+
+    let mut cpu = quiet_cpu(&add_then_count(), 128);
+    cpu.output_sink = Some(Box::new(|_| OutputResult::Yield));
+    assert_eq!(cpu.run(), Ok(RunResult::NeedsInput));
+    cpu.io_in.push_front(1);
+    assert_eq!(cpu.run(), Ok(RunResult::OutputPending));
+    assert_eq!(cpu.ip, 8);
+
+    // Stops before the second output of the count, then carries on past it
+    cpu.breakpoints.insert(12);
+    cpu.output_sink = None;
+    assert_eq!(cpu.run(), Ok(RunResult::NeedsInput));
+    cpu.io_in.push_front(0);
+    assert_eq!(cpu.run(), Ok(RunResult::Breakpoint(12)));
+    assert!(matches!(cpu.state, State::Ready));
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    assert_eq!(cpu.io_out, [3, 2, 1]);
+}
+
+#[test]
+fn peek_and_poke_grow_memory_within_the_limit() {
+    let mut cpu = quiet_cpu(&[99], 64);
+    assert_eq!(cpu.peek(40), Ok(0));
+    assert_eq!(cpu.poke(40, 7), Ok(()));
+    assert_eq!(cpu.peek(40), Ok(7));
+    assert_eq!(cpu.memory().allocated(), 41);
+
+    assert_eq!(
+        cpu.poke(64, 1),
+        Err(IntcodeError::OutOfBounds { ip: 0, addr: 64 })
+    );
+    assert_eq!(
+        cpu.peek(64),
+        Err(IntcodeError::OutOfBounds { ip: 0, addr: 64 })
+    );
+}
+
+#[test]
+fn restore_rewinds_to_a_snapshot() {
+    let mut cpu = quiet_cpu(&add_then_count(), 128);
+    cpu.io_in.push_front(4);
+    cpu.run().unwrap();
+    let snapshot = cpu.snapshot();
+
+    // One branch finishes the program and changes memory
+    cpu.io_in.push_front(0);
+    cpu.run().unwrap();
+    cpu.poke(100, 42).unwrap();
+    assert!(matches!(cpu.state, State::Halted));
+
+    // The other starts from the same place
+    cpu.restore(&snapshot);
+    assert_eq!(cpu.ip, 8);
+    assert_eq!(cpu.peek(100), Ok(8));
+    assert_eq!(cpu.io_out, [8]);
+    cpu.io_in.push_front(0);
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    assert_eq!(cpu.io_out, [3, 2, 1, 8]);
+}
+
+#[test]
+fn strict_mode_catches_overflow() {
+    // Doubles i64::MAX into 9 and outputs it
+    let program = [1, 9, 9, 9, 4, 9, 99, 0, 0, i64::MAX];
+    let mut cpu = quiet_cpu(&program, 16);
+    cpu.run().unwrap();
+    assert_eq!(cpu.read_output(), Ok(-2));
+
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .strict(true)
+        .build()
+        .unwrap();
+    assert_eq!(cpu.run(), Err(IntcodeError::Overflow { ip: 0 }));
+    assert_eq!(cpu.peek(9), Ok(i64::MAX));
+}
+
+#[test]
+fn wide_cells_hold_what_i64_cannot() {
+    // Multiplies the input by itself into 11 and outputs it
+    let program: Vec<i128> = vec![3, 11, 2, 11, 11, 11, 4, 11, 99, 0, 0, 0];
+    let mut cpu = CpuBuilder::<i128>::default()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .strict(true)
+        .input(&[i64::MAX as i128])
+        .build()
+        .unwrap();
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    assert_eq!(cpu.read_output(), Ok(i64::MAX as i128 * i64::MAX as i128));
+
+    // Addresses still have to fit in an i64
+    let mut cpu = CpuBuilder::<i128>::default()
+        .program(&[1, 1 << 64, 0, 0, 99])
+        .verbosity(Verbosity::Silent)
+        .build()
+        .unwrap();
+    assert_eq!(
+        cpu.run(),
+        Err(IntcodeError::OutOfBounds {
+            ip: 0,
+            addr: i64::MAX
+        })
+    );
+}
+
+#[test]
+fn logger_gets_what_the_verbosity_allows() {
+    // Reads a value, outputs it and halts
+    let program = [3, 5, 4, 5, 99, 0];
+    let run = |verbosity| {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&events);
+        let mut cpu = Cpu::builder()
+            .program(&program)
+            .verbosity(verbosity)
+            .logger(move |event: LogEvent| log.borrow_mut().push(format!("{event:?}")))
+            .build()
+            .unwrap();
+        cpu.run().unwrap();
+        cpu.io_in.push_front(7);
+        cpu.run().unwrap();
+        events.take()
+    };
+
+    assert!(run(Verbosity::Silent).is_empty());
+    assert_eq!(
+        run(Verbosity::Io),
+        ["WaitingForInput", "Input(7)", "Output(7)", "Halted"]
+    );
+    let trace = run(Verbosity::Trace);
+    assert_eq!(trace.len(), 8);
+    assert_eq!(
+        trace[0],
+        "Instruction { ip: 0, bp: 0, op: In, instruction: 3, operands: [5] }"
+    );
+}
+
+#[test]
+fn stats_count_retired_instructions() {
+    // Reads a value, outputs it and halts
+    let program = [3, 5, 4, 5, 99, 0];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .stats(true)
+        .build()
+        .unwrap();
+    cpu.run().unwrap();
+    cpu.io_in.push_front(7);
+    cpu.run().unwrap();
+
+    let stats = cpu.stats().unwrap();
+    assert_eq!(stats.total, 3);
+    assert_eq!(stats.waits, 1);
+    assert_eq!((stats.inputs(), stats.outputs()), (1, 1));
+    assert_eq!(
+        stats.op_counts().collect::<Vec<_>>(),
+        [(Op::In, 1), (Op::Out, 1), (Op::Hlt, 1)]
+    );
+
+    cpu.set_stats(false);
+    assert_eq!(cpu.stats(), None);
+}
+
+#[test]
+fn waiting_for_input_is_not_profiled() {
+    // Reads a value, outputs it and halts
+    let program = [3, 5, 4, 5, 99, 0];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .profile(true)
+        .build()
+        .unwrap();
+    cpu.op_log = Some(Vec::new());
+    cpu.run().unwrap();
+    cpu.run().unwrap();
+    cpu.io_in.push_front(7);
+    cpu.run().unwrap();
+
+    let profile = cpu.profile.as_ref().unwrap();
+    assert_eq!(profile.total, 3);
+    assert_eq!(profile.addr_counts.get(&0), Some(&1));
+    assert_eq!(cpu.op_log, Some(vec![Op::In, Op::Out, Op::Hlt]));
+}
+
+#[test]
+fn coverage_separates_code_from_data() {
+    // Jumps over the data at 3 and 4 to output it, then halts
+    let program = [1105, 1, 5, 42, 0, 4, 3, 99];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .coverage(true)
+        .build()
+        .unwrap();
+    cpu.run().unwrap();
+
+    let coverage = cpu.coverage().unwrap();
+    assert_eq!(coverage.instructions().collect::<Vec<_>>(), [0, 5, 7]);
+    assert!(coverage.is_code(1) && !coverage.executed(1));
+    assert!(!coverage.is_code(3));
+    assert_eq!(coverage.code_ranges(), [0..3, 5..8]);
+}
+
+#[test]
+fn watchpoints_log_and_pause_on_writes() {
+    // Adds 1 to 9 twice, then halts
+    let program = [1001, 9, 1, 9, 1001, 9, 1, 9, 99, 5];
+    let writes = Rc::new(RefCell::new(Vec::new()));
+    let log = Rc::clone(&writes);
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .logger(move |event: LogEvent| {
+            if let LogEvent::Watched(write) = event {
+                log.borrow_mut().push(write);
+            }
+        })
+        .build()
+        .unwrap();
+
+    cpu.watchpoints.insert(9, Watch::Pause);
+    assert_eq!(cpu.run(), Ok(RunResult::Watchpoint(9)));
+    assert_eq!(cpu.ip, 4);
+    let first = WatchedWrite {
+        ip: 0,
+        addr: 9,
+        old: 5,
+        new: 6,
+    };
+    assert_eq!(cpu.last_watched(), Some(&first));
+
+    cpu.watchpoints.insert(9, Watch::Log);
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    let second = WatchedWrite {
+        ip: 4,
+        addr: 9,
+        old: 6,
+        new: 7,
+    };
+    assert_eq!(*writes.borrow(), [first, second]);
+}
+
+#[test]
+fn json_trace_records_each_retired_instruction() {
+    let path = std::env::temp_dir().join(format!("intcode-trace-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    // Reads into 9, adds 1 to it in place and halts
+    let program = [3, 9, 1001, 9, 1, 9, 99, 0, 0, 0];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .json_trace(JsonTrace::append(&path).unwrap())
+        .build()
+        .unwrap();
+    cpu.run().unwrap();
+    cpu.io_in.push_front(4);
+    cpu.run().unwrap();
+    cpu.json_trace.take().unwrap().finish().unwrap();
+
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        trace.lines().collect::<Vec<_>>(),
+        [
+            r#"{"ip":0,"op":"In","instruction":3,"operands":[9],"modes":[0],"writes":[{"addr":9,"value":4}]}"#,
+            r#"{"ip":2,"op":"Add","instruction":1001,"operands":[9,1,9],"modes":[0,1,0],"writes":[{"addr":9,"value":5}]}"#,
+            r#"{"ip":6,"op":"Hlt","instruction":99,"operands":[],"modes":[],"writes":[]}"#,
+        ]
+    );
+}
+
+#[test]
+fn replay_reproduces_a_recorded_run() {
+    // Outputs each input doubled, forever
+    let program = [3, 9, 1002, 9, 2, 9, 4, 9, 1105, 1, 0];
+    let build = || {
+        Cpu::builder()
+            .program(&program)
+            .verbosity(Verbosity::Silent)
+            .io_log(true)
+            .build()
+            .unwrap()
+    };
+    let mut cpu = build();
+    cpu.io_in.push_front(3);
+    cpu.io_in.push_front(5);
+    cpu.run().unwrap();
+    let log = cpu.io_log.take().unwrap();
+    assert_eq!(log.to_string(), "in 3,5\nout 6,10\n");
+    assert_eq!(log.to_string().parse(), Ok(log.clone()));
+
+    // Carries on from where the log ends
+    let mut cpu = build();
+    assert_eq!(cpu.replay(&log), Ok(()));
+    assert!(cpu.io_out.is_empty());
+    cpu.io_in.push_front(1);
+    cpu.run().unwrap();
+    assert_eq!(cpu.read_output(), Ok(2));
+
+    let wrong: IoLog = "in 3,5\nout 6,11".parse().unwrap();
+    assert_eq!(
+        build().replay(&wrong),
+        Err(IntcodeError::ReplayDiverged {
+            ip: 6,
+            index: 1,
+            expected: Some(11),
+            actual: Some(10)
+        })
+    );
+    let longer: IoLog = "in 3\nout 6,10".parse().unwrap();
+    assert_eq!(
+        build().replay(&longer),
+        Err(IntcodeError::ReplayDiverged {
+            ip: 0,
+            index: 1,
+            expected: Some(10),
+            actual: None
+        })
+    );
+}
+
+#[test]
+fn resume_drives_the_machine_as_a_coroutine() {
+    // Outputs each input doubled until it reads 0
+    let program = [
+        3, 15, 1006, 15, 14, 1002, 15, 2, 15, 4, 15, 1105, 1, 0, 99, 0,
+    ];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .mode(CpuMode::Network(0))
+        .input_source(Constant(7))
+        .build()
+        .unwrap();
+
+    // Neither the NIC mode nor the input source supplies input while resuming
+    assert_eq!(cpu.resume(None), Ok(Yield::NeedsInput));
+    assert_eq!(cpu.resume(Some(4)), Ok(Yield::Output(8)));
+    assert_eq!(cpu.resume(None), Ok(Yield::NeedsInput));
+    assert_eq!(cpu.resume(Some(0)), Ok(Yield::Done));
+    assert_eq!(cpu.resume(Some(5)), Ok(Yield::Done));
+}
+
+#[test]
+fn starved_machine_reports_a_deadlock() {
+    let mut cpu = Cpu::builder()
+        .program(&ADD_INPUTS)
+        .verbosity(Verbosity::Silent)
+        .deadlock_after(2)
+        .build()
+        .unwrap();
+
+    // Reading resets the count
+    assert_eq!(cpu.run(), Ok(RunResult::NeedsInput));
+    cpu.io_in.push_front(1);
+    assert_eq!(cpu.run(), Ok(RunResult::NeedsInput));
+    assert_eq!(cpu.run(), Err(IntcodeError::Deadlocked { ip: 2, waits: 2 }));
+    assert!(matches!(cpu.state, State::Halted));
+}
+
+#[test]
+fn registered_opcodes_run_like_built_in_ones() {
+    // 42 subtracts, 50 reports its operand
+    let program = [1142, 7, 3, 8, 50, 8, 99, 0, 0];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .stats(true)
+        .build()
+        .unwrap();
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let sink = seen.clone();
+    cpu.register_op(
+        42,
+        CustomOp::writing(3, |operands| operands[0] - operands[1]),
+    );
+    cpu.register_op(
+        50,
+        CustomOp::reading(1, move |operands| sink.borrow_mut().push(operands[0])),
+    );
+
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    assert_eq!(cpu.peek(8), Ok(4));
+    assert_eq!(*seen.borrow(), [4]);
+    assert_eq!(cpu.stats().unwrap().count(Op::Custom(42)), 1);
+
+    // Unregistered opcodes are still invalid
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        cpu.run(),
+        Err(IntcodeError::InvalidOpcode { value: 1142, .. })
+    ));
+}
+
+#[test]
+fn hooks_see_instructions_writes_and_io() {
+    let mut cpu = Cpu::builder()
+        .program(&ADD_INPUTS)
+        .verbosity(Verbosity::Silent)
+        .input(&[2, 3])
+        .build()
+        .unwrap();
+    let ops = Rc::new(RefCell::new(Vec::new()));
+    let writes = Rc::new(RefCell::new(Vec::new()));
+    let io = Rc::new(RefCell::new(Vec::new()));
+    let (o, w, i) = (ops.clone(), writes.clone(), io.clone());
+    cpu.on_before_instruction(move |instruction: &Instruction| {
+        o.borrow_mut().push((instruction.ip, instruction.op))
+    });
+    cpu.on_memory_write(move |write: &WatchedWrite| w.borrow_mut().push((write.addr, write.new)));
+    cpu.on_io(move |event| i.borrow_mut().push(event));
+
+    cpu.poke(13, 7).unwrap();
+    cpu.run().unwrap();
+    assert_eq!(
+        *ops.borrow(),
+        [
+            (0, Op::In),
+            (2, Op::In),
+            (4, Op::Add),
+            (8, Op::Out),
+            (10, Op::Hlt)
+        ]
+    );
+    assert_eq!(*writes.borrow(), [(11, 2), (12, 3), (13, 5)]);
+    assert_eq!(*io.borrow(), [Io::Input(2), Io::Input(3), Io::Output(5)]);
+
+    cpu.clear_hooks();
+    cpu.reset();
+    cpu.io_in.extend([1, 1]);
+    cpu.run().unwrap();
+    assert_eq!(ops.borrow().len(), 5);
+}
+
+#[test]
+fn writes_over_code_already_run_are_flagged() {
+    // Writes to data at 9, then rewrites the first instruction
+    let program = [1101, 1, 1, 9, 1001, 0, 0, 0, 99, 0];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .self_modifying(true)
+        .build()
+        .unwrap();
+
+    cpu.run().unwrap();
+    assert!(cpu.coverage().is_some());
+    assert_eq!(
+        cpu.self_modifying(),
+        Some(
+            &[WatchedWrite {
+                ip: 4,
+                addr: 0,
+                old: 1101,
+                new: 1101
+            }][..]
+        )
+    );
+}
+
+#[test]
+fn interpreter_passes_its_self_test() {
+    let report = self_test();
+    let failures: Vec<_> = report
+        .results
+        .iter()
+        .filter_map(|result| Some((result.name, result.failure.as_ref()?)))
+        .collect();
+    assert!(failures.is_empty(), "{failures:?}");
+    assert!(report.passed());
+}
+
+#[test]
+fn disassemble_shows_operand_modes() {
+    let cpu = quiet_cpu(&[1001, 4, -3, 4, 204, 2, 99, 42], 16);
+    let lines = cpu.disassemble(0, 4);
+    assert_eq!(
+        lines[0],
+        Decoded::Instruction {
+            addr: 0,
+            op: Op::Add,
+            operands: vec![
+                Operand::Position(4),
+                Operand::Immediate(-3),
+                Operand::Position(4)
+            ],
+        }
+    );
+    let text: Vec<(usize, String)> = lines
+        .iter()
+        .map(|line| (line.addr(), line.to_string()))
+        .collect();
+    assert_eq!(
+        text,
+        [
+            (0, "Add @4 #-3 @4".to_string()),
+            (4, "Out bp+2".to_string()),
+            (6, "Hlt".to_string()),
+            (7, "data 42".to_string()),
+        ]
+    );
+
+    // Stops at the end of limited memory
+    assert_eq!(cpu.disassemble(6, 100).len(), 10);
+}
+
+#[test]
+fn op_breakpoints_pause_before_an_opcode() {
+    let mut cpu = quiet_cpu(&COUNT, 16);
+    cpu.op_breakpoints.insert(Op::Out);
+    let mut stops = Vec::new();
+    while let RunResult::Breakpoint(addr) = cpu.run().unwrap() {
+        stops.push(addr);
+    }
+    // Like an address breakpoint, one where the run starts pauses it too
+    assert_eq!(stops, [0, 2, 4]);
+    assert!(matches!(cpu.state, State::Halted));
+    assert_eq!(cpu.io_out, [3, 2, 1]);
+}
+
+#[test]
+fn access_watchpoints_pause_on_reads() {
+    // Copies 9 to 10, then adds 1 to 9
+    let program = [1001, 9, 0, 10, 1001, 9, 1, 9, 99, 5, 0];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .logger(|_: LogEvent| ())
+        .build()
+        .unwrap();
+
+    cpu.watchpoints.insert(9, Watch::Access);
+    assert_eq!(cpu.run(), Ok(RunResult::Watchpoint(9)));
+    assert_eq!(cpu.ip, 4);
+    let read = WatchedRead {
+        ip: 0,
+        addr: 9,
+        value: 5,
+    };
+    assert_eq!(cpu.last_watched_read(), Some(&read));
+    assert_eq!(cpu.last_watched(), None);
+
+    // Reads and writes 9 in one instruction, so pauses once
+    assert_eq!(cpu.run(), Ok(RunResult::Watchpoint(9)));
+    assert_eq!(cpu.ip, 8);
+    assert_eq!(cpu.last_watched().map(|write| write.new), Some(6));
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+}
+
+#[test]
+fn breakpoint_at_the_entry_point_pauses_the_first_run() {
+    let mut cpu = quiet_cpu(&COUNT, 16);
+    cpu.breakpoints.insert(0);
+    assert_eq!(cpu.run(), Ok(RunResult::Breakpoint(0)));
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    assert_eq!(cpu.io_out, [3, 2, 1]);
+}
+
+#[test]
+fn breakpoint_right_after_a_watchpoint_pauses() {
+    // Adds 1 to 9, outputs it and halts
+    let program = [1001, 9, 1, 9, 4, 9, 99, 0, 0, 5];
+    let mut cpu = Cpu::builder()
+        .program(&program)
+        .verbosity(Verbosity::Silent)
+        .logger(|_: LogEvent| ())
+        .build()
+        .unwrap();
+    cpu.watchpoints.insert(9, Watch::Pause);
+    cpu.breakpoints.insert(4);
+
+    assert_eq!(cpu.run(), Ok(RunResult::Watchpoint(9)));
+    assert_eq!(cpu.run(), Ok(RunResult::Breakpoint(4)));
+    // Carrying on passes the breakpoint just reported, once
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    assert_eq!(cpu.io_out, [6]);
+}
+
+#[test]
+fn at_breakpoint_checks_address_and_opcode() {
+    let mut cpu = quiet_cpu(&COUNT, 16);
+    assert!(!cpu.at_breakpoint());
+    cpu.breakpoints.insert(0);
+    assert!(cpu.at_breakpoint());
+    cpu.breakpoints.clear();
+    cpu.op_breakpoints.insert(Op::Out);
+    assert!(cpu.at_breakpoint());
+    cpu.ip = 6;
+    assert!(!cpu.at_breakpoint());
+}
+
+#[test]
+fn core_dumps_round_trip_through_text() {
+    // Reads into 20 and adds 1 + 2 into 21, then fails on 42
+    let program = [3, 20, 1101, 1, 2, 21, 42];
+    let mut cpu = quiet_cpu(&program, 2000);
+    cpu.io_in.extend([6, 5]);
+    cpu.poke(1500, 7).unwrap();
+    let err = cpu.run().unwrap_err();
+
+    let dump = cpu.core_dump(Some(&err));
+    assert_eq!(dump.ip, 6);
+    assert!(dump.halted);
+    assert_eq!(dump.io_in, vec![6]);
+    assert_eq!(dump.get(20), 5);
+    assert_eq!(dump.get(21), 3);
+    assert_eq!(dump.get(1500), 7);
+
+    let text = dump.to_string();
+    assert!(text.contains("mem 0 3,20,1101,1,2,21,42,0,"));
+    assert!(text.contains("mem 1500 7\n"));
+    let parsed: CoreDump = text.parse().unwrap();
+    assert_eq!(parsed, dump);
+
+    let mut loaded = Cpu::new();
+    loaded.load_core_dump(&parsed);
+    assert_eq!(loaded.ip, 6);
+    assert_eq!(loaded.memory_limit(), Some(2000));
+    assert_eq!(loaded.peek(21), Ok(3));
+    assert_eq!(loaded.io_in.pop_back(), Some(6));
+    assert!(matches!(loaded.state, State::Halted));
+}
+
+#[test]
+fn loaded_core_dump_survives_reset() {
+    let mut cpu = quiet_cpu(&[1101, 1, 2, 5, 99, 0], 1 << 21);
+    cpu.run().unwrap();
+    // Far beyond what is loaded as the dense program image
+    let far = (1 << 20) + 12;
+    cpu.poke(far, 9).unwrap();
+    let dump = cpu.core_dump(None);
+
+    let mut loaded = Cpu::new();
+    loaded.load_core_dump(&dump);
+    loaded.poke(far, 4).unwrap();
+    loaded.poke(far + 1, 6).unwrap();
+    loaded.poke(5, 0).unwrap();
+    loaded.reset();
+    assert_eq!(loaded.peek(far), Ok(9));
+    assert_eq!(loaded.peek(far + 1), Ok(0));
+    assert_eq!(loaded.peek(5), Ok(3));
+
+    // Loading a program forgets them
+    loaded.load_program(&[99]).unwrap();
+    loaded.reset();
+    assert_eq!(loaded.peek(far), Ok(0));
+}
+
+#[test]
+fn failing_instruction_writes_a_core_dump() {
+    let path = std::env::temp_dir().join(format!("intcode-core-{}.txt", std::process::id()));
+    let mut cpu = Cpu::builder()
+        .program(&[1101, 1, 2, 5, 42])
+        .verbosity(Verbosity::Silent)
+        .core_dump(&path)
+        .build()
+        .unwrap();
+    assert!(cpu.run().is_err());
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let dump: CoreDump = text.parse().unwrap();
+    assert_eq!(dump.ip, 4);
+    assert_eq!(dump.get(5), 3);
+    assert!(dump.error.unwrap().starts_with("invalid opcode 42 at 4"));
+}
+
+#[test]
+fn core_dump_diff_lists_changed_cells() {
+    let mut cpu = quiet_cpu(&[1101, 1, 2, 5, 99, 0], 8);
+    let before = cpu.core_dump(None);
+    cpu.run().unwrap();
+    cpu.poke(0, 0).unwrap();
+    let after = cpu.core_dump(None);
+    assert_eq!(before.diff(&after), vec![(0, 1101, 0), (5, 0, 3)]);
+    assert_eq!(after.diff(&after), vec![]);
+}
+
+#[test]
+fn diverge_finds_where_runs_part_ways() {
+    // Outputs 1 if it reads 0, and 2 otherwise
+    let program = [3, 20, 1005, 20, 9, 104, 1, 99, 0, 104, 2, 99];
+    let build = |input: i64| {
+        Cpu::builder()
+            .program(&program)
+            .verbosity(Verbosity::Silent)
+            .input(&[input])
+            .build()
+            .unwrap()
+    };
+
+    let (mut a, mut b) = (build(0), build(5));
+    let divergence = diverge(&mut a, &mut b, 100).unwrap().unwrap();
+    // Reading different values isn't parting; jumping differently after is
+    assert_eq!(divergence.steps, 2);
+    assert_eq!((divergence.a.ip, divergence.b.ip), (5, 9));
+    assert_eq!(divergence.a.event, StepEvent::Output(1));
+    assert_eq!(divergence.b.event, StepEvent::Output(2));
+    assert_eq!(divergence.memory, vec![(20, 0, 5)]);
+
+    let (mut a, mut b) = (build(3), build(5));
+    assert_eq!(diverge(&mut a, &mut b, 100).unwrap(), None);
+}
+
+#[test]
+fn rewritten_instructions_are_decoded_again() {
+    let program = [
+        1101, 1, 1, 40, // m40 = 1 + 1
+        4, 40, // out m40
+        1005, 41, 24, // second time round, halt
+        1101, 0, 1102, 0, // turn the add into a multiply
+        1101, 0, 3, 1, // of 3 and 1
+        1101, 0, 1, 41, // note it is the second time
+        1105, 1, 0, // go round again
+        99,
+    ];
+    let mut cpu = quiet_cpu(&program, 64);
+    cpu.run().unwrap();
+    assert_eq!(cpu.drain_values(), [2, 3]);
+
+    // Poking between runs counts too
+    cpu.reset();
+    cpu.poke(0, 1102).unwrap();
+    cpu.run().unwrap();
+    assert_eq!(cpu.drain_values(), [1, 3]);
+}
+
+#[test]
+fn retired_instructions_outlast_a_reset() {
+    // An add and an output, then halt
+    let mut cpu = quiet_cpu(&[1101, 1, 2, 7, 104, 3, 99, 0], 8);
+    cpu.run().unwrap();
+    assert_eq!(cpu.retired(), 3);
+
+    // Waiting for input doesn't count
+    cpu.load_program(&[3, 3, 99, 0]).unwrap();
+    cpu.run().unwrap();
+    assert_eq!(cpu.retired(), 3);
+    cpu.reset();
+    cpu.io_in.push_front(5);
+    cpu.run().unwrap();
+    assert_eq!(cpu.retired(), 5);
+}
+
+// A writer whose contents can still be read once a logger owns it
+#[derive(Clone, Default)]
+struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+impl std::io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn ansi_lines_are_written_out_when_a_run_returns() {
+    let out = SharedBuf::default();
+    let mut cpu = Cpu::builder()
+        .program(&[3, 9, 4, 9, 3, 9, 99, 0, 0, 0])
+        .verbosity(Verbosity::Trace)
+        .logger(Ansi::with_writer(out.clone()))
+        .input(&[5])
+        .build()
+        .unwrap();
+
+    assert_eq!(cpu.run(), Ok(RunResult::NeedsInput));
+    let text = String::from_utf8(out.0.borrow().clone()).unwrap();
+    // Three traced instructions, the input, the output and the wait
+    assert_eq!(text.lines().count(), 6);
+    assert!(text.contains("INPUT  <\x1b[m 5"));
+    assert!(text.contains("Waiting for IO in"));
+
+    cpu.io_in.push_front(6);
+    assert_eq!(cpu.run(), Ok(RunResult::Halted));
+    let text = String::from_utf8(out.0.borrow().clone()).unwrap();
+    assert!(text.ends_with("Halting...\x1b[m\n"));
+}
+
+#[test]
+fn decode_cache_matches_decoding_every_step() {
+    // Outputs 1, then rewrites its own output instruction from `104,1` to `4,3` and
+    // runs it again, outputting cell 3
+    let program = [
+        104, 1, // out 1
+        1101, 4, 0, 0, // [0] = 4
+        1101, 3, 0, 1, // [1] = 3
+        1005, 30, 21, // second time round, halt
+        1101, 1, 0, 30, // [30] = 1
+        1105, 1, 0, // back to the start
+        0, 99,
+    ];
+    let build = |decode_cache: bool| {
+        Cpu::builder()
+            .program(&program)
+            .verbosity(Verbosity::Silent)
+            .decode_cache(decode_cache)
+            .build()
+            .unwrap()
+    };
+
+    let mut cached = build(true);
+    let mut uncached = build(false);
+    assert!(cached.decode_cache());
+    assert!(!uncached.decode_cache());
+    assert_eq!(diverge(&mut cached, &mut uncached, 1000), Ok(None));
+
+    for decode_cache in [true, false] {
+        let mut cpu = build(decode_cache);
+        cpu.run().unwrap();
+        assert_eq!(cpu.drain_values(), [1, 4]);
+    }
+}
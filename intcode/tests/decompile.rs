@@ -0,0 +1,41 @@
+use intcode::{Cpu, decompile};
+
+fn pseudocode(program: &[i64]) -> String {
+    let mut cpu = Cpu::with_memory_limit(program.len());
+    cpu.load_program(program).unwrap();
+    decompile(&cpu.disassemble(0, program.len()))
+}
+
+#[test]
+fn jumps_over_a_block_become_if_else() {
+    // Outputs 0 for an input of 0, and 1 otherwise
+    let program = [3, 100, 1005, 100, 10, 104, 0, 1105, 1, 12, 104, 1, 99];
+    assert_eq!(
+        pseudocode(&program),
+        "m[100] = input()\n\
+         if m[100] == 0 {\n    output(0)\n} else {\n    output(1)\n}\n\
+         halt\n"
+    );
+}
+
+#[test]
+fn jumps_back_become_loops() {
+    let forever = [104, 1, 1105, 1, 0];
+    assert_eq!(pseudocode(&forever), "loop {\n    output(1)\n}\n");
+
+    // Counts m[9] down to 0
+    let countdown = [101, -1, 9, 9, 1005, 9, 0, 99, 0, 42];
+    assert_eq!(
+        pseudocode(&countdown),
+        "do {\n    m[9] = -1 + m[9]\n} while m[9] != 0\nhalt\ndata 0\ndata 42\n"
+    );
+}
+
+#[test]
+fn other_jumps_stay_as_gotos() {
+    let program = [1105, 1, 5, 104, 7, 99];
+    assert_eq!(pseudocode(&program), "goto L0\noutput(7)\nL0:\nhalt\n");
+
+    // A computed jump, as used to return from a call
+    assert_eq!(pseudocode(&[2106, 0, -1]), "goto *m[bp-1]\n");
+}
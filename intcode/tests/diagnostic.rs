@@ -0,0 +1,48 @@
+use intcode::{Cpu, Op, Verbosity, run_diagnostic};
+
+// Reads the system ID, then runs four tests of which the second (an add) and the
+// fourth (a less-than) output non-zero, and finishes with the diagnostic code
+const PROGRAM: [i64; 25] = [
+    3, 50, // in [50]
+    104, 0, // test 0 passes
+    1101, 2, 3, 51, // [51] = 2 + 3
+    4, 51, // test 1 fails with 5
+    1102, 0, 5, 52, // [52] = 0 * 5
+    4, 52, // test 2 passes
+    1107, 1, 2, 53, // [53] = 1 < 2
+    4, 53, // test 3 fails with 1
+    104, 1234, // diagnostic code
+    99,
+];
+
+fn quiet_cpu() -> Cpu {
+    Cpu::builder().verbosity(Verbosity::Silent).build().unwrap()
+}
+
+#[test]
+fn failing_tests_report_their_index_and_opcode() {
+    let mut cpu = quiet_cpu();
+    let report = run_diagnostic(&mut cpu, &PROGRAM, 1).unwrap();
+
+    assert_eq!(report.outputs, vec![0, 5, 0, 1, 1234]);
+    assert_eq!(report.code(), Some(1234));
+    assert!(!report.passed());
+
+    let failures: Vec<(usize, i64, Vec<Op>)> = report
+        .failures
+        .iter()
+        .map(|failure| (failure.index, failure.value, failure.ops.clone()))
+        .collect();
+    assert_eq!(failures, vec![(1, 5, vec![Op::Add]), (3, 1, vec![Op::Lt])]);
+}
+
+#[test]
+fn diagnostic_code_is_not_a_test() {
+    // A single passing test followed by a non-zero code
+    let program = [3, 9, 104, 0, 4, 9, 99];
+    let mut cpu = quiet_cpu();
+    let report = run_diagnostic(&mut cpu, &program, 5).unwrap();
+
+    assert!(report.passed());
+    assert_eq!(report.code(), Some(5));
+}
@@ -0,0 +1,34 @@
+use intcode::Memory;
+
+#[test]
+fn unwritten_cells_read_zero() {
+    let memory = Memory::new();
+
+    assert_eq!(memory.get(0), Some(0));
+    assert_eq!(memory.get(1 << 40), Some(0));
+    assert_eq!(memory[12345], 0);
+    assert_eq!(memory.allocated(), 0);
+}
+
+#[test]
+fn writes_grow_memory() {
+    let mut memory = Memory::new();
+
+    memory[10] = 3;
+    *memory.get_mut(1 << 40).unwrap() = 4;
+
+    assert_eq!(memory.get(10), Some(3));
+    assert_eq!(memory[1 << 40], 4);
+    // The far cell doesn't allocate everything below it
+    assert_eq!(memory.allocated(), 12);
+}
+
+#[test]
+fn limited_memory_stops_at_the_limit() {
+    let mut memory = Memory::with_limit(8);
+
+    memory[7] = 1;
+    assert_eq!(memory.get(7), Some(1));
+    assert_eq!(memory.get(8), None);
+    assert!(memory.get_mut(8).is_none());
+}
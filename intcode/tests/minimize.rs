@@ -0,0 +1,32 @@
+use intcode::minimize;
+
+fn has_7_and_9(program: &[i64]) -> bool {
+    program.contains(&7) && program.contains(&9)
+}
+
+#[test]
+fn result_is_one_minimal() {
+    let program = [3, 7, 1, 4, 4, 1, 0, 8, 9, 9, 2, 7, 5, 6];
+    let minimal = minimize(&program, has_7_and_9);
+
+    assert!(has_7_and_9(&minimal));
+    assert_eq!(minimal.len(), 2);
+    // No single cell can be removed or zeroed without losing the failure
+    for i in 0..minimal.len() {
+        let mut removed = minimal.clone();
+        removed.remove(i);
+        assert!(!has_7_and_9(&removed), "{minimal:?} without cell {i}");
+
+        let mut zeroed = minimal.clone();
+        zeroed[i] = 0;
+        assert!(!has_7_and_9(&zeroed), "{minimal:?} with cell {i} zeroed");
+    }
+}
+
+#[test]
+fn cells_that_cannot_go_are_zeroed() {
+    // Only the length matters, so everything left is zeroed
+    let minimal = minimize(&[5, 6, 7, 8, 9], |program| program.len() >= 3);
+
+    assert_eq!(minimal, vec![0, 0, 0]);
+}
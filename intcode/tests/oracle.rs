@@ -0,0 +1,86 @@
+use grid::Point2;
+use intcode::{GridOracle, IntcodeError, IntcodeOracle, Memo, MockOracle};
+
+#[test]
+fn intcode_oracle_runs_program_per_probe() {
+    // Reads x and y and outputs x + y
+    let program = [3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0];
+    let mut oracle = IntcodeOracle::new(&program);
+
+    assert_eq!(oracle.probe(Point2::new(2, 3)), Ok(5));
+    assert_eq!(oracle.probe(Point2::new(-4, 10)), Ok(6));
+    assert_eq!(oracle.runs, 2);
+}
+
+#[test]
+fn memo_probes_each_cell_once() {
+    let mut memo = Memo::new(MockOracle::new(|pos: Point2| pos.x * pos.y));
+    let points = [Point2::new(2, 3), Point2::new(4, 5), Point2::new(2, 3)];
+
+    assert_eq!(memo.probe_all(&points), Ok(vec![6, 20, 6]));
+    assert_eq!(memo.probe(Point2::new(4, 5)), Ok(20));
+    assert_eq!(memo.inner.probes, 2);
+    assert_eq!((memo.hits, memo.misses), (2, 2));
+    assert_eq!(memo.cache().len(), 2);
+}
+
+#[test]
+fn memo_never_probes_known_cells() {
+    let mut memo = Memo::new(MockOracle::new(|_| -> i64 {
+        panic!("Probed a known cell")
+    }));
+    memo.insert(Point2::ORIGIN, 3);
+
+    assert!(memo.is_cached(Point2::ORIGIN));
+    assert_eq!(memo.probe(Point2::ORIGIN), Ok(3));
+}
+
+#[test]
+fn memo_does_not_cache_errors() {
+    // Halts without output
+    let mut memo = Memo::new(IntcodeOracle::new(&[99]));
+
+    assert_eq!(
+        memo.probe(Point2::ORIGIN),
+        Err(IntcodeError::MissingOutput { ip: 0 })
+    );
+    assert!(!memo.is_cached(Point2::ORIGIN));
+}
+
+// A cone from the origin between the lines y = x and 2y = 3x
+fn synthetic_beam(pos: Point2) -> i64 {
+    (pos.y <= pos.x && 2 * pos.x <= 3 * pos.y) as i64
+}
+
+#[test]
+fn scan_row_matches_probing_every_cell() {
+    let mut oracle = MockOracle::new(synthetic_beam);
+    for y in 0..40 {
+        let points: Vec<Point2> = (0..60).map(|x| Point2::new(x, y)).collect();
+        let cells = oracle.probe_all(&points).unwrap();
+        let inside: Vec<i64> = (0..60).filter(|x| cells[*x as usize] == 1).collect();
+        let expected = inside
+            .first()
+            .map(|start| *start..inside.last().unwrap() + 1);
+
+        assert_eq!(oracle.scan_row(y, 0..60), Ok(expected), "row {y}");
+    }
+}
+
+#[test]
+fn scan_row_stops_past_the_right_edge() {
+    let mut oracle = MockOracle::new(synthetic_beam);
+
+    // Row 20 is covered from 20 to 30
+    assert_eq!(oracle.scan_row(20, 15..100), Ok(Some(20..31)));
+    assert_eq!(oracle.probes, 17);
+}
+
+#[test]
+fn scan_row_clips_to_the_range() {
+    let mut oracle = MockOracle::new(synthetic_beam);
+
+    assert_eq!(oracle.scan_row(20, 25..28), Ok(Some(25..28)));
+    assert_eq!(oracle.scan_row(20, 0..10), Ok(None));
+    assert_eq!(oracle.scan_row(20, 40..50), Ok(None));
+}
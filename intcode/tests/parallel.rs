@@ -0,0 +1,34 @@
+use intcode::{IntcodeError, par_map, run_parallel};
+
+// Reads two inputs and outputs their product
+const MULTIPLY: [i64; 9] = [3, 0, 3, 1, 2, 0, 1, 0, 4];
+
+#[test]
+fn runs_come_back_in_order() {
+    let program = [&MULTIPLY[..], &[0, 99]].concat();
+    let inputs: Vec<Vec<i64>> = (0..50).map(|i| vec![i, i + 1]).collect();
+    let outputs = run_parallel(&program, &inputs).unwrap();
+
+    let expected: Vec<Vec<i64>> = (0..50).map(|i| vec![i * (i + 1)]).collect();
+    assert_eq!(outputs, expected);
+}
+
+#[test]
+fn short_of_input_keeps_what_was_output() {
+    // Outputs 7, then waits for input
+    let outputs = run_parallel(&[104, 7, 3, 0, 99], &[vec![], vec![1]]).unwrap();
+    assert_eq!(outputs, [vec![7], vec![7]]);
+}
+
+#[test]
+fn first_error_is_returned() {
+    let items: Vec<usize> = (0..20).collect();
+    let result = par_map(&items, |&i| {
+        if i >= 5 {
+            Err(IntcodeError::MissingOutput { ip: i })
+        } else {
+            Ok(i)
+        }
+    });
+    assert_eq!(result, Err(IntcodeError::MissingOutput { ip: 5 }));
+}
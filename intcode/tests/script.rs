@@ -0,0 +1,42 @@
+use intcode::script::run_script;
+
+#[test]
+fn script_drives_a_machine() {
+    // Doubles each input
+    let program = [3, 20, 1002, 20, 2, 20, 4, 20, 1105, 1, 0];
+    let source = r#"
+        let m = machine(program);
+        let total = 0;
+        for n in [1, 2, 3] {
+            m.input(n);
+            m.run();
+            total += m.output();
+        }
+        total
+    "#;
+    assert_eq!(run_script(source, &program).unwrap().as_int(), Ok(12));
+}
+
+#[test]
+fn script_sees_text_and_memory() {
+    // Prints "hi\n" then halts
+    let program = [104, 104, 104, 105, 104, 10, 99];
+    let source = r#"
+        let m = machine(program);
+        m.poke(1, 72);
+        m.run();
+        [m.read_text(), m.halted, m.peek(1)]
+    "#;
+    let result = run_script(source, &program).unwrap().into_array().unwrap();
+    assert_eq!(result[0].clone().into_string().unwrap(), "Hi\n");
+    assert_eq!(result[1].as_bool(), Ok(true));
+    assert_eq!(result[2].as_int(), Ok(72));
+}
+
+#[test]
+fn interpreter_errors_stop_the_script() {
+    let program = [42];
+    let source = "let m = machine(program); m.run(); 1";
+    let err = run_script(source, &program).unwrap_err();
+    assert!(err.to_string().contains("invalid opcode 42 at 0"));
+}
@@ -0,0 +1,42 @@
+use intcode::{Cpu, Symbols};
+
+// Counts mem[9] up from 0 to 3, jumping back to 0 while below
+const COUNT_UP: [i64; 11] = [1001, 9, 1, 9, 1007, 9, 3, 10, 1005, 10, 0];
+
+#[test]
+fn symbols_parse_with_comments_and_round_trip() {
+    let text = "# counters\n0=top\n 9 = count # the loop variable\n\n10=below\n";
+    let symbols: Symbols = text.parse().unwrap();
+    assert_eq!(symbols.name(9), Some("count"));
+    assert_eq!(symbols.addr("below"), Some(10));
+    assert_eq!(symbols.describe(4), "4");
+    assert_eq!(symbols.to_string(), "0=top\n9=count\n10=below\n");
+    assert_eq!(symbols.to_string().parse::<Symbols>(), Ok(symbols));
+}
+
+#[test]
+fn symbols_reject_bad_lines() {
+    assert_eq!(
+        "0=top\nloop".parse::<Symbols>(),
+        Err("line 2: expected `addr=name`".to_string())
+    );
+    assert!("x=top".parse::<Symbols>().is_err());
+    assert!("1=2nd".parse::<Symbols>().is_err());
+    assert!("1=two words".parse::<Symbols>().is_err());
+}
+
+#[test]
+fn disassembly_shows_names() {
+    let mut cpu = Cpu::new();
+    cpu.load_program(&COUNT_UP).unwrap();
+    let symbols: Symbols = "0=top\n9=count".parse().unwrap();
+    let lines: Vec<String> = cpu
+        .disassemble(0, 3)
+        .iter()
+        .map(|line| line.display_with(&symbols))
+        .collect();
+    assert_eq!(
+        lines,
+        ["Add @count #1 @count", "Lt @count #3 @10", "Jnz @10 #top",]
+    );
+}
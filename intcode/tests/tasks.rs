@@ -0,0 +1,40 @@
+use intcode::{Cpu, Verbosity, tasks::run_async};
+use tokio::{sync::mpsc, task::LocalSet};
+
+// Doubles each input until it reads 0
+const DOUBLER: [i64; 16] = [
+    3, 15, 1006, 15, 14, 1002, 15, 2, 15, 4, 15, 1105, 1, 0, 99, 0,
+];
+
+#[tokio::test(flavor = "current_thread")]
+async fn machines_run_as_tasks_over_channels() {
+    let local = LocalSet::new();
+    let (first_tx, first_rx) = mpsc::channel(1);
+    let (middle_tx, middle_rx) = mpsc::channel(1);
+    let (last_tx, mut last_rx) = mpsc::channel(1);
+
+    // Two doublers in a row, so each value comes out four times as big
+    for (mut rx, tx) in [(first_rx, middle_tx), (middle_rx, last_tx)] {
+        local.spawn_local(async move {
+            let mut cpu = Cpu::builder()
+                .program(&DOUBLER)
+                .verbosity(Verbosity::Silent)
+                .build()
+                .unwrap();
+            run_async(&mut cpu, &mut rx, &tx).await
+        });
+    }
+    let outputs = local
+        .run_until(async move {
+            for value in [1, 2, 3, 0] {
+                first_tx.send(value).await.unwrap();
+            }
+            let mut outputs = Vec::new();
+            while let Some(value) = last_rx.recv().await {
+                outputs.push(value);
+            }
+            outputs
+        })
+        .await;
+    assert_eq!(outputs, [4, 8, 12]);
+}
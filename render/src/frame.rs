@@ -0,0 +1,62 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Cell {
+    pub ch: char,
+    pub colour: Option<&'static str>,
+}
+
+/// One screenful of text, built up row by row.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Frame {
+    pub(crate) rows: Vec<Vec<Cell>>,
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_canvas(canvas: &[Vec<char>], palette: &[(char, &'static str)]) -> Self {
+        let mut frame = Self::new();
+        frame.push_canvas(canvas, palette);
+        frame
+    }
+
+    /// Adds the rows of a rendered canvas, colouring each char listed in `palette` with
+    /// its escape sequence, like `grid::print_canvas` does.
+    pub fn push_canvas(&mut self, canvas: &[Vec<char>], palette: &[(char, &'static str)]) {
+        for row in canvas {
+            self.rows.push(
+                row.iter()
+                    .map(|ch| Cell {
+                        ch: *ch,
+                        colour: palette.iter().find(|(p, _)| p == ch).map(|(_, c)| *c),
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    /// Adds a row of plain text, such as a score under the canvas.
+    pub fn push_line(&mut self, text: &str) {
+        self.rows
+            .push(text.chars().map(|ch| Cell { ch, colour: None }).collect());
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    /// The frame's characters without colours, one line per row.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for row in &self.rows {
+            text.extend(row.iter().map(|cell| cell.ch));
+            text.push('\n');
+        }
+        text
+    }
+}
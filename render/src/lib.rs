@@ -0,0 +1,14 @@
+//! Flicker-free terminal animation for the puzzles that draw their progress.
+//!
+//! Build a [`Frame`] each step and hand it to [`Renderer::present`], which moves the
+//! cursor to the cells that changed instead of clearing and reprinting the screen. To
+//! keep drawing out of the solver, capture frames with a [`Recorder`] and replay them
+//! afterwards.
+
+mod frame;
+mod recorder;
+mod renderer;
+
+pub use frame::Frame;
+pub use recorder::Recorder;
+pub use renderer::Renderer;
@@ -0,0 +1,60 @@
+use std::{fs, io::Write, thread::sleep, time::Duration};
+
+use crate::{Frame, Renderer};
+
+/// Frames captured while a puzzle is being solved, to be replayed or exported once
+/// the answer is known.
+#[derive(Clone, Default)]
+pub struct Recorder {
+    frames: Vec<Frame>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `frame`, unless it is the same as the last one.
+    pub fn capture(&mut self, frame: Frame) {
+        if self.frames.last() != Some(&frame) {
+            self.frames.push(frame);
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Presents every frame in turn, waiting `delay` after each.
+    pub fn replay<W: Write>(&self, renderer: &mut Renderer<W>, delay: Duration) {
+        for frame in &self.frames {
+            renderer.present(frame);
+            sleep(delay);
+        }
+    }
+
+    /// Every frame as plain text under a `# frame N` header.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (i, frame) in self.frames.iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            text.push_str(&format!("# frame {}\n", i + 1));
+            text.push_str(&frame.to_text());
+        }
+        text
+    }
+
+    pub fn write_text(&self, path: &str) {
+        fs::write(path, self.to_text()).expect("Failed to write recording");
+    }
+}
@@ -0,0 +1,88 @@
+use std::io::{Stdout, Write, stdout};
+
+use crate::frame::{Cell, Frame};
+
+/// Draws [`Frame`]s to a terminal, rewriting only the cells that changed since the last
+/// one.
+pub struct Renderer<W: Write> {
+    out: W,
+    shown: Option<Frame>,
+}
+
+impl Renderer<Stdout> {
+    pub fn stdout() -> Self {
+        Self::new(stdout())
+    }
+}
+
+impl<W: Write> Renderer<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, shown: None }
+    }
+
+    /// Shows `frame`. The first frame clears the screen; after that only changed cells
+    /// are written. The cursor is left on the line below the frame, so ordinary
+    /// printing carries on underneath it.
+    pub fn present(&mut self, frame: &Frame) {
+        let shown = self.shown.take().unwrap_or_else(|| {
+            self.write("\x1b[2J");
+            Frame::new()
+        });
+
+        for y in 0..frame.height().max(shown.height()) {
+            let new = frame.rows.get(y).map_or(&[][..], |row| row.as_slice());
+            let old = shown.rows.get(y).map_or(&[][..], |row| row.as_slice());
+            self.draw_row(y, new, old);
+        }
+        self.move_to(frame.height(), 0);
+        self.out.flush().expect("Failed to flush terminal");
+
+        self.shown = Some(frame.clone());
+    }
+
+    /// Forgets what is on screen, so the next frame is drawn from scratch.
+    pub fn reset(&mut self) {
+        self.shown = None;
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn draw_row(&mut self, y: usize, new: &[Cell], old: &[Cell]) {
+        // Where the cursor is after the last cell written, if on this row
+        let mut cursor: Option<usize> = None;
+        for (x, cell) in new.iter().enumerate() {
+            if old.get(x) == Some(cell) {
+                continue;
+            }
+            if cursor != Some(x) {
+                self.move_to(y, x);
+            }
+            match cell.colour {
+                Some(colour) => self.write(&format!("{colour}{}\x1b[m", cell.ch)),
+                None => self.write(&cell.ch.to_string()),
+            }
+            cursor = Some(x + 1);
+        }
+        if old.len() > new.len() {
+            self.move_to(y, new.len());
+            self.write("\x1b[K");
+        }
+    }
+
+    // Rows and columns count from 0 here, but from 1 on the terminal
+    fn move_to(&mut self, y: usize, x: usize) {
+        self.write(&format!("\x1b[{};{}H", y + 1, x + 1));
+    }
+
+    fn write(&mut self, text: &str) {
+        self.out
+            .write_all(text.as_bytes())
+            .expect("Failed to write to terminal");
+    }
+}
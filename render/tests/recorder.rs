@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use render::{Frame, Recorder, Renderer};
+
+fn frame(rows: &[&str]) -> Frame {
+    let canvas: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+    Frame::from_canvas(&canvas, &[('#', "\x1b[34m")])
+}
+
+#[test]
+fn capture_skips_repeated_frames() {
+    let mut recorder = Recorder::new();
+    recorder.capture(frame(&["#."]));
+    recorder.capture(frame(&["#."]));
+    recorder.capture(frame(&["##"]));
+
+    assert_eq!(recorder.len(), 2);
+    assert_eq!(recorder.frames()[1], frame(&["##"]));
+}
+
+#[test]
+fn text_export() {
+    let mut recorder = Recorder::new();
+    recorder.capture(frame(&["#.", ".#"]));
+    let mut score = frame(&["##"]);
+    score.push_line("Score: 3");
+    recorder.capture(score);
+
+    assert_eq!(
+        recorder.to_text(),
+        "# frame 1\n#.\n.#\n\n# frame 2\n##\nScore: 3\n"
+    );
+}
+
+#[test]
+fn replay_presents_every_frame() {
+    let mut recorder = Recorder::new();
+    recorder.capture(frame(&[".."]));
+    recorder.capture(frame(&[".#"]));
+
+    let mut renderer = Renderer::new(Vec::new());
+    recorder.replay(&mut renderer, Duration::ZERO);
+    let out = String::from_utf8(renderer.into_inner()).unwrap();
+
+    assert_eq!(
+        out,
+        "\x1b[2J\x1b[1;1H..\x1b[2;1H\x1b[1;2H\x1b[34m#\x1b[m\x1b[2;1H"
+    );
+}
@@ -0,0 +1,66 @@
+use render::{Frame, Renderer};
+
+// What each present wrote
+fn present_all(frames: &[Frame]) -> Vec<String> {
+    let mut renderer = Renderer::new(Vec::new());
+    frames
+        .iter()
+        .map(|frame| {
+            renderer.present(frame);
+            let out = renderer.get_mut();
+            let text = String::from_utf8(out.clone()).unwrap();
+            out.clear();
+            text
+        })
+        .collect()
+}
+
+fn canvas(rows: &[&str]) -> Vec<Vec<char>> {
+    rows.iter().map(|row| row.chars().collect()).collect()
+}
+
+#[test]
+fn first_frame_clears_and_draws_everything() {
+    let frame = Frame::from_canvas(&canvas(&["#.", ".#"]), &[('#', "\x1b[34m")]);
+    let out = present_all(&[frame]);
+
+    assert_eq!(
+        out[0],
+        "\x1b[2J\x1b[1;1H\x1b[34m#\x1b[m.\x1b[2;1H.\x1b[34m#\x1b[m\x1b[3;1H"
+    );
+}
+
+#[test]
+fn later_frames_only_write_changes() {
+    let first = Frame::from_canvas(&canvas(&["....", "...."]), &[]);
+    let second = Frame::from_canvas(&canvas(&["....", ".OO."]), &[]);
+    let out = present_all(&[first.clone(), second, first.clone(), first]);
+
+    // One cursor move for a run of changed cells
+    assert_eq!(out[1], "\x1b[2;2HOO\x1b[3;1H");
+    assert_eq!(out[2], "\x1b[2;2H..\x1b[3;1H");
+    // Nothing changed, so only the cursor is parked under the frame
+    assert_eq!(out[3], "\x1b[3;1H");
+}
+
+#[test]
+fn shrinking_frames_clear_leftovers() {
+    let mut first = Frame::from_canvas(&canvas(&["###"]), &[]);
+    first.push_line("Score: 100");
+    let second = Frame::from_canvas(&canvas(&["#"]), &[]);
+    let out = present_all(&[first, second]);
+
+    assert_eq!(out[1], "\x1b[1;2H\x1b[K\x1b[2;1H\x1b[K\x1b[2;1H");
+}
+
+#[test]
+fn reset_redraws_from_scratch() {
+    let frame = Frame::from_canvas(&canvas(&["#"]), &[]);
+    let mut renderer = Renderer::new(Vec::new());
+    renderer.present(&frame);
+    renderer.get_mut().clear();
+    renderer.reset();
+    renderer.present(&frame);
+
+    assert_eq!(renderer.into_inner(), b"\x1b[2J\x1b[1;1H#\x1b[2;1H");
+}
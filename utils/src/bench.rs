@@ -0,0 +1,126 @@
+use std::{
+    collections::HashMap,
+    fs,
+    time::{Duration, Instant},
+};
+
+pub struct Stats {
+    pub samples: Vec<Duration>,
+    pub mean: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub stddev: Duration,
+    /// Indices into `samples` more than 3 MADs above the median
+    pub outliers: Vec<usize>,
+}
+
+impl Stats {
+    /// A slow first run is usually warmup (page faults zeroing the Cpu's memory, cold
+    /// caches) rather than something the solver did.
+    pub fn warmup_suspected(&self) -> bool {
+        self.outliers.first() == Some(&0)
+    }
+
+    pub fn print(&self, name: &str) {
+        println!("\x1b[34;1m{name}\x1b[m  ({} runs)", self.samples.len());
+        println!("  median : {:?}", self.median);
+        println!("  mean   : {:?}", self.mean);
+        println!("  p95    : {:?}", self.p95);
+        println!("  stddev : {:?}", self.stddev);
+        if !self.outliers.is_empty() {
+            println!(
+                "  \x1b[33moutliers: {:?}\x1b[m",
+                self.outliers
+                    .iter()
+                    .map(|i| (i, self.samples[*i]))
+                    .collect::<Vec<_>>()
+            );
+        }
+        if self.warmup_suspected() {
+            println!("  \x1b[33mfirst run is an outlier: likely warmup\x1b[m");
+        }
+    }
+}
+
+pub fn time_runs<F>(runs: usize, mut f: F) -> Vec<Duration>
+where
+    F: FnMut(),
+{
+    let mut samples: Vec<Duration> = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let start = Instant::now();
+        f();
+        samples.push(start.elapsed());
+    }
+    samples
+}
+
+fn percentile(sorted: &[Duration], pct: f64) -> Duration {
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub fn summarise(samples: Vec<Duration>) -> Stats {
+    assert!(!samples.is_empty(), "No samples to summarise");
+    let mut sorted = samples.clone();
+    sorted.sort();
+
+    let n = samples.len() as f64;
+    let nanos: Vec<f64> = samples.iter().map(|s| s.as_nanos() as f64).collect();
+    let mean = nanos.iter().sum::<f64>() / n;
+    let variance = nanos.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    let median = percentile(&sorted, 50.0);
+
+    let med = median.as_nanos() as f64;
+    let mut deviations: Vec<f64> = nanos.iter().map(|x| (x - med).abs()).collect();
+    deviations.sort_by(f64::total_cmp);
+    let mad = deviations[deviations.len() / 2];
+    let outliers = nanos
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| mad > 0.0 && **x - med > 3.0 * mad)
+        .map(|(i, _)| i)
+        .collect();
+
+    Stats {
+        mean: Duration::from_nanos(mean as u64),
+        median,
+        p95: percentile(&sorted, 95.0),
+        stddev: Duration::from_nanos(variance.sqrt() as u64),
+        outliers,
+        samples,
+    }
+}
+
+/// Baseline files hold one `name median_ns` pair per line.
+pub fn read_baseline(path: &str) -> HashMap<String, Duration> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    let mut baseline = HashMap::new();
+    for line in contents.lines() {
+        let Some((name, nanos)) = line.rsplit_once(' ') else {
+            continue;
+        };
+        if let Ok(nanos) = nanos.trim().parse() {
+            baseline.insert(name.to_string(), Duration::from_nanos(nanos));
+        }
+    }
+    baseline
+}
+
+pub fn write_baseline(path: &str, baseline: &HashMap<String, Duration>) {
+    let mut names: Vec<&String> = baseline.keys().collect();
+    names.sort();
+    let contents: String = names
+        .into_iter()
+        .map(|name| format!("{name} {}\n", baseline[name].as_nanos()))
+        .collect();
+    fs::write(path, contents).expect("Failed to write baseline file");
+}
+
+/// Percentage change of the median against the baseline; positive means slower.
+pub fn regression_pct(stats: &Stats, baseline: Duration) -> f64 {
+    let base = baseline.as_nanos() as f64;
+    (stats.median.as_nanos() as f64 - base) / base * 100.0
+}
@@ -0,0 +1,112 @@
+use std::{
+    env,
+    path::Path,
+    process::{self, Command, Stdio},
+};
+
+use utils::bench::{read_baseline, regression_pct, summarise, time_runs, write_baseline};
+
+const DEFAULT_RUNS: usize = 10;
+const DEFAULT_THRESHOLD: f64 = 10.0;
+
+fn usage() {
+    println!(
+        "usage: bench <binary> [input] [--runs K] [--baseline FILE] [--threshold PCT] [--save]"
+    );
+}
+
+// Day binaries are looked up in target/release first so `bench day19 input.txt` works
+// after a `cargo build --release`.
+fn resolve_binary(name: &str) -> String {
+    if Path::new(name).is_file() {
+        return name.to_string();
+    }
+    let release = format!("target/release/{name}");
+    if Path::new(&release).is_file() {
+        return release;
+    }
+    println!("\x1b[31;1mNo binary found for {name}\x1b[m");
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut positional: Vec<&String> = Vec::new();
+    let mut runs = DEFAULT_RUNS;
+    let mut baseline_path: Option<&String> = None;
+    let mut threshold = DEFAULT_THRESHOLD;
+    let mut save = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--runs" => {
+                runs = iter
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--runs needs a number")
+            }
+            "--baseline" => baseline_path = Some(iter.next().expect("--baseline needs a file")),
+            "--threshold" => {
+                threshold = iter
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--threshold needs a percentage")
+            }
+            "--save" => save = true,
+            _ => positional.push(arg),
+        }
+    }
+    if positional.is_empty() || runs == 0 {
+        usage();
+        return;
+    }
+
+    let binary = resolve_binary(positional[0]);
+    let input = positional.get(1);
+    let name = match input {
+        Some(input) => format!("{}:{input}", positional[0]),
+        None => positional[0].to_string(),
+    };
+
+    let samples = time_runs(runs, || {
+        let mut cmd = Command::new(&binary);
+        if let Some(input) = input {
+            cmd.arg(input);
+        }
+        let status = cmd
+            .stdout(Stdio::null())
+            .stdin(Stdio::null())
+            .status()
+            .expect("Failed to launch binary");
+        if !status.success() {
+            println!("\x1b[31;1m{name} exited with {status}\x1b[m");
+            process::exit(2);
+        }
+    });
+    let stats = summarise(samples);
+    stats.print(&name);
+
+    let Some(baseline_path) = baseline_path else {
+        return;
+    };
+    let mut baseline = read_baseline(baseline_path);
+    if save {
+        baseline.insert(name, stats.median);
+        write_baseline(baseline_path, &baseline);
+        println!("baseline saved to {baseline_path}");
+        return;
+    }
+    let Some(base) = baseline.get(&name) else {
+        println!("\x1b[33mNo baseline recorded for {name}\x1b[m");
+        return;
+    };
+    let change = regression_pct(&stats, *base);
+    if change > threshold {
+        println!(
+            "\x1b[31;1mFAIL\x1b[m median {change:+.1}% vs baseline {base:?} (threshold {threshold}%)"
+        );
+        process::exit(1);
+    }
+    println!("\x1b[32;1mPASS\x1b[m median {change:+.1}% vs baseline {base:?}");
+}
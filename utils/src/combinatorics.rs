@@ -0,0 +1,111 @@
+/// Every ordering of `items`, in lexicographic order of their positions in `items`.
+pub fn permutations<T: Clone>(items: &[T]) -> Permutations<T> {
+    Permutations {
+        items: items.to_vec(),
+        indices: (0..items.len()).collect(),
+        done: false,
+    }
+}
+
+pub struct Permutations<T> {
+    items: Vec<T>,
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+        let current = self
+            .indices
+            .iter()
+            .map(|i| self.items[*i].clone())
+            .collect();
+
+        // Step to the next permutation: find the last ascent, swap in the next larger
+        // value from the tail, then reverse the tail.
+        match self.indices.windows(2).rposition(|w| w[0] < w[1]) {
+            Some(pivot) => {
+                let swap = self
+                    .indices
+                    .iter()
+                    .rposition(|i| *i > self.indices[pivot])
+                    .expect("Ascent guarantees a larger element");
+                self.indices.swap(pivot, swap);
+                self.indices[pivot + 1..].reverse();
+            }
+            None => self.done = true,
+        }
+
+        Some(current)
+    }
+}
+
+/// Every way to choose `k` of `items`, keeping their original order.
+pub fn combinations<T: Clone>(items: &[T], k: usize) -> Combinations<T> {
+    Combinations {
+        items: items.to_vec(),
+        indices: (0..k).collect(),
+        done: k > items.len(),
+    }
+}
+
+pub struct Combinations<T> {
+    items: Vec<T>,
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl<T: Clone> Iterator for Combinations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+        let current = self
+            .indices
+            .iter()
+            .map(|i| self.items[*i].clone())
+            .collect();
+
+        let n = self.items.len();
+        let k = self.indices.len();
+        // Rightmost index that can still move right
+        match (0..k).rposition(|i| self.indices[i] < n - k + i) {
+            Some(i) => {
+                self.indices[i] += 1;
+                for j in i + 1..k {
+                    self.indices[j] = self.indices[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+
+        Some(current)
+    }
+}
+
+/// Bitmasks of every subset of `n` items in Gray code order, so consecutive masks differ
+/// by exactly one item. Starts from the empty set.
+pub fn gray_code(n: u32) -> impl Iterator<Item = u64> {
+    assert!(n < 64, "Too many items for a u64 mask");
+    (0..1u64 << n).map(|i| i ^ (i >> 1))
+}
+
+/// Every subset of `items` (the power set), from the empty set up to all of them.
+pub fn subsets<T: Clone>(items: &[T]) -> impl Iterator<Item = Vec<T>> + '_ {
+    assert!(items.len() < 64, "Too many items for a u64 mask");
+    (0..1u64 << items.len()).map(move |mask| {
+        items
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, item)| item.clone())
+            .collect()
+    })
+}
@@ -0,0 +1,99 @@
+use std::{error::Error, fmt, io, process};
+
+use crate::parse::ParseError;
+
+/// Anything that stops a day's solution, reported once by `main` rather than as a panic.
+/// Intcode errors are boxed so this crate doesn't depend on the interpreter; the
+/// intcode crate converts its own error type into `AocError::Intcode`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AocError {
+    /// Bad command line arguments
+    Usage(String),
+    /// The puzzle input couldn't be read or parsed
+    Input(ParseError),
+    /// The Intcode program failed
+    Intcode(Box<dyn Error + Send + Sync>),
+    /// The input was fine but the solution found nothing, or the program said something
+    /// the solution doesn't understand
+    Solver(String),
+    /// Reading from the terminal or writing an output file
+    Io { context: String, error: io::Error },
+}
+
+impl AocError {
+    pub fn solver(message: impl Into<String>) -> Self {
+        AocError::Solver(message.into())
+    }
+
+    pub fn io(context: impl Into<String>, error: io::Error) -> Self {
+        AocError::Io {
+            context: context.into(),
+            error,
+        }
+    }
+
+    /// The process exit code: 2 for usage errors as is customary, then one code per kind
+    /// of failure so scripts running every day can tell them apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AocError::Usage(_) => 2,
+            AocError::Input(_) => 3,
+            AocError::Intcode(_) => 4,
+            AocError::Solver(_) => 5,
+            AocError::Io { .. } => 6,
+        }
+    }
+
+    /// The error followed by each of its causes, one per line
+    pub fn report(&self) -> String {
+        let mut previous = self.to_string();
+        let mut text = format!("\x1b[31;1merror:\x1b[m {previous}");
+        let mut source = self.source();
+        while let Some(cause) = source {
+            let message = cause.to_string();
+            // Some errors already include their cause in their own message
+            if !previous.contains(&message) {
+                text.push_str(&format!("\n  \x1b[31mcaused by:\x1b[m {message}"));
+            }
+            previous = message;
+            source = cause.source();
+        }
+        text
+    }
+
+    /// Prints the report and exits with `exit_code`
+    pub fn exit(&self) -> ! {
+        println!("{}", self.report());
+        process::exit(self.exit_code())
+    }
+}
+
+impl fmt::Display for AocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AocError::Usage(message) => write!(f, "{message}"),
+            AocError::Input(_) => write!(f, "bad puzzle input"),
+            AocError::Intcode(_) => write!(f, "Intcode program failed"),
+            AocError::Solver(message) => write!(f, "{message}"),
+            AocError::Io { context, .. } => write!(f, "{context}"),
+        }
+    }
+}
+
+impl Error for AocError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AocError::Input(error) => Some(error),
+            AocError::Intcode(error) => Some(error.as_ref()),
+            AocError::Io { error, .. } => Some(error),
+            AocError::Usage(_) | AocError::Solver(_) => None,
+        }
+    }
+}
+
+impl From<ParseError> for AocError {
+    fn from(error: ParseError) -> Self {
+        AocError::Input(error)
+    }
+}
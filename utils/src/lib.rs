@@ -0,0 +1,9 @@
+pub mod bench;
+pub mod combinatorics;
+pub mod error;
+pub mod math;
+pub mod ocr;
+pub mod parse;
+pub mod pathfinding;
+pub mod runner;
+pub mod search;
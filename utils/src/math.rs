@@ -0,0 +1,95 @@
+/// Always non-negative; gcd(0, 0) is 0.
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Always non-negative; lcm with 0 is 0.
+pub fn lcm(a: i128, b: i128) -> i128 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a / gcd(a, b) * b).abs()
+}
+
+pub fn lcm_all<I>(values: I) -> i128
+where
+    I: IntoIterator<Item = i128>,
+{
+    values.into_iter().fold(1, lcm)
+}
+
+/// Result is in 0..m, even for negative `a`.
+pub fn modulo(a: i128, m: i128) -> i128 {
+    a.rem_euclid(m)
+}
+
+// (a + b) mod m for a, b already in 0..m, without overflowing
+fn mod_add(a: i128, b: i128, m: i128) -> i128 {
+    if a >= m - b { a - (m - b) } else { a + b }
+}
+
+/// (a * b) mod m without overflowing when m is close to i128::MAX, by doubling.
+pub fn mod_mul(a: i128, b: i128, m: i128) -> i128 {
+    let (mut a, mut b) = (modulo(a, m), modulo(b, m));
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = mod_add(result, a, m);
+        }
+        a = mod_add(a, a, m);
+        b >>= 1;
+    }
+    result
+}
+
+/// base^exp mod m, for exp >= 0.
+pub fn mod_pow(base: i128, exp: i128, m: i128) -> i128 {
+    assert!(exp >= 0, "Negative exponent: use mod_inverse first");
+    if m == 1 {
+        return 0;
+    }
+    let mut result = 1;
+    let mut base = modulo(base, m);
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mod_mul(result, base, m);
+        }
+        base = mod_mul(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Returns (g, x, y) with a*x + b*y = g = gcd(a, b).
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_s, mut s) = (1, 0);
+    let (mut old_t, mut t) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+        (old_t, t) = (t, old_t - q * t);
+    }
+    if old_r < 0 {
+        return (-old_r, -old_s, -old_t);
+    }
+    (old_r, old_s, old_t)
+}
+
+/// x in 0..m with a*x = 1 (mod m), or None if a and m aren't coprime.
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (g, x, _) = extended_gcd(modulo(a, m), m);
+    if g != 1 {
+        return None;
+    }
+    Some(modulo(x, m))
+}
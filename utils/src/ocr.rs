@@ -0,0 +1,97 @@
+// The 4x6 block font used by the puzzles that draw letters (Y is 5 wide). Rows are
+// joined with '\n'.
+const GLYPHS: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+const GLYPH_HEIGHT: usize = 6;
+
+fn is_lit(c: char) -> bool {
+    c == '#'
+}
+
+// Columns of a glyph as bitmasks (bit n = row n), with blank edge columns removed so
+// glyphs compare equal however they were padded.
+fn trim_columns(columns: &[u8]) -> &[u8] {
+    let start = columns
+        .iter()
+        .position(|c| *c != 0)
+        .unwrap_or(columns.len());
+    let end = columns
+        .iter()
+        .rposition(|c| *c != 0)
+        .map_or(start, |i| i + 1);
+    &columns[start..end]
+}
+
+fn glyph_columns(glyph: &str) -> Vec<u8> {
+    let rows: Vec<&str> = glyph.lines().collect();
+    let width = rows[0].len();
+    (0..width)
+        .map(|x| {
+            rows.iter()
+                .enumerate()
+                .filter(|(_, row)| is_lit(row.as_bytes()[x] as char))
+                .fold(0, |mask, (y, _)| mask | 1 << y)
+        })
+        .collect()
+}
+
+fn lookup(columns: &[u8]) -> char {
+    for (letter, glyph) in GLYPHS {
+        if trim_columns(&glyph_columns(glyph)) == columns {
+            return letter;
+        }
+    }
+    '?'
+}
+
+/// Reads the letters drawn with '#' on a canvas. Blank rows and columns around the text
+/// are ignored and letters are split on blank columns; anything that isn't a known
+/// glyph comes back as '?'.
+pub fn read_letters(canvas: &[Vec<char>]) -> String {
+    let lit_rows: Vec<&Vec<char>> = canvas
+        .iter()
+        .skip_while(|row| !row.iter().any(|c| is_lit(*c)))
+        .collect();
+    let Some(n_rows) = lit_rows
+        .iter()
+        .rposition(|row| row.iter().any(|c| is_lit(*c)))
+    else {
+        return String::new();
+    };
+    let rows = &lit_rows[..=n_rows.min(GLYPH_HEIGHT - 1)];
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let columns: Vec<u8> = (0..width)
+        .map(|x| {
+            rows.iter()
+                .enumerate()
+                .filter(|(_, row)| row.get(x).is_some_and(|c| is_lit(*c)))
+                .fold(0, |mask, (y, _)| mask | 1 << y)
+        })
+        .collect();
+
+    columns
+        .split(|col| *col == 0)
+        .filter(|glyph| !glyph.is_empty())
+        .map(lookup)
+        .collect()
+}
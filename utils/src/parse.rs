@@ -0,0 +1,256 @@
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    ops::Deref,
+    path::Path,
+    str::FromStr,
+};
+
+use crate::error::AocError;
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io {
+        path: String,
+        error: io::Error,
+    },
+    /// `line` counts from 1
+    Value {
+        line: usize,
+        text: String,
+    },
+    /// A value of an Intcode program; `column` counts from 1 too
+    Token {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    Ragged {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    Empty,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io { path, error } => write!(f, "failed to read {path}: {error}"),
+            ParseError::Value { line, text } => write!(f, "line {line}: can't parse {text:?}"),
+            ParseError::Token { line, column, text } => {
+                write!(f, "line {line}, column {column}: can't parse {text:?}")
+            }
+            ParseError::Ragged {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: grid row is {found} wide, expected {expected}"
+            ),
+            ParseError::Empty => write!(f, "input is empty"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// The file at `path`, or stdin if the path is `-`, so generated input can be piped in
+pub fn read_to_string(path: impl AsRef<Path>) -> Result<String, ParseError> {
+    let path = path.as_ref();
+    if path == Path::new("-") {
+        return read_from(io::stdin().lock(), "stdin");
+    }
+    fs::read_to_string(path).map_err(|error| ParseError::Io {
+        path: path.display().to_string(),
+        error,
+    })
+}
+
+/// Everything `reader` has. `name` says where it came from in errors.
+pub fn read_from(mut reader: impl Read, name: &str) -> Result<String, ParseError> {
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|error| ParseError::Io {
+            path: name.to_string(),
+            error,
+        })?;
+    Ok(text)
+}
+
+/// A comma separated Intcode program. It may be split over lines, with or without a
+/// comma at the end of each, and `#` or `;` starts a comment running to the end of the
+/// line. Whitespace around values is ignored.
+pub fn parse_intcode(text: &str) -> Result<Vec<i64>, ParseError> {
+    let mut program: Vec<i64> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = match line.find(['#', ';']) {
+            Some(start) => &line[..start],
+            None => line,
+        };
+        let values: Vec<&str> = line.split(',').collect();
+        let mut column = 1;
+        for (j, value) in values.iter().enumerate() {
+            let start = column + value.len() - value.trim_start().len();
+            column += value.len() + 1;
+            let value = value.trim();
+            // Nothing after the last comma of a line, or on a blank line
+            if value.is_empty() && j == values.len() - 1 {
+                continue;
+            }
+            program.push(value.parse().map_err(|_| ParseError::Token {
+                line: i + 1,
+                column: start,
+                text: value.to_string(),
+            })?);
+        }
+    }
+    if program.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    Ok(program)
+}
+
+/// An Intcode program as written in a puzzle input, parsed by `parse_intcode`. It
+/// derefs to its values, so it can be handed straight to a `Cpu`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Program(Vec<i64>);
+
+impl Program {
+    /// The program in the file at `path`, or on stdin if the path is `-`
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, ParseError> {
+        read_to_string(path)?.parse()
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, ParseError> {
+        read_from(reader, "program")?.parse()
+    }
+
+    pub fn into_vec(self) -> Vec<i64> {
+        self.0
+    }
+}
+
+impl FromStr for Program {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, ParseError> {
+        parse_intcode(text).map(Program)
+    }
+}
+
+impl Deref for Program {
+    type Target = [i64];
+
+    fn deref(&self) -> &[i64] {
+        &self.0
+    }
+}
+
+impl From<Program> for Vec<i64> {
+    fn from(program: Program) -> Self {
+        program.0
+    }
+}
+
+pub fn read_intcode(path: impl AsRef<Path>) -> Result<Vec<i64>, ParseError> {
+    Program::read(path).map(Program::into_vec)
+}
+
+/// Changes to make to a program once it is loaded, one `addr=value` per line. Comments
+/// and blank lines are allowed as in a program.
+pub fn parse_patches(text: &str) -> Result<Vec<(usize, i64)>, ParseError> {
+    let mut patches = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = match line.find(['#', ';']) {
+            Some(start) => &line[..start],
+            None => line,
+        }
+        .trim();
+        if line.is_empty() {
+            continue;
+        }
+        let patch = line.split_once('=').and_then(|(addr, value)| {
+            Some((addr.trim().parse().ok()?, value.trim().parse().ok()?))
+        });
+        patches.push(patch.ok_or_else(|| ParseError::Value {
+            line: i + 1,
+            text: line.to_string(),
+        })?);
+    }
+    Ok(patches)
+}
+
+pub fn read_patches(path: impl AsRef<Path>) -> Result<Vec<(usize, i64)>, ParseError> {
+    parse_patches(&read_to_string(path)?)
+}
+
+/// Writes each patch into `program`, growing it with zeros for any past its end.
+pub fn apply_patches(program: &mut Vec<i64>, patches: &[(usize, i64)]) {
+    for &(addr, value) in patches {
+        if addr >= program.len() {
+            program.resize(addr + 1, 0);
+        }
+        program[addr] = value;
+    }
+}
+
+/// Applies the patch file named after `--patch` in `args`, if there is one, so a day
+/// can run a changed program for experiments.
+pub fn patch_from_args(args: &[String], program: &mut Vec<i64>) -> Result<(), AocError> {
+    let Some(i) = args.iter().position(|arg| arg == "--patch") else {
+        return Ok(());
+    };
+    let path = args
+        .get(i + 1)
+        .ok_or_else(|| AocError::Usage(String::from("--patch needs a file")))?;
+    apply_patches(program, &read_patches(path)?);
+    Ok(())
+}
+
+/// One value per line; blank lines are skipped.
+pub fn parse_lines<T: FromStr>(text: &str) -> Result<Vec<T>, ParseError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.trim().parse().map_err(|_| ParseError::Value {
+                line: i + 1,
+                text: line.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A rectangular grid of chars, indexed `[y][x]`. Trailing blank lines are ignored, but
+/// every other row has to be as wide as the first.
+pub fn parse_grid(text: &str) -> Result<Vec<Vec<char>>, ParseError> {
+    let grid: Vec<Vec<char>> = text
+        .trim_end_matches(['\n', '\r'])
+        .lines()
+        .map(|line| line.chars().collect())
+        .collect();
+    let Some(width) = grid.first().map(|row| row.len()) else {
+        return Err(ParseError::Empty);
+    };
+
+    for (i, row) in grid.iter().enumerate() {
+        if row.len() != width {
+            return Err(ParseError::Ragged {
+                line: i + 1,
+                expected: width,
+                found: row.len(),
+            });
+        }
+    }
+    Ok(grid)
+}
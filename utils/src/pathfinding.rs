@@ -0,0 +1,125 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+// Walks the `came_from` links back from `end` to the start of the search.
+fn build_path<N>(came_from: &HashMap<N, N>, end: N) -> Vec<N>
+where
+    N: Eq + Hash + Clone,
+{
+    let mut path = vec![end];
+    while let Some(prev) = came_from.get(path.last().expect("Path is never empty")) {
+        path.push(prev.clone());
+    }
+    path.reverse();
+    path
+}
+
+/// Shortest path by number of steps. Returns every node on the path from `start` to
+/// the first node satisfying `is_goal`, both ends included.
+pub fn bfs<N, FN, IN, FG>(start: N, mut neighbours: FN, mut is_goal: FG) -> Option<Vec<N>>
+where
+    N: Eq + Hash + Clone,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = N>,
+    FG: FnMut(&N) -> bool,
+{
+    let mut came_from: HashMap<N, N> = HashMap::new();
+    let mut seen: HashSet<N> = HashSet::new();
+    let mut queue: VecDeque<N> = VecDeque::new();
+
+    seen.insert(start.clone());
+    queue.push_back(start);
+    while let Some(node) = queue.pop_front() {
+        if is_goal(&node) {
+            return Some(build_path(&came_from, node));
+        }
+        for next in neighbours(&node) {
+            if seen.insert(next.clone()) {
+                came_from.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Number of steps from `start` to every reachable node.
+pub fn bfs_distances<N, FN, IN>(start: N, mut neighbours: FN) -> HashMap<N, usize>
+where
+    N: Eq + Hash + Clone,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = N>,
+{
+    let mut dist: HashMap<N, usize> = HashMap::new();
+    let mut queue: VecDeque<N> = VecDeque::new();
+
+    dist.insert(start.clone(), 0);
+    queue.push_back(start);
+    while let Some(node) = queue.pop_front() {
+        let d = dist[&node];
+        for next in neighbours(&node) {
+            if !dist.contains_key(&next) {
+                dist.insert(next.clone(), d + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    dist
+}
+
+/// Cheapest path where `neighbours` yields (node, cost) pairs. Returns the path and its
+/// total cost.
+pub fn dijkstra<N, FN, IN, FG>(start: N, neighbours: FN, is_goal: FG) -> Option<(Vec<N>, u64)>
+where
+    N: Eq + Hash + Clone + Ord,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, u64)>,
+    FG: FnMut(&N) -> bool,
+{
+    astar(start, neighbours, |_| 0, is_goal)
+}
+
+/// Dijkstra guided by `heuristic`, which must never overestimate the remaining cost.
+pub fn astar<N, FN, IN, FH, FG>(
+    start: N,
+    mut neighbours: FN,
+    mut heuristic: FH,
+    mut is_goal: FG,
+) -> Option<(Vec<N>, u64)>
+where
+    N: Eq + Hash + Clone + Ord,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, u64)>,
+    FH: FnMut(&N) -> u64,
+    FG: FnMut(&N) -> bool,
+{
+    let mut came_from: HashMap<N, N> = HashMap::new();
+    let mut cost: HashMap<N, u64> = HashMap::new();
+    let mut heap: BinaryHeap<Reverse<(u64, u64, N)>> = BinaryHeap::new();
+
+    cost.insert(start.clone(), 0);
+    heap.push(Reverse((heuristic(&start), 0, start)));
+    while let Some(Reverse((_, g, node))) = heap.pop() {
+        if g > cost[&node] {
+            continue;
+        }
+        if is_goal(&node) {
+            return Some((build_path(&came_from, node), g));
+        }
+        for (next, step) in neighbours(&node) {
+            let next_cost = g + step;
+            if cost.get(&next).is_none_or(|c| next_cost < *c) {
+                cost.insert(next.clone(), next_cost);
+                came_from.insert(next.clone(), node.clone());
+                heap.push(Reverse((next_cost + heuristic(&next), next_cost, next)));
+            }
+        }
+    }
+
+    None
+}
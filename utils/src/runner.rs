@@ -0,0 +1,13 @@
+use std::env;
+
+/// Set by the `aoc2019` runner to ask a solver how many Intcode instructions it ran
+pub const INSTRUCTIONS_VAR: &str = "AOC_REPORT_INSTRUCTIONS";
+
+/// Prints `instructions: <count>` for the `aoc2019` runner when it has asked for it
+/// with `AOC_REPORT_INSTRUCTIONS`, and nothing otherwise. Solvers call this last, with
+/// the instructions retired by every machine they ran.
+pub fn report_instructions(count: u64) {
+    if env::var_os(INSTRUCTIONS_VAR).is_some() {
+        println!("instructions: {count}");
+    }
+}
@@ -0,0 +1,65 @@
+use std::convert::Infallible;
+
+/// Returns the first value in `lo..hi` for which `pred` is false, or `hi` if there is
+/// none. `pred` must be true for some prefix of the range and false for the rest.
+pub fn partition_point_i64<F>(lo: i64, hi: i64, mut pred: F) -> i64
+where
+    F: FnMut(i64) -> bool,
+{
+    let Ok(point) = try_partition_point_i64(lo, hi, |x| Ok::<_, Infallible>(pred(x)));
+    point
+}
+
+/// Same as `partition_point_i64`, for a `pred` that can fail. The search stops at the
+/// first error and returns it.
+pub fn try_partition_point_i64<F, E>(lo: i64, hi: i64, mut pred: F) -> Result<i64, E>
+where
+    F: FnMut(i64) -> Result<bool, E>,
+{
+    let mut lo = lo;
+    let mut hi = hi;
+
+    while lo < hi {
+        // hi - lo can exceed i64::MAX, so halve the distance as unsigned
+        let half = (hi.wrapping_sub(lo) as u64) / 2;
+        let mid = lo.wrapping_add(half as i64);
+        if pred(mid)? {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    Ok(lo)
+}
+
+/// Same as `partition_point_i64` but with no known upper bound: probes lo + 1, lo + 2,
+/// lo + 4, ... until `pred` fails, then binary searches the last gap.
+pub fn exponential_partition_point_i64<F>(lo: i64, mut pred: F) -> i64
+where
+    F: FnMut(i64) -> bool,
+{
+    let Ok(point) = try_exponential_partition_point_i64(lo, |x| Ok::<_, Infallible>(pred(x)));
+    point
+}
+
+/// Same as `exponential_partition_point_i64`, for a `pred` that can fail.
+pub fn try_exponential_partition_point_i64<F, E>(lo: i64, mut pred: F) -> Result<i64, E>
+where
+    F: FnMut(i64) -> Result<bool, E>,
+{
+    if !pred(lo)? {
+        return Ok(lo);
+    }
+
+    let mut bound = lo;
+    let mut step: i64 = 1;
+    loop {
+        let next = bound.checked_add(step).unwrap_or(i64::MAX);
+        if next == i64::MAX || !pred(next)? {
+            return try_partition_point_i64(bound + 1, next, pred);
+        }
+        bound = next;
+        step = step.saturating_mul(2);
+    }
+}
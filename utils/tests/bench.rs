@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use utils::bench::{regression_pct, summarise};
+
+fn ms(values: &[u64]) -> Vec<Duration> {
+    values.iter().map(|v| Duration::from_millis(*v)).collect()
+}
+
+#[test]
+fn median_and_percentiles() {
+    let stats = summarise(ms(&[5, 1, 4, 2, 3]));
+    assert_eq!(stats.median, Duration::from_millis(3));
+    assert_eq!(stats.mean, Duration::from_millis(3));
+    assert_eq!(stats.p95, Duration::from_millis(5));
+    assert!(stats.outliers.is_empty());
+}
+
+#[test]
+fn flags_warmup_outlier() {
+    let stats = summarise(ms(&[90, 10, 11, 10, 12, 10, 11]));
+    assert_eq!(stats.outliers, vec![0]);
+    assert!(stats.warmup_suspected());
+}
+
+#[test]
+fn constant_samples_have_no_spread() {
+    let stats = summarise(ms(&[7, 7, 7]));
+    assert_eq!(stats.stddev, Duration::ZERO);
+    assert!(stats.outliers.is_empty());
+}
+
+#[test]
+fn regression_against_baseline() {
+    let stats = summarise(ms(&[110, 110, 110]));
+    let change = regression_pct(&stats, Duration::from_millis(100));
+    assert!((change - 10.0).abs() < 1e-9);
+}
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+use utils::combinatorics::{combinations, gray_code, permutations, subsets};
+
+#[test]
+fn permutations_in_order() {
+    let perms: Vec<Vec<i64>> = permutations(&[1, 2, 3]).collect();
+    assert_eq!(
+        perms,
+        vec![
+            vec![1, 2, 3],
+            vec![1, 3, 2],
+            vec![2, 1, 3],
+            vec![2, 3, 1],
+            vec![3, 1, 2],
+            vec![3, 2, 1],
+        ]
+    );
+}
+
+#[test]
+fn permutations_count_and_edges() {
+    let perms: HashSet<Vec<i64>> = permutations(&[5, 6, 7, 8, 9]).collect();
+    assert_eq!(perms.len(), 120);
+    assert_eq!(permutations::<i64>(&[]).count(), 1);
+    assert_eq!(permutations(&['x']).collect::<Vec<_>>(), vec![vec!['x']]);
+}
+
+#[test]
+fn combinations_choose_k() {
+    let combos: Vec<Vec<char>> = combinations(&['a', 'b', 'c', 'd'], 2).collect();
+    assert_eq!(combos.len(), 6);
+    assert_eq!(combos[0], vec!['a', 'b']);
+    assert_eq!(combos[5], vec!['c', 'd']);
+    assert_eq!(combinations(&[1, 2, 3], 0).count(), 1);
+    assert_eq!(combinations(&[1, 2, 3], 3).count(), 1);
+    assert_eq!(combinations(&[1, 2, 3], 4).count(), 0);
+}
+
+#[test]
+fn power_set() {
+    let sets: Vec<Vec<i64>> = subsets(&[1, 2, 3]).collect();
+    assert_eq!(sets.len(), 8);
+    assert_eq!(sets[0], Vec::<i64>::new());
+    assert_eq!(sets[7], vec![1, 2, 3]);
+}
+
+#[test]
+fn gray_code_flips_one_bit() {
+    let masks: Vec<u64> = gray_code(4).collect();
+    assert_eq!(masks.len(), 16);
+    assert_eq!(masks.iter().collect::<HashSet<_>>().len(), 16);
+    for pair in masks.windows(2) {
+        assert_eq!((pair[0] ^ pair[1]).count_ones(), 1);
+    }
+}
@@ -0,0 +1,35 @@
+use std::io;
+
+use utils::error::AocError;
+use utils::parse::ParseError;
+
+#[test]
+fn exit_codes_by_kind() {
+    assert_eq!(AocError::Usage(String::from("no input")).exit_code(), 2);
+    assert_eq!(AocError::from(ParseError::Empty).exit_code(), 3);
+    assert_eq!(AocError::solver("nothing found").exit_code(), 5);
+    let err = io::Error::other("disk full");
+    assert_eq!(AocError::io("failed to write", err).exit_code(), 6);
+}
+
+#[test]
+fn report_lists_causes() {
+    let err = AocError::io("failed to write report.txt", io::Error::other("disk full"));
+    let report = err.report();
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with("failed to write report.txt"));
+    assert!(lines[1].ends_with("disk full"));
+}
+
+#[test]
+fn report_skips_repeated_cause() {
+    // The parse error's message already includes the io error
+    let err = AocError::from(ParseError::Io {
+        path: String::from("input.txt"),
+        error: io::Error::other("no such file"),
+    });
+    let report = err.report();
+    assert_eq!(report.lines().count(), 2);
+    assert!(report.contains("failed to read input.txt: no such file"));
+}
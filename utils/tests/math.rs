@@ -0,0 +1,47 @@
+use utils::math::{extended_gcd, gcd, lcm, lcm_all, mod_inverse, mod_mul, mod_pow, modulo};
+
+#[test]
+fn gcd_and_lcm() {
+    assert_eq!(gcd(48, 18), 6);
+    assert_eq!(gcd(-48, 18), 6);
+    assert_eq!(gcd(0, 5), 5);
+    assert_eq!(gcd(0, 0), 0);
+    assert_eq!(lcm(4, 6), 12);
+    assert_eq!(lcm(-4, 6), 12);
+    assert_eq!(lcm(0, 6), 0);
+    // day 12 example periods
+    assert_eq!(lcm_all([18, 28, 44]), 2772);
+}
+
+#[test]
+fn modular_arithmetic() {
+    assert_eq!(modulo(-7, 5), 3);
+    assert_eq!(mod_pow(4, 13, 497), 445);
+    assert_eq!(mod_pow(123, 0, 7), 1);
+    assert_eq!(mod_pow(5, 3, 1), 0);
+    let big = 119_315_717_514_047;
+    assert_eq!(mod_mul(big - 1, big - 1, big), 1);
+    let huge = i128::MAX - 1;
+    assert_eq!(mod_mul(huge - 1, 2, huge), huge - 2);
+}
+
+#[test]
+fn inverses() {
+    assert_eq!(mod_inverse(3, 11), Some(4));
+    assert_eq!(mod_inverse(-3, 11), Some(7));
+    assert_eq!(mod_inverse(6, 9), None);
+    let deck = 119_315_717_514_047;
+    let inv = mod_inverse(2020, deck).unwrap();
+    assert_eq!(mod_mul(inv, 2020, deck), 1);
+    // Fermat: a^(p-2) is the inverse mod a prime
+    assert_eq!(mod_pow(2020, deck - 2, deck), inv);
+}
+
+#[test]
+fn bezout_identity() {
+    for (a, b) in [(240, 46), (-17, 5), (0, 9), (12, 0)] {
+        let (g, x, y) = extended_gcd(a, b);
+        assert_eq!(g, gcd(a, b));
+        assert_eq!(a * x + b * y, g);
+    }
+}
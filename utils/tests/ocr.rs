@@ -0,0 +1,53 @@
+use utils::ocr::read_letters;
+
+fn canvas(rows: &[&str]) -> Vec<Vec<char>> {
+    rows.iter().map(|row| row.chars().collect()).collect()
+}
+
+#[test]
+fn reads_word() {
+    let text = canvas(&[
+        "#..#.####.#....#.....##.",
+        "#..#.#....#....#....#..#",
+        "####.###..#....#....#..#",
+        "#..#.#....#....#....#..#",
+        "#..#.#....#....#....#..#",
+        "#..#.####.####.####..##.",
+    ]);
+    assert_eq!(read_letters(&text), "HELLO");
+}
+
+#[test]
+fn ignores_padding_and_blank_pixels() {
+    // Day 11 renders unpainted tiles as spaces and may leave margins around the text
+    let text = canvas(&[
+        "             ",
+        "   ###   ### ",
+        "   #  #   #  ",
+        "   #  #   #  ",
+        "   ###    #  ",
+        "   # #    #  ",
+        "   #  #  ### ",
+        "             ",
+    ]);
+    assert_eq!(read_letters(&text), "RI");
+}
+
+#[test]
+fn wide_y_and_unknown_glyphs() {
+    let text = canvas(&[
+        "#...#.#..",
+        "#...#.##.",
+        ".#.#..#.#",
+        "..#...#..",
+        "..#...#..",
+        "..#...#..",
+    ]);
+    assert_eq!(read_letters(&text), "Y?");
+}
+
+#[test]
+fn empty_canvas() {
+    assert_eq!(read_letters(&canvas(&["....", "...."])), "");
+    assert_eq!(read_letters(&[]), "");
+}
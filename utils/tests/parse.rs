@@ -0,0 +1,141 @@
+use utils::error::AocError;
+use utils::parse::{
+    ParseError, Program, apply_patches, parse_grid, parse_intcode, parse_lines, parse_patches,
+    patch_from_args, read_intcode,
+};
+
+#[test]
+fn intcode_programs() {
+    assert_eq!(parse_intcode("1,0,0,3,99\n").unwrap(), [1, 0, 0, 3, 99]);
+    assert_eq!(parse_intcode(" 104, -5 ,99").unwrap(), [104, -5, 99]);
+    // split over lines
+    assert_eq!(parse_intcode("1,2,\n3,4").unwrap(), [1, 2, 3, 4]);
+
+    assert!(matches!(parse_intcode("\n"), Err(ParseError::Empty)));
+    match parse_intcode("1,2,\n3, x") {
+        Err(ParseError::Token { line, column, text }) => {
+            assert_eq!((line, column, text.as_str()), (2, 4, "x"))
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    assert!(parse_intcode("1,,2").is_err());
+}
+
+#[test]
+fn hand_written_intcode() {
+    let text = "# doubles its input\n3,9,  ; read\n1002,9,2,9\n\n4,9,99,0,\n";
+    assert_eq!(
+        parse_intcode(text).unwrap(),
+        [3, 9, 1002, 9, 2, 9, 4, 9, 99, 0]
+    );
+    assert!(matches!(
+        parse_intcode("; nothing but comments\n"),
+        Err(ParseError::Empty)
+    ));
+    match parse_intcode("1,2 3") {
+        Err(ParseError::Token { column, text, .. }) => {
+            assert_eq!((column, text.as_str()), (3, "2 3"))
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn intcode_from_a_reader() {
+    let program = Program::from_reader("1,0,0,3,99\n".as_bytes()).unwrap();
+    assert_eq!(*program, [1, 0, 0, 3, 99]);
+
+    let err = Program::from_reader(&[0xff, b'1'][..]).unwrap_err();
+    assert!(matches!(err, ParseError::Io { .. }));
+}
+
+#[test]
+fn program_from_str() {
+    let program: Program = "104, 7,\n99 # halt\n".parse().unwrap();
+    assert_eq!(program.len(), 3);
+    assert_eq!(Vec::from(program.clone()), [104, 7, 99]);
+    assert_eq!(program.into_vec(), [104, 7, 99]);
+
+    assert!(matches!("".parse::<Program>(), Err(ParseError::Empty)));
+    assert!(matches!(
+        "1,x".parse::<Program>(),
+        Err(ParseError::Token { column: 3, .. })
+    ));
+}
+
+#[test]
+fn missing_file() {
+    let err = read_intcode("no/such/input.txt").unwrap_err();
+    assert!(matches!(err, ParseError::Io { .. }));
+    assert!(err.to_string().contains("no/such/input.txt"));
+}
+
+#[test]
+fn lines_of_values() {
+    let masses: Vec<u32> = parse_lines("12\n14\n\n1969\n").unwrap();
+    assert_eq!(masses, [12, 14, 1969]);
+
+    match parse_lines::<u32>("12\n-14\n") {
+        Err(ParseError::Value { line, text }) => assert_eq!((line, text.as_str()), (2, "-14")),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn grids() {
+    let grid = parse_grid(".#.\n##.\n\n").unwrap();
+    assert_eq!(grid, [vec!['.', '#', '.'], vec!['#', '#', '.']]);
+
+    match parse_grid(".#.\n##\n...") {
+        Err(ParseError::Ragged {
+            line,
+            expected,
+            found,
+        }) => assert_eq!((line, expected, found), (2, 3, 2)),
+        other => panic!("unexpected {other:?}"),
+    }
+    assert!(matches!(parse_grid(""), Err(ParseError::Empty)));
+}
+
+#[test]
+fn patches() {
+    let patches = parse_patches("# play for free\n0=2\n\n 10 = -1 ; far\n").unwrap();
+    assert_eq!(patches, [(0, 2), (10, -1)]);
+
+    let mut program = vec![1, 0, 0, 99];
+    apply_patches(&mut program, &patches);
+    assert_eq!(program, [2, 0, 0, 99, 0, 0, 0, 0, 0, 0, -1]);
+
+    match parse_patches("0=2\n3\n") {
+        Err(ParseError::Value { line, text }) => assert_eq!((line, text.as_str()), (2, "3")),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn patches_from_args() {
+    let args = |list: &[&str]| -> Vec<String> { list.iter().map(|arg| arg.to_string()).collect() };
+    let mut program = vec![1, 0, 0, 99];
+
+    patch_from_args(&args(&["day", "input.txt", "animate"]), &mut program).unwrap();
+    assert_eq!(program, [1, 0, 0, 99]);
+
+    let path = std::env::temp_dir().join(format!("utils-patch-{}.txt", std::process::id()));
+    std::fs::write(&path, "0=2\n").unwrap();
+    let path = path.to_string_lossy().into_owned();
+    let result = patch_from_args(&args(&["day", "input.txt", "--patch", &path]), &mut program);
+    std::fs::remove_file(&path).unwrap();
+    result.unwrap();
+    assert_eq!(program, [2, 0, 0, 99]);
+    assert!(matches!(
+        patch_from_args(&args(&["day", "input.txt", "--patch"]), &mut program),
+        Err(AocError::Usage(_))
+    ));
+    assert!(matches!(
+        patch_from_args(
+            &args(&["day", "input.txt", "--patch", "no/such/file"]),
+            &mut program
+        ),
+        Err(AocError::Input(_))
+    ));
+}
@@ -0,0 +1,68 @@
+use utils::pathfinding::{astar, bfs, bfs_distances, dijkstra};
+
+const MAZE: [&str; 5] = ["#######", "#S..#.#", "#.#.###", "#.#...#", "#####E#"];
+
+fn find(c: char) -> (i64, i64) {
+    for (y, row) in MAZE.iter().enumerate() {
+        if let Some(x) = row.find(c) {
+            return (x as i64, y as i64);
+        }
+    }
+    panic!("{c} not in maze");
+}
+
+fn open_neighbours(&(x, y): &(i64, i64)) -> Vec<(i64, i64)> {
+    [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)]
+        .into_iter()
+        .filter(|&(x, y)| {
+            MAZE.get(y as usize)
+                .and_then(|row| row.as_bytes().get(x as usize))
+                .is_some_and(|c| *c != b'#')
+        })
+        .collect()
+}
+
+#[test]
+fn bfs_shortest_path() {
+    let end = find('E');
+    let path = bfs(find('S'), open_neighbours, |pos| *pos == end).unwrap();
+    assert_eq!(path.first(), Some(&find('S')));
+    assert_eq!(path.last(), Some(&end));
+    assert_eq!(path.len() - 1, 7);
+}
+
+#[test]
+fn bfs_unreachable() {
+    assert_eq!(bfs(find('S'), open_neighbours, |pos| *pos == (5, 1)), None);
+}
+
+#[test]
+fn bfs_distances_covers_region() {
+    let dist = bfs_distances(find('S'), open_neighbours);
+    assert_eq!(dist[&find('S')], 0);
+    assert_eq!(dist[&find('E')], 7);
+    assert!(!dist.contains_key(&(5, 1)));
+}
+
+#[test]
+fn dijkstra_prefers_cheap_edges() {
+    // 0 -> 1 -> 3 costs 2, 0 -> 2 -> 3 costs 11, 0 -> 3 directly costs 5
+    let edges = |n: &u32| match n {
+        0 => vec![(1, 1), (2, 1), (3, 5)],
+        1 => vec![(3, 1)],
+        2 => vec![(3, 10)],
+        _ => vec![],
+    };
+    assert_eq!(dijkstra(0, edges, |n| *n == 3), Some((vec![0, 1, 3], 2)));
+    assert_eq!(dijkstra(3, edges, |n| *n == 0), None);
+}
+
+#[test]
+fn astar_matches_bfs() {
+    let end = find('E');
+    let manhattan = |&(x, y): &(i64, i64)| x.abs_diff(end.0) + y.abs_diff(end.1);
+    let weighted = |pos: &(i64, i64)| open_neighbours(pos).into_iter().map(|n| (n, 1));
+    let (path, cost) = astar(find('S'), weighted, manhattan, |pos| *pos == end).unwrap();
+    assert_eq!(cost, 7);
+    assert_eq!(path.len(), 8);
+}
@@ -0,0 +1,91 @@
+use utils::search::{
+    exponential_partition_point_i64, partition_point_i64, try_exponential_partition_point_i64,
+    try_partition_point_i64,
+};
+
+#[test]
+fn finds_first_false() {
+    assert_eq!(partition_point_i64(0, 100, |x| x < 37), 37);
+    assert_eq!(partition_point_i64(-50, 50, |x| x < -10), -10);
+}
+
+#[test]
+fn all_true_or_all_false() {
+    assert_eq!(partition_point_i64(0, 10, |_| true), 10);
+    assert_eq!(partition_point_i64(0, 10, |_| false), 0);
+}
+
+#[test]
+fn empty_range() {
+    assert_eq!(partition_point_i64(5, 5, |_| true), 5);
+    assert_eq!(partition_point_i64(5, 2, |_| true), 5);
+}
+
+#[test]
+fn full_i64_range_does_not_overflow() {
+    assert_eq!(partition_point_i64(i64::MIN, i64::MAX, |x| x < 0), 0);
+    assert_eq!(
+        partition_point_i64(i64::MIN, i64::MAX, |x| x < i64::MAX - 1),
+        i64::MAX - 1
+    );
+    assert_eq!(partition_point_i64(i64::MIN, i64::MAX, |_| false), i64::MIN);
+    assert_eq!(partition_point_i64(i64::MIN, i64::MAX, |_| true), i64::MAX);
+}
+
+#[test]
+fn matches_slice_partition_point() {
+    let values: Vec<i64> = (0..200).map(|x| x * 3 - 100).collect();
+    for target in -120..520 {
+        let expected = values.partition_point(|v| *v < target) as i64;
+        let found = partition_point_i64(0, values.len() as i64, |i| values[i as usize] < target);
+        assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn exponential_search() {
+    assert_eq!(
+        exponential_partition_point_i64(0, |x| x < 1_000_003),
+        1_000_003
+    );
+    assert_eq!(exponential_partition_point_i64(10, |x| x < 3), 10);
+    assert_eq!(exponential_partition_point_i64(-7, |x| x * x < 50), 8);
+    assert_eq!(exponential_partition_point_i64(0, |_| true), i64::MAX);
+}
+
+#[test]
+fn exponential_search_counts_probes() {
+    let mut probes = 0;
+    let found = exponential_partition_point_i64(0, |x| {
+        probes += 1;
+        x < 1 << 40
+    });
+    assert_eq!(found, 1 << 40);
+    assert!(probes < 100);
+}
+
+#[test]
+fn fallible_searches_stop_at_the_first_error() {
+    assert_eq!(
+        try_partition_point_i64(0, 100, |x| Ok::<_, ()>(x < 37)),
+        Ok(37)
+    );
+
+    let mut probes = 0;
+    let result = try_partition_point_i64(0, 100, |x| {
+        probes += 1;
+        if x < 40 { Ok(true) } else { Err(x) }
+    });
+    assert!(result.is_err());
+    assert_eq!(probes, 1);
+
+    assert_eq!(
+        try_exponential_partition_point_i64(0, |x| Ok::<_, ()>(x < 1000)),
+        Ok(1000)
+    );
+    // Probes 0, 1, 3, 7 and fails there
+    assert_eq!(
+        try_exponential_partition_point_i64(0, |x| if x < 5 { Ok(true) } else { Err(x) }),
+        Err(7)
+    );
+}